@@ -0,0 +1,48 @@
+package app.tauri.plugin.cache
+
+import org.json.JSONObject
+import java.io.File
+import java.security.MessageDigest
+
+// Maps cache keys to the names of the files holding their entries
+//
+// Keys made of file name safe characters are used as the file name directly, so files written by
+// earlier versions stay readable. Other keys (path separators, unicode, very long keys) map to a
+// short readable prefix plus a hash of the whole key; the '~' separator can't occur in a plain key,
+// so the two kinds of names never collide.
+internal object CacheFileNames {
+    private val SAFE_KEY = Regex("[A-Za-z0-9._-]+")
+    private val UNSAFE_CHAR = Regex("[^A-Za-z0-9._-]")
+    
+    // Get the file in dir holding the entry for a key
+    fun fileForKey(dir: File, key: String): File {
+        if (key.length <= 100 && key != "." && key != ".." && key.matches(SAFE_KEY)) {
+            return File(dir, key)
+        }
+        
+        val prefix = key.take(32).replace(UNSAFE_CHAR, "_")
+        val digest = MessageDigest.getInstance("SHA-256").digest(key.toByteArray(Charsets.UTF_8))
+        val hash = digest.take(16).joinToString("") { "%02x".format(it) }
+        return File(dir, "$prefix~$hash")
+    }
+    
+    // Get the key of the entry stored in a cache file, reversing fileForKey
+    fun keyForFile(file: File): String {
+        if (!file.name.contains('~')) {
+            return file.name
+        }
+        
+        return try {
+            JSONObject(file.readText()).optString("key", file.name)
+        } catch (e: Exception) {
+            file.name
+        }
+    }
+    
+    // Get a copy of a stored entry that records another key, for moving it to that key's file
+    fun withKey(entryText: String, key: String): String {
+        val entry = JSONObject(entryText)
+        entry.put("key", key)
+        return entry.toString()
+    }
+}
@@ -5,13 +5,16 @@ import android.util.Base64
 import app.tauri.annotation.Command
 import app.tauri.annotation.InvokeArg
 import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.JSArray
 import app.tauri.plugin.JSObject
 import app.tauri.plugin.Plugin
 import app.tauri.plugin.Invoke
 import org.json.JSONObject
+import org.json.JSONTokener
 import java.io.ByteArrayInputStream
 import java.io.ByteArrayOutputStream
 import java.io.File
+import java.util.concurrent.atomic.AtomicLong
 import java.util.zip.Deflater
 import java.util.zip.Inflater
 import org.tukaani.xz.XZInputStream
@@ -45,6 +48,11 @@ class GetRequest {
   lateinit var key: String
 }
 
+@InvokeArg
+class GetManyRequest {
+  var keys: List<String> = emptyList()
+}
+
 @InvokeArg
 class HasRequest {
   lateinit var key: String
@@ -55,6 +63,58 @@ class RemoveRequest {
   lateinit var key: String
 }
 
+@InvokeArg
+class RenameRequest {
+  lateinit var from: String
+  lateinit var to: String
+  var overwrite: Boolean = false
+}
+
+@InvokeArg
+class RateLimitRequest {
+  lateinit var key: String
+  var max: Long = 0
+  var windowSecs: Long = 0
+}
+
+@InvokeArg
+class TtlRequest {
+  lateinit var key: String
+}
+
+@InvokeArg
+class SetExpiryRequest {
+  lateinit var key: String
+  var ttl: Long? = null
+}
+
+@InvokeArg
+class ClearPrefixRequest {
+  lateinit var prefix: String
+}
+
+@InvokeArg
+class ClearOlderThanRequest {
+  var ageSecs: Long = 0
+}
+
+@InvokeArg
+class StatsPrefixRequest {
+  lateinit var prefix: String
+}
+
+@InvokeArg
+class KeysRequest {
+  var prefix: String? = null
+}
+
+@InvokeArg
+class KeysPageRequest {
+  var offset: Int = 0
+  var limit: Int = 0
+  var includeExpired: Boolean = false
+}
+
 @InvokeArg
 class CompressionConfig {
   var enabled: Boolean = true
@@ -70,6 +130,14 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
     private var compressionLevel = 6
     private var compressionThreshold = 1024 // 1KB
     private var compressionMethod = "zlib"
+    
+    // Cumulative sizes of the values that went through compression
+    private val compressedBytesIn = AtomicLong(0)
+    private val compressedBytesOut = AtomicLong(0)
+    
+    // Increases on every write; based on the current time so it keeps increasing across restarts
+    private val currentChangeToken = AtomicLong(System.currentTimeMillis())
+    @Volatile private var lastModified = 0L
 
     init {
         cacheDir = File(activity.cacheDir, "tauri_cache")
@@ -128,27 +196,7 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
             val request = invoke.parseArgs(SetRequest::class.java)
             android.util.Log.i("CachePlugin", "Setting cache item with key: ${request.key}")
             
-            // Use the data - use request.value instead of invoke.data
-            val valueString = if (request.value != null) {
-                // Check if the value is JSON
-                try {
-                    // If it's a JSON object
-                    if (request.value.toString().trim().startsWith("{") || 
-                        request.value.toString().trim().startsWith("[")) {
-                        // Save in JSON format
-                        request.value.toString()
-                    } else {
-                        // Convert simple string values to JSON format
-                        "\"" + request.value.toString().replace("\"", "\\\"") + "\""
-                    }
-                } catch (e: Exception) {
-                    // In case of any error, treat as simple string
-                    "\"" + request.value.toString().replace("\"", "\\\"") + "\""
-                }
-            } else {
-                // Empty JSON object for null values
-                "null"
-            }
+            val valueString = toValueString(request.value)
             
             android.util.Log.i("CachePlugin", "Value to save: $valueString")
             
@@ -164,34 +212,7 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
                 null
             }
             
-            // Create cache entry
-            val entry = JSONObject()
-            
-            // Compress data (if needed)
-            if (shouldCompress && valueString.length > compressionThreshold) {
-                android.util.Log.i("CachePlugin", "Compressing data (${valueString.length} bytes) with $compressionMethodToUse")
-                
-                // Compress the data
-                val compressed = compressData(valueString.toByteArray(), compressionMethodToUse)
-                
-                // Encode with Base64
-                val encodedString = Base64.encodeToString(compressed, Base64.NO_WRAP)
-                
-                entry.put("value", encodedString)
-                entry.put("is_compressed", true)
-            } else {
-                entry.put("value", valueString)
-                entry.put("is_compressed", false)
-            }
-            
-            // Add expiration time - explicitly specify Long type to resolve type ambiguity
-            if (expiresAt != null) {
-                entry.put("expires_at", expiresAt.toLong())
-            }
-            
-            // Save to file
-            val file = File(cacheDir, request.key)
-            file.writeText(entry.toString())
+            val file = writeEntry(request.key, valueString, expiresAt, shouldCompress, compressionMethodToUse)
             
             android.util.Log.i("CachePlugin", "Cache item saved to ${file.absolutePath}")
             
@@ -203,106 +224,76 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
         }
     }
 
+    @Command
+    fun loadFromJson(invoke: Invoke) {
+        try {
+            val args = invoke.getArgs()
+            val map = args.getJSONObject("map")
+            val ttl = if (args.isNull("defaultTtl")) null else args.getLong("defaultTtl")
+            val skipExisting = args.optBoolean("skipExisting", false)
+            android.util.Log.i("CachePlugin", "Loading ${map.length()} cache items from JSON")
+            
+            val now = System.currentTimeMillis()
+            val expiresAt = if (ttl != null) now + (ttl * 1000) else null
+            var loaded = 0
+            
+            for (key in map.keys()) {
+                val existing = fileForKey(key)
+                if (skipExisting && existing.exists() && !isExpired(existing, now)) {
+                    continue
+                }
+                
+                writeEntry(key, toValueString(map.get(key)), expiresAt, defaultCompression, compressionMethod)
+                loaded++
+            }
+            
+            android.util.Log.i("CachePlugin", "Loaded $loaded cache items")
+            invoke.resolveObject(loaded)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Load from JSON error: ${e.message}")
+            invoke.reject("Failed to load items: ${e.message}")
+        }
+    }
+
     @Command
     fun get(invoke: Invoke) {
         try {
             val request = invoke.parseArgs(GetRequest::class.java)
             android.util.Log.i("CachePlugin", "Getting cache item with key: ${request.key}")
             
-            val file = File(cacheDir, request.key)
-            
-            if (!file.exists()) {
-                android.util.Log.i("CachePlugin", "Cache item not found: ${request.key}")
+            val value = readItemValue(request.key)
+            if (value == null) {
                 invoke.resolve(null)
                 return
             }
             
-            // Read from file
-            val entryJson = JSONObject(file.readText())
-            
-            // Check expiration date
-            if (entryJson.has("expires_at")) {
-                val expiresAt = entryJson.getLong("expires_at")
-                
-                if (System.currentTimeMillis() > expiresAt) {
-                    android.util.Log.i("CachePlugin", "Cache item expired: ${request.key}")
-                    file.delete()
-                    invoke.resolve(null)
-                    return
-                }
-            }
-            
-            // Check if compressed
-            val isCompressed = entryJson.optBoolean("is_compressed", false)
-            val value = entryJson.getString("value")
-            
-            if (isCompressed) {
-                android.util.Log.i("CachePlugin", "Decompressing cache item: ${request.key}")
-                
-                // Decode from Base64
-                val compressedData = Base64.decode(value, Base64.NO_WRAP)
-                
-                // Decompress the data
-                val decompressedBytes = decompressData(compressedData)
-                val decompressedString = String(decompressedBytes)
-                
-                // Convert content to JSObject - handle JSON parsing errors
-                try {
-                    // First check JSON format
-                    val trimmedValue = decompressedString.trim()
-                    if (trimmedValue.startsWith("{") && trimmedValue.endsWith("}") ||
-                        trimmedValue.startsWith("[") && trimmedValue.endsWith("]")) {
-                        // JSON object or array
-                        val result = JSObject(decompressedString)
-                        invoke.resolve(result)
-                    } else if (trimmedValue.startsWith("\"") && trimmedValue.endsWith("\"")) {
-                        // String value (in quotes)
-                        val stringValue = trimmedValue.substring(1, trimmedValue.length - 1)
-                        val result = JSObject()
-                        result.put("value", stringValue)
-                        invoke.resolve(result)
-                    } else {
-                        // Primitive value
-                        val result = JSObject()
-                        result.put("value", decompressedString)
-                        invoke.resolve(result)
-                    }
-                } catch (e: Exception) {
-                    // JSON parse error - return as simple string
-                    android.util.Log.e("CachePlugin", "JSON parsing error, returning as string: ${e.message}")
+            // Convert content to JSObject - handle JSON parsing errors
+            try {
+                // First check JSON format
+                val trimmedValue = value.trim()
+                if (trimmedValue.startsWith("{") && trimmedValue.endsWith("}") ||
+                    trimmedValue.startsWith("[") && trimmedValue.endsWith("]")) {
+                    // JSON object or array
+                    val result = JSObject(value)
+                    invoke.resolve(result)
+                } else if (trimmedValue.startsWith("\"") && trimmedValue.endsWith("\"")) {
+                    // String value (in quotes)
+                    val stringValue = trimmedValue.substring(1, trimmedValue.length - 1)
                     val result = JSObject()
-                    result.put("value", decompressedString)
+                    result.put("value", stringValue)
                     invoke.resolve(result)
-                }
-            } else {
-                // Uncompressed data - handle JSON parsing errors
-                try {
-                    // First check JSON format
-                    val trimmedValue = value.trim()
-                    if (trimmedValue.startsWith("{") && trimmedValue.endsWith("}") ||
-                        trimmedValue.startsWith("[") && trimmedValue.endsWith("]")) {
-                        // JSON object or array
-                        val result = JSObject(value)
-                        invoke.resolve(result)
-                    } else if (trimmedValue.startsWith("\"") && trimmedValue.endsWith("\"")) {
-                        // String value (in quotes)
-                        val stringValue = trimmedValue.substring(1, trimmedValue.length - 1)
-                        val result = JSObject()
-                        result.put("value", stringValue)
-                        invoke.resolve(result)
-                    } else {
-                        // Primitive value
-                        val result = JSObject()
-                        result.put("value", value)
-                        invoke.resolve(result)
-                    }
-                } catch (e: Exception) {
-                    // JSON parse error - return as simple string
-                    android.util.Log.e("CachePlugin", "JSON parsing error, returning as string: ${e.message}")
+                } else {
+                    // Primitive value
                     val result = JSObject()
                     result.put("value", value)
                     invoke.resolve(result)
                 }
+            } catch (e: Exception) {
+                // JSON parse error - return as simple string
+                android.util.Log.e("CachePlugin", "JSON parsing error, returning as string: ${e.message}")
+                val result = JSObject()
+                result.put("value", value)
+                invoke.resolve(result)
             }
         } catch (e: Exception) {
             android.util.Log.e("CachePlugin", "Get error: ${e.message}")
@@ -311,13 +302,79 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
         }
     }
 
+    @Command
+    fun getMany(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(GetManyRequest::class.java)
+            android.util.Log.i("CachePlugin", "Getting ${request.keys.size} cache items")
+            
+            // Missing and expired items are reported as null rather than left out
+            val result = JSObject()
+            for (key in request.keys) {
+                val value = readItemValue(key)
+                val parsed = if (value == null) {
+                    JSONObject.NULL
+                } else {
+                    try {
+                        JSONTokener(value).nextValue()
+                    } catch (e: Exception) {
+                        value
+                    }
+                }
+                result.put(key, parsed)
+            }
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Get many error: ${e.message}")
+            invoke.reject("Failed to get items: ${e.message}")
+        }
+    }
+
+    // Reads the stored value of a live item as JSON text, deleting the item if it has expired
+    private fun readItemValue(key: String): String? {
+        val file = fileForKey(key)
+        
+        if (!file.exists()) {
+            android.util.Log.i("CachePlugin", "Cache item not found: $key")
+            return null
+        }
+        
+        // Read from file
+        val entryJson = JSONObject(file.readText())
+        
+        // Check expiration date
+        if (entryJson.has("expires_at")) {
+            val expiresAt = entryJson.getLong("expires_at")
+            
+            if (System.currentTimeMillis() > expiresAt) {
+                android.util.Log.i("CachePlugin", "Cache item expired: $key")
+                file.delete()
+                return null
+            }
+        }
+        
+        // Check if compressed
+        val isCompressed = entryJson.optBoolean("is_compressed", false)
+        val value = entryJson.getString("value")
+        
+        if (!isCompressed) {
+            return value
+        }
+        
+        android.util.Log.i("CachePlugin", "Decompressing cache item: $key")
+        
+        // Decode from Base64 and decompress the data
+        val compressedData = Base64.decode(value, Base64.NO_WRAP)
+        return String(decompressData(compressedData))
+    }
+
     @Command
     fun has(invoke: Invoke) {
         try {
             val request = invoke.parseArgs(HasRequest::class.java)
             android.util.Log.i("CachePlugin", "Checking cache item with key: ${request.key}")
             
-            val file = File(cacheDir, request.key)
+            val file = fileForKey(request.key)
             
             if (!file.exists()) {
                 val result = JSObject()
@@ -366,10 +423,11 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
             val request = invoke.parseArgs(RemoveRequest::class.java)
             android.util.Log.i("CachePlugin", "Removing cache item with key: ${request.key}")
             
-            val file = File(cacheDir, request.key)
+            val file = fileForKey(request.key)
             
             if (file.exists()) {
                 file.delete()
+                recordWrite()
                 android.util.Log.i("CachePlugin", "Cache item removed: ${request.key}")
             }
             
@@ -380,6 +438,128 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
         }
     }
 
+    @Command
+    fun rename(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(RenameRequest::class.java)
+            android.util.Log.i("CachePlugin", "Renaming cache item ${request.from} to ${request.to}")
+            
+            val fromFile = fileForKey(request.from)
+            val toFile = fileForKey(request.to)
+            val now = System.currentTimeMillis()
+            val result = JSObject()
+            
+            if (!fromFile.exists() || isExpired(fromFile, now)) {
+                result.put("value", false)
+            } else if (fromFile == toFile) {
+                // Already under that key; moving it would delete it
+                result.put("value", true)
+            } else if (!request.overwrite && toFile.exists() && !isExpired(toFile, now)) {
+                invoke.reject("Key already exists: ${request.to}")
+                return
+            } else {
+                // The entry records its key, so write a copy under the new key first. rename(2) then
+                // replaces the target atomically, so readers never see a partial item
+                val tempFile = File.createTempFile("rename", ".tmp", cacheDir.parentFile)
+                tempFile.writeText(CacheFileNames.withKey(fromFile.readText(), request.to))
+                
+                val renamed = tempFile.renameTo(toFile)
+                if (renamed) {
+                    fromFile.delete()
+                } else {
+                    tempFile.delete()
+                }
+                if (renamed) {
+                    recordWrite()
+                }
+                result.put("value", renamed)
+            }
+            
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Rename error: ${e.message}")
+            invoke.reject("Failed to rename item: ${e.message}")
+        }
+    }
+
+    @Command
+    fun setExpiry(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(SetExpiryRequest::class.java)
+            val file = fileForKey(request.key)
+            val now = System.currentTimeMillis()
+            val result = JSObject()
+            
+            if (!file.exists() || isExpired(file, now)) {
+                result.put("value", false)
+            } else {
+                // Only the expiration time changes, the stored value is written back as is
+                val entryJson = JSONObject(file.readText())
+                val ttl = request.ttl
+                if (ttl != null) {
+                    entryJson.put("expires_at", now + ttl * 1000)
+                } else {
+                    entryJson.remove("expires_at")
+                }
+                val tempFile = File.createTempFile("expiry", ".tmp", cacheDir.parentFile)
+                tempFile.writeText(entryJson.toString())
+                val replaced = tempFile.renameTo(file)
+                if (replaced) {
+                    recordWrite()
+                } else {
+                    tempFile.delete()
+                }
+                result.put("value", replaced)
+            }
+            
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "SetExpiry error: ${e.message}")
+            invoke.reject("Failed to update expiration: ${e.message}")
+        }
+    }
+
+    @Command
+    fun rateLimit(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(RateLimitRequest::class.java)
+            
+            // Commands can run concurrently, so the read and increment happen under a lock
+            val result = synchronized(this) {
+                val file = fileForKey(request.key)
+                val now = System.currentTimeMillis()
+                
+                // Continue the current window, or start a new one
+                var count = 0L
+                var expiresAt = now + request.windowSecs * 1000
+                if (file.exists() && !isExpired(file, now)) {
+                    val entryJson = JSONObject(file.readText())
+                    if (entryJson.has("expires_at") && !entryJson.optBoolean("is_compressed", false)) {
+                        count = entryJson.getString("value").toLongOrNull() ?: 0
+                        expiresAt = entryJson.getLong("expires_at")
+                    }
+                }
+                
+                val allowed = count < request.max
+                if (allowed) {
+                    count += 1
+                    writeEntry(request.key, count.toString(), expiresAt, false, compressionMethod)
+                }
+                
+                val response = JSObject()
+                response.put("allowed", allowed)
+                response.put("remaining", if (allowed) request.max - count else 0L)
+                response.put("resetAt", expiresAt / 1000)
+                response
+            }
+            
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Rate limit error: ${e.message}")
+            invoke.reject("Failed to apply rate limit: ${e.message}")
+        }
+    }
+
     @Command
     fun clear(invoke: Invoke) {
         try {
@@ -390,6 +570,7 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
                 for (file in files) {
                     file.delete()
                 }
+                recordWrite()
                 android.util.Log.i("CachePlugin", "Removed ${files.size} cache items")
             }
             
@@ -400,46 +581,378 @@ class CachePlugin(private val activity: Activity): Plugin(activity) {
         }
     }
 
+    @Command
+    fun clearPrefix(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(ClearPrefixRequest::class.java)
+            android.util.Log.i("CachePlugin", "Clearing cache items under: ${request.prefix}")
+            
+            var removed = 0
+            val files = cacheDir.listFiles { file -> keyForFile(file).startsWith(request.prefix) } ?: emptyArray()
+            for (file in files) {
+                if (file.delete()) {
+                    removed++
+                }
+            }
+            
+            if (removed > 0) {
+                recordWrite()
+            }
+            
+            android.util.Log.i("CachePlugin", "Removed $removed cache items")
+            invoke.resolveObject(removed)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Clear prefix error: ${e.message}")
+            invoke.reject("Failed to clear items: ${e.message}")
+        }
+    }
+
+    @Command
+    fun clearOlderThan(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(ClearOlderThanRequest::class.java)
+            android.util.Log.i("CachePlugin", "Clearing cache items older than ${request.ageSecs} seconds")
+            
+            // Files are rewritten on every set, so their modification time is the time the item was stored
+            val cutoff = System.currentTimeMillis() - request.ageSecs * 1000
+            var removed = 0
+            
+            val files = cacheDir.listFiles() ?: emptyArray()
+            for (file in files) {
+                if (file.lastModified() < cutoff && file.delete()) {
+                    removed++
+                }
+            }
+            
+            if (removed > 0) {
+                recordWrite()
+            }
+            
+            android.util.Log.i("CachePlugin", "Removed $removed old cache items")
+            invoke.resolveObject(removed)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Clear older than error: ${e.message}")
+            invoke.reject("Failed to clear old items: ${e.message}")
+        }
+    }
+
     @Command
     fun stats(invoke: Invoke) {
         try {
             android.util.Log.i("CachePlugin", "Getting cache stats")
             
             val files = cacheDir.listFiles() ?: emptyArray()
-            var totalSize = 0
-            var activeSize = 0
-            val now = System.currentTimeMillis()
+            val result = countEntries(files)
             
-            for (file in files) {
-                totalSize++
-                
-                try {
-                    val entryJson = JSONObject(file.readText())
-                    
-                    if (entryJson.has("expires_at")) {
-                        val expiresAt = entryJson.getLong("expires_at")
-                        
-                        if (now <= expiresAt) {
-                            activeSize++
-                        }
-                    } else {
-                        activeSize++
-                    }
-                } catch (e: Exception) {
-                    // Ignore if file cannot be read
+            android.util.Log.i("CachePlugin", "Cache stats: total=${result.getInteger("totalSize")}, active=${result.getInteger("activeSize")}")
+            
+            val bytesIn = compressedBytesIn.get()
+            val bytesOut = compressedBytesOut.get()
+            
+            result.put("compressionRatio", if (bytesIn == 0L) 0.0 else bytesOut.toDouble() / bytesIn)
+            result.put("bytesSaved", maxOf(bytesIn - bytesOut, 0L))
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Stats error: ${e.message}")
+            invoke.reject("Failed to get stats: ${e.message}")
+        }
+    }
+
+    @Command
+    fun size(invoke: Invoke) {
+        // Every item has its own file, so counting them needs no parsing
+        val files = cacheDir.listFiles() ?: emptyArray()
+        invoke.resolveObject(files.size)
+    }
+
+    @Command
+    fun activeSize(invoke: Invoke) {
+        try {
+            val files = cacheDir.listFiles() ?: emptyArray()
+            invoke.resolveObject(countEntries(files).getInteger("activeSize"))
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Active size error: ${e.message}")
+            invoke.reject("Failed to count active items: ${e.message}")
+        }
+    }
+
+    @Command
+    fun changeToken(invoke: Invoke) {
+        val result = JSObject()
+        result.put("token", currentChangeToken.get())
+        if (lastModified > 0) {
+            result.put("lastModified", lastModified)
+        }
+        invoke.resolve(result)
+    }
+
+    @Command
+    fun ttl(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(TtlRequest::class.java)
+            val file = fileForKey(request.key)
+            val result = JSObject()
+
+            val entryJson = try {
+                if (file.exists()) JSONObject(file.readText()) else null
+            } catch (e: Exception) {
+                null
+            }
+
+            if (entryJson == null) {
+                result.put("status", "missing")
+            } else if (!entryJson.has("expires_at")) {
+                result.put("status", "noExpiry")
+            } else {
+                val remaining = entryJson.getLong("expires_at") - System.currentTimeMillis()
+                if (remaining < 0) {
+                    file.delete()
+                    result.put("status", "missing")
+                } else {
+                    result.put("status", "expiresIn")
+                    result.put("secs", remaining / 1000)
                 }
             }
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Ttl error: ${e.message}")
+            invoke.reject("Failed to get TTL: ${e.message}")
+        }
+    }
+
+    @Command
+    fun cachePath(invoke: Invoke) {
+        val result = JSObject()
+        result.put("dir", cacheDir.absolutePath)
+        result.put("file", cacheDir.absolutePath)
+        result.put("exists", cacheDir.exists())
+        invoke.resolve(result)
+    }
+
+    @Command
+    fun resetStats(invoke: Invoke) {
+        compressedBytesIn.set(0)
+        compressedBytesOut.set(0)
+        invoke.resolve(JSObject())
+    }
+
+    @Command
+    fun statsPrefix(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(StatsPrefixRequest::class.java)
+            android.util.Log.i("CachePlugin", "Getting cache stats for prefix: ${request.prefix}")
+            
+            val files = cacheDir.listFiles { file -> keyForFile(file).startsWith(request.prefix) } ?: emptyArray()
+            val result = countEntries(files)
+            
+            android.util.Log.i("CachePlugin", "Cache stats for ${request.prefix}: total=${result.getInteger("totalSize")}, active=${result.getInteger("activeSize")}")
+            
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Stats prefix error: ${e.message}")
+            invoke.reject("Failed to get stats: ${e.message}")
+        }
+    }
+    
+    @Command
+    fun keys(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(KeysRequest::class.java)
+            val prefix = request.prefix ?: ""
+            android.util.Log.i("CachePlugin", "Listing cache keys under: $prefix")
+            
+            val now = System.currentTimeMillis()
+            val files = cacheDir.listFiles { file -> keyForFile(file).startsWith(prefix) } ?: emptyArray()
+            val keys = files
+                .filter { file -> !isExpired(file, now) }
+                .map { file -> keyForFile(file) }
+                .sorted()
+            
+            val list = JSArray()
+            for (key in keys) {
+                list.put(key)
+            }
             
-            android.util.Log.i("CachePlugin", "Cache stats: total=$totalSize, active=$activeSize")
+            val result = JSObject()
+            result.put("keys", list)
+            result.put("total", keys.size)
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            android.util.Log.e("CachePlugin", "Keys error: ${e.message}")
+            invoke.reject("Failed to list keys: ${e.message}")
+        }
+    }
+
+    @Command
+    fun keysPage(invoke: Invoke) {
+        try {
+            val request = invoke.parseArgs(KeysPageRequest::class.java)
+            android.util.Log.i("CachePlugin", "Listing cache keys from ${request.offset} (limit ${request.limit})")
+            
+            val now = System.currentTimeMillis()
+            val files = cacheDir.listFiles() ?: emptyArray()
+            
+            // Sort the keys so pages stay consistent between calls
+            val keys = files
+                .filter { file -> request.includeExpired || !isExpired(file, now) }
+                .map { file -> keyForFile(file) }
+                .sorted()
+            
+            val page = JSArray()
+            for (key in keys.drop(request.offset).take(request.limit)) {
+                page.put(key)
+            }
             
             val result = JSObject()
-            result.put("totalSize", totalSize)
-            result.put("activeSize", activeSize)
+            result.put("keys", page)
+            result.put("total", keys.size)
             invoke.resolve(result)
         } catch (e: Exception) {
-            android.util.Log.e("CachePlugin", "Stats error: ${e.message}")
-            invoke.reject("Failed to get stats: ${e.message}")
+            android.util.Log.e("CachePlugin", "Keys page error: ${e.message}")
+            invoke.reject("Failed to list keys: ${e.message}")
+        }
+    }
+    
+    // Get the file holding the entry for a key
+    private fun fileForKey(key: String): File = CacheFileNames.fileForKey(cacheDir, key)
+    
+    // Get the key of the entry stored in a cache file, reversing fileForKey
+    private fun keyForFile(file: File): String = CacheFileNames.keyForFile(file)
+    
+    // Record a write to the cache directory
+    private fun recordWrite() {
+        val now = System.currentTimeMillis()
+        currentChangeToken.updateAndGet { token -> maxOf(token + 1, now) }
+        lastModified = now / 1000
+    }
+    
+    // Convert a value received from the Rust side into the JSON string stored in the cache file
+    private fun toValueString(value: Any?): String {
+        return if (value != null) {
+            // Check if the value is JSON
+            try {
+                // If it's a JSON object
+                if (value.toString().trim().startsWith("{") || 
+                    value.toString().trim().startsWith("[")) {
+                    // Save in JSON format
+                    value.toString()
+                } else {
+                    // Convert simple string values to JSON format
+                    "\"" + value.toString().replace("\"", "\\\"") + "\""
+                }
+            } catch (e: Exception) {
+                // In case of any error, treat as simple string
+                "\"" + value.toString().replace("\"", "\\\"") + "\""
+            }
+        } else {
+            // Empty JSON object for null values
+            "null"
+        }
+    }
+    
+    // Build a cache entry for the value and save it to the key's file
+    private fun writeEntry(key: String, valueString: String, expiresAt: Long?, shouldCompress: Boolean, method: String): File {
+        // Create cache entry, recording the key since the file name may not reveal it
+        val entry = JSONObject()
+        entry.put("key", key)
+        
+        // Compress data (if needed)
+        if (shouldCompress && valueString.length > compressionThreshold) {
+            android.util.Log.i("CachePlugin", "Compressing data (${valueString.length} bytes) with $method")
+            
+            // Compress the data
+            val bytes = valueString.toByteArray()
+            val compressed = compressData(bytes, method)
+            compressedBytesIn.addAndGet(bytes.size.toLong())
+            compressedBytesOut.addAndGet(compressed.size.toLong())
+            
+            // Encode with Base64
+            val encodedString = Base64.encodeToString(compressed, Base64.NO_WRAP)
+            
+            entry.put("value", encodedString)
+            entry.put("is_compressed", true)
+        } else {
+            entry.put("value", valueString)
+            entry.put("is_compressed", false)
+        }
+        
+        // Add expiration time - explicitly specify Long type to resolve type ambiguity
+        if (expiresAt != null) {
+            entry.put("expires_at", expiresAt.toLong())
         }
+        
+        // Save to file
+        val file = fileForKey(key)
+        file.writeText(entry.toString())
+        recordWrite()
+        return file
+    }
+    
+    // Check whether a cache file holds an expired entry
+    private fun isExpired(file: File, now: Long): Boolean {
+        return try {
+            val entryJson = JSONObject(file.readText())
+            entryJson.has("expires_at") && now > entryJson.getLong("expires_at")
+        } catch (e: Exception) {
+            false
+        }
+    }
+    
+    // Count total and active (non-expired) entries among the given cache files, and their sizes by compression
+    private fun countEntries(files: Array<File>): JSObject {
+        var totalSize = 0
+        var activeSize = 0
+        var compressedCount = 0
+        var uncompressedCount = 0
+        var compressedBytes = 0L
+        var uncompressedBytes = 0L
+        var decompressedBytes = 0L
+        var totalBytesOnDisk = 0L
+        val now = System.currentTimeMillis()
+        
+        for (file in files) {
+            totalSize++
+            totalBytesOnDisk += file.length()
+            
+            try {
+                val entryJson = JSONObject(file.readText())
+                
+                if (entryJson.has("expires_at")) {
+                    val expiresAt = entryJson.getLong("expires_at")
+                    
+                    if (now <= expiresAt) {
+                        activeSize++
+                    }
+                } else {
+                    activeSize++
+                }
+                
+                // Break the stored values down by compression
+                val storedBytes = entryJson.optString("value").toByteArray().size.toLong()
+                if (entryJson.optBoolean("is_compressed", false)) {
+                    compressedCount++
+                    compressedBytes += storedBytes
+                    decompressedBytes += decompressData(Base64.decode(entryJson.getString("value"), Base64.NO_WRAP)).size
+                } else {
+                    uncompressedCount++
+                    uncompressedBytes += storedBytes
+                    decompressedBytes += storedBytes
+                }
+            } catch (e: Exception) {
+                // Ignore if file cannot be read
+            }
+        }
+        
+        val result = JSObject()
+        result.put("totalSize", totalSize)
+        result.put("activeSize", activeSize)
+        result.put("compressedCount", compressedCount)
+        result.put("uncompressedCount", uncompressedCount)
+        result.put("compressedBytes", compressedBytes)
+        result.put("uncompressedBytes", uncompressedBytes)
+        result.put("decompressedBytes", decompressedBytes)
+        result.put("totalBytesOnDisk", totalBytesOnDisk)
+        return result
     }
     
     // Data compression helper method
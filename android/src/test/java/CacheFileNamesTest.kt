@@ -0,0 +1,83 @@
+package app.tauri.plugin.cache
+
+import org.json.JSONObject
+import org.junit.Assert.assertEquals
+import org.junit.Assert.assertFalse
+import org.junit.Assert.assertTrue
+import org.junit.Rule
+import org.junit.Test
+import org.junit.rules.TemporaryFolder
+import java.io.File
+
+class CacheFileNamesTest {
+    @get:Rule
+    val folder = TemporaryFolder()
+    
+    private val keys = listOf(
+        "plain-key_1.txt",
+        "user/42/profile",
+        "../../etc/passwd",
+        "..",
+        ".",
+        "thumb:1:small",
+        "thumb_1_small",
+        "ключ",
+        "日本語🔑",
+        "a".repeat(100),
+        "a".repeat(101),
+        "a".repeat(500),
+    )
+    
+    // Write an entry the way the plugin does, recording its key
+    private fun store(dir: File, key: String): File {
+        val file = CacheFileNames.fileForKey(dir, key)
+        file.writeText(JSONObject().put("key", key).put("value", "\"v\"").toString())
+        return file
+    }
+    
+    @Test
+    fun everyKeyMapsToAValidFileInTheCacheDirectory() {
+        val dir = folder.newFolder("tauri_cache")
+        for (key in keys) {
+            val file = CacheFileNames.fileForKey(dir, key)
+            assertEquals(key, dir, file.parentFile)
+            assertFalse(key, file.name == "." || file.name == "..")
+            assertTrue(key, file.name.matches(Regex("[A-Za-z0-9._~-]{1,100}")))
+        }
+    }
+    
+    @Test
+    fun keysMapToUniqueFiles() {
+        val dir = folder.newFolder("tauri_cache")
+        val names = keys.map { CacheFileNames.fileForKey(dir, it).name }
+        assertEquals(keys.size, names.toSet().size)
+    }
+    
+    @Test
+    fun plainKeysKeepTheirFileNames() {
+        val dir = folder.newFolder("tauri_cache")
+        assertEquals("plain-key_1.txt", CacheFileNames.fileForKey(dir, "plain-key_1.txt").name)
+        assertEquals("a".repeat(100), CacheFileNames.fileForKey(dir, "a".repeat(100)).name)
+    }
+    
+    @Test
+    fun keyForFileReversesFileForKey() {
+        val dir = folder.newFolder("tauri_cache")
+        for (key in keys) {
+            assertEquals(key, CacheFileNames.keyForFile(store(dir, key)))
+        }
+        assertEquals(keys.size, dir.listFiles()!!.size)
+    }
+    
+    @Test
+    fun renamedEntriesRecordTheNewKey() {
+        val dir = folder.newFolder("tauri_cache")
+        val from = store(dir, "draft/1")
+        
+        val to = CacheFileNames.fileForKey(dir, "final:1")
+        to.writeText(CacheFileNames.withKey(from.readText(), "final:1"))
+        
+        assertEquals("final:1", CacheFileNames.keyForFile(to))
+        assertEquals("\"v\"", JSONObject(to.readText()).getString("value"))
+    }
+}
@@ -1,4 +1,54 @@
-const COMMANDS: &[&str] = &["set", "get", "has", "remove", "clear", "stats"];
+const COMMANDS: &[&str] = &[
+    "set",
+    "get",
+    "has",
+    "remove",
+    "clear",
+    "stats",
+    "stats_prefix",
+    "reconfigure",
+    "keys_page",
+    "clear_older_than",
+    "rename",
+    "get_with_source",
+    "load_from_json",
+    "reset_stats",
+    "change_token",
+    "relocate",
+    "rate_limit",
+    "get_if_changed",
+    "value_type",
+    "set_all",
+    "cache_path",
+    "get_item",
+    "get_many",
+    "set_many",
+    "keys",
+    "clear_prefix",
+    "remove_many",
+    "ttl",
+    "expire",
+    "persist",
+    "get_or_set",
+    "flush",
+    "push",
+    "pop",
+    "export",
+    "import",
+    "invalidate_tag",
+    "get_stale",
+    "size",
+    "active_size",
+    "find_key_by_value",
+    "set_cleanup_interval",
+    "cleanup",
+    "set_if_absent",
+    "replace",
+    "getset",
+    "remove_matching",
+    "keys_matching",
+    "prune",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
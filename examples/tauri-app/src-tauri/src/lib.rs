@@ -14,6 +14,7 @@ pub fn run() {
         compression_level: Some(9),
         compression_threshold: Some(4096),
         compression_method: Some(tauri_plugin_cache::CompressionMethod::Lzma2),
+        ..Default::default()
     };
     
     tauri::Builder::default()
@@ -11,9 +11,110 @@ export enum CompressionMethod {
   /**
    * LZMA2 compression (better compression ratio, slower)
    */
-  Lzma2 = 'lzma2'
+  Lzma2 = 'lzma2',
+  /**
+   * Gzip compression, stored as plain gzip data that external tools can read
+   * (desktop only, mobile platforms use Zlib instead)
+   */
+  Gzip = 'gzip',
+  /**
+   * Zstandard compression, faster than Zlib at a similar or better ratio
+   * (desktop only with the `zstd` feature, Zlib is used instead otherwise)
+   */
+  Zstd = 'zstd'
+}
+
+/**
+ * Where a value returned by the cache was found
+ */
+export type CacheSource = 'memory' | 'disk' | 'backingStore';
+
+/**
+ * A cached value along with where it was found
+ */
+export interface SourcedValue<T = any> {
+  /**
+   * The stored value
+   */
+  value: T;
+  /**
+   * Where the value was found
+   */
+  source: CacheSource;
+}
+
+/**
+ * A cached value along with whether it has expired and is only served within its stale window
+ */
+export interface StaleValue<T = any> {
+  /**
+   * The stored value
+   */
+  value: T;
+  /**
+   * Whether the value has expired and should be refreshed
+   */
+  stale: boolean;
+}
+
+/**
+ * A cached value along with the details stored with it
+ */
+export interface CacheItem<T = any, M = any> {
+  /**
+   * The stored value
+   */
+  value: T;
+  /**
+   * Unix timestamp in seconds when the item expires, if it has a TTL
+   */
+  expiresAt?: number;
+  /**
+   * Whether the value is stored compressed
+   */
+  isCompressed?: boolean;
+  /**
+   * Metadata stored with the value
+   */
+  metadata?: M;
 }
 
+/**
+ * JSON type of a stored value
+ */
+export type JsonType = 'null' | 'boolean' | 'number' | 'string' | 'array' | 'object';
+
+/**
+ * Result of a conditional read
+ */
+export type ConditionalValue<T = any> =
+  | { status: 'notModified' }
+  | {
+      status: 'modified';
+      /**
+       * The current value
+       */
+      value: T;
+      /**
+       * The current version, to pass to the next conditional read (null if unknown)
+       */
+      version: number | null;
+    };
+
+/**
+ * Time left until an item expires
+ */
+export type Ttl =
+  | { status: 'missing' }
+  | { status: 'noExpiry' }
+  | {
+      status: 'expiresIn';
+      /**
+       * Seconds left until the item expires
+       */
+      secs: number;
+    };
+
 /**
  * Interface for cache statistics
  */
@@ -26,6 +127,128 @@ export interface CacheStats {
    * Number of active (non-expired) items in the cache
    */
   activeSize: number;
+  /**
+   * Compressed size divided by original size across all compressed writes since the
+   * last reset (0 if nothing was compressed). Only reported by `stats`
+   */
+  compressionRatio: number;
+  /**
+   * Bytes saved by compression since the last reset. Only reported by `stats`
+   */
+  bytesSaved: number;
+  /**
+   * Number of items stored compressed
+   */
+  compressedCount: number;
+  /**
+   * Number of items stored uncompressed
+   */
+  uncompressedCount: number;
+  /**
+   * Bytes taken up by the stored values of compressed items
+   */
+  compressedBytes: number;
+  /**
+   * Bytes taken up by the stored values of uncompressed items
+   */
+  uncompressedBytes: number;
+  /**
+   * Bytes the stored values of all items take up once decompressed
+   */
+  decompressedBytes: number;
+  /**
+   * Size of the cache on disk. On mobile this is the size of the counted item files, desktop
+   * reports 0 for `statsPrefix`
+   */
+  totalBytesOnDisk: number;
+  /**
+   * Reads that found a live item since the last reset. Only reported by `stats`, desktop only
+   */
+  hits: number;
+  /**
+   * Reads that found no live item since the last reset, including expired ones. Only reported
+   * by `stats`, desktop only
+   */
+  misses: number;
+  /**
+   * Misses where the item was still stored but had expired. Only reported by `stats`, desktop only
+   */
+  expirations: number;
+}
+
+/**
+ * Outcome of a rate-limited call
+ */
+export interface RateLimitResult {
+  /**
+   * Whether the call is allowed
+   */
+  allowed: boolean;
+  /**
+   * Number of calls still allowed in the current window
+   */
+  remaining: number;
+  /**
+   * Time the current window ends in seconds since the Unix epoch
+   */
+  resetAt: number;
+}
+
+/**
+ * Outcome of removing the expired items
+ */
+export interface PruneResult {
+  /**
+   * Number of expired items removed
+   */
+  removed: number;
+  /**
+   * Number of active items left
+   */
+  remaining: number;
+}
+
+/**
+ * A page of cache keys
+ */
+export interface KeysPage {
+  /**
+   * The keys in this page, in lexicographic order
+   */
+  keys: string[];
+  /**
+   * Total number of keys across all pages
+   */
+  total: number;
+}
+
+/**
+ * A token identifying the current state of the cache
+ */
+export interface CachePath {
+  /**
+   * Directory containing the cache
+   */
+  dir: string;
+  /**
+   * The cache file, or on mobile, where every item is a separate file, the cache directory
+   */
+  file: string;
+  /**
+   * Whether the cache exists on disk yet
+   */
+  exists: boolean;
+}
+
+export interface ChangeToken {
+  /**
+   * Increases whenever the cache is written
+   */
+  token: number;
+  /**
+   * Time of the last write in seconds since the Unix epoch, if known
+   */
+  lastModified?: number;
 }
 
 /**
@@ -33,7 +256,8 @@ export interface CacheStats {
  */
 export interface SetItemOptions {
   /**
-   * Time-to-live in seconds. If not provided, the item will never expire.
+   * Time-to-live in seconds. If not provided, the item gets the configured `default_ttl`, or
+   * never expires without one. With a `default_ttl`, 0 stores the item without expiration.
    */
   ttl?: number;
   /**
@@ -44,6 +268,66 @@ export interface SetItemOptions {
    * Compression method to use. If not provided, uses the default compression method.
    */
   compressionMethod?: CompressionMethod;
+  /**
+   * Age in seconds after which the item is still returned by `get`, but a `cache://stale` event
+   * is emitted so it can be refreshed in the background (desktop only).
+   */
+  softTtl?: number;
+  /**
+   * Seconds past expiration during which `getStale` still returns the value, flagged as stale,
+   * and the background cleanup keeps it (desktop only).
+   */
+  staleTtl?: number;
+  /**
+   * Arbitrary data stored along with the value and returned by `getItem`, e.g. an ETag. It's
+   * never compressed and doesn't affect expiration (desktop only).
+   */
+  metadata?: unknown;
+  /**
+   * Tags to group the item by, so `invalidateTag` can remove every item carrying one, e.g.
+   * everything tagged `user-42` on logout (desktop only).
+   */
+  tags?: string[];
+}
+
+/**
+ * An item to store with `setMany`
+ */
+export interface SetManyItem<T = any> {
+  /**
+   * The key to store the value under
+   */
+  key: string;
+  /**
+   * The value to store
+   */
+  value: T;
+  /**
+   * Options for storing this item
+   */
+  options?: SetItemOptions;
+}
+
+/**
+ * Compression settings that can be changed at runtime
+ */
+export interface CompressionSettings {
+  /**
+   * Whether new items are compressed by default
+   */
+  defaultCompression: boolean;
+  /**
+   * Compression level (0-9, where 0 is no compression and 9 is max compression). Defaults to 6.
+   */
+  compressionLevel?: number;
+  /**
+   * Threshold in bytes after which compression is applied. Defaults to 1024.
+   */
+  compressionThreshold?: number;
+  /**
+   * Compression method to use. Defaults to Zlib.
+   */
+  compressionMethod?: CompressionMethod;
 }
 
 /**
@@ -82,78 +366,858 @@ export async function set(key: string, value: any, options?: SetItemOptions): Pr
 }
 
 /**
- * Gets an item from the cache
- * @param key The key to retrieve
- * @returns The stored value or null if not found or expired
+ * Gets an item, first storing `value` if the key is missing or expired. On desktop the check and
+ * the insert are atomic, so concurrent callers all get back the single value that was stored
+ * @param key The key of the item
+ * @param value The value to store if the key is missing or expired
+ * @param options Optional settings used when storing the value
+ * @returns The value now held by the cache
  * @example
  * ```typescript
- * const user = await cache.get<User>('user');
- * if (user) {
- *   console.log(user.name); // 'John'
- * }
+ * const user = await cache.getOrSet('user', defaultUser, { ttl: 60 });
  * ```
  */
-export async function get<T = any>(key: string): Promise<T | null> {
-  const result = await invoke<T | null>('plugin:cache|get', {
+export async function getOrSet<T = any>(key: string, value: T, options?: SetItemOptions): Promise<T> {
+  return await invoke<T>('plugin:cache|get_or_set', {
     key,
+    value,
+    options,
   });
-  return result === undefined ? null : result;
 }
 
 /**
- * Checks if an item exists in the cache and is not expired
- * @param key The key to check
- * @returns True if the item exists and is not expired
+ * Stores an item only if the key is missing or expired, e.g. to take a lock held under that key.
+ * On desktop the check and the insert are atomic, so when several callers race exactly one of
+ * them gets `true`
+ * @param key The key of the item
+ * @param value The value to store
+ * @param options Optional settings used when storing the value
+ * @returns Whether the value was stored
  * @example
  * ```typescript
- * if (await cache.has('user')) {
- *   // User exists in cache
+ * if (await cache.setIfAbsent('sync-lock', ownerId, { ttl: 30 })) {
+ *   // This window holds the lock for the next 30 seconds
  * }
  * ```
  */
-export async function has(key: string): Promise<boolean> {
-  const response = await invoke<{ value: boolean }>('plugin:cache|has', {
+export async function setIfAbsent<T = any>(key: string, value: T, options?: SetItemOptions): Promise<boolean> {
+  const response = await invoke<BooleanResponse>('plugin:cache|set_if_absent', {
     key,
+    value,
+    options,
   });
   return response.value;
 }
 
 /**
- * Removes an item from the cache
- * @param key The key to remove
- * @returns A promise that resolves when the operation is complete
+ * Stores an item only if the key holds a live item, so an item removed in the meantime isn't
+ * brought back. Nothing is written for a missing or expired key
+ * @param key The key of the item
+ * @param value The new value
+ * @param options Optional settings used when storing the value
+ * @returns Whether the value was stored
  * @example
  * ```typescript
- * await cache.remove('user');
+ * await cache.replace('session', refreshedSession, { ttl: 3600 });
  * ```
  */
-export async function remove(key: string): Promise<void> {
-  await invoke('plugin:cache|remove', {
+export async function replace<T = any>(key: string, value: T, options?: SetItemOptions): Promise<boolean> {
+  const response = await invoke<BooleanResponse>('plugin:cache|replace', {
     key,
+    value,
+    options,
   });
+  return response.value;
 }
 
 /**
- * Clears all items from the cache
- * @returns A promise that resolves when the operation is complete
+ * Stores an item and returns the value it replaced, e.g. to swap a feature flag. On desktop the
+ * read and the write are atomic, so no other write can slip in between
+ * @param key The key of the item
+ * @param value The new value
+ * @param options Optional settings used when storing the value
+ * @returns The previous value, or null if the key was missing or expired
  * @example
  * ```typescript
- * await cache.clear();
+ * const previous = await cache.getset('feature:checkout', 'v2');
  * ```
  */
-export async function clear(): Promise<void> {
-  await invoke('plugin:cache|clear');
+export async function getset<T = any>(key: string, value: T, options?: SetItemOptions): Promise<T | null> {
+  const result = await invoke<T | null>('plugin:cache|getset', {
+    key,
+    value,
+    options,
+  });
+  return result === undefined ? null : result;
 }
 
 /**
- * Gets statistics about the cache
- * @returns Cache statistics including the number of active and total items
+ * Appends a value to the array stored under a key, starting a new array if the key is missing or
+ * expired. On desktop this is atomic, so concurrent pushes never lose elements
+ * @param key The key of the array
+ * @param value The value to append
+ * @param options Optional settings used when starting a new array
+ * @returns The array's new length
  * @example
  * ```typescript
- * const stats = await cache.stats();
- * console.log(`Cache has ${stats.totalSize} items (${stats.activeSize} active)`);
+ * await cache.push('log', { event: 'login', at: Date.now() });
  * ```
  */
-export async function stats(): Promise<CacheStats> {
-  return await invoke<CacheStats>('plugin:cache|stats');
+export async function push(key: string, value: any, options?: SetItemOptions): Promise<number> {
+  return await invoke<number>('plugin:cache|push', {
+    key,
+    value,
+    options,
+  });
+}
+
+/**
+ * Removes and returns the last element of the array stored under a key
+ * @param key The key of the array
+ * @returns The removed element, or null if the key is missing, expired or an empty array
+ * @example
+ * ```typescript
+ * const last = await cache.pop<LogEntry>('log');
+ * ```
+ */
+export async function pop<T = any>(key: string): Promise<T | null> {
+  return await invoke<T | null>('plugin:cache|pop', { key });
+}
+
+/**
+ * Stores multiple items in a single write, each with its own options. Either every item is
+ * stored or none is (on mobile, items are stored one at a time)
+ * @param items The items to store
+ * @example
+ * ```typescript
+ * await cache.setMany([
+ *   { key: 'user', value: user, options: { ttl: 3600 } },
+ *   { key: 'report', value: report, options: { compress: true } },
+ * ]);
+ * ```
+ */
+export async function setMany(items: SetManyItem[]): Promise<void> {
+  await invoke('plugin:cache|set_many', {
+    items,
+  });
+}
+
+/**
+ * Stores multiple items with the same TTL in a single write, using the default compression settings
+ * @param values The items to store, keyed by cache key
+ * @param ttl TTL in seconds applied to every item (no expiration if omitted)
+ * @example
+ * ```typescript
+ * // Cache a fetched page of products for 10 minutes
+ * const page = await fetchProducts(3);
+ * await cache.setAll(Object.fromEntries(page.map((p) => [`product:${p.id}`, p])), 600);
+ * ```
+ */
+export async function setAll(values: Record<string, any>, ttl?: number): Promise<void> {
+  await invoke('plugin:cache|set_all', {
+    values,
+    ttl,
+  });
+}
+
+/**
+ * Seeds the cache with every key/value pair of a JSON object in a single write,
+ * using the default compression settings
+ * @param map The items to store, keyed by cache key
+ * @param defaultTtl TTL in seconds applied to every item (no expiration if omitted)
+ * @param skipExisting Whether existing items are kept instead of overwritten (defaults to false)
+ * @returns The number of items stored
+ * @example
+ * ```typescript
+ * import defaults from './cache-defaults.json';
+ * await cache.loadFromJson(defaults, 3600, true);
+ * ```
+ */
+export async function loadFromJson(
+  map: Record<string, any>,
+  defaultTtl?: number,
+  skipExisting?: boolean
+): Promise<number> {
+  return await invoke<number>('plugin:cache|load_from_json', {
+    map,
+    defaultTtl,
+    skipExisting,
+  });
+}
+
+/**
+ * Gets an item from the cache
+ * @param key The key to retrieve
+ * @returns The stored value or null if not found or expired
+ * @example
+ * ```typescript
+ * const user = await cache.get<User>('user');
+ * if (user) {
+ *   console.log(user.name); // 'John'
+ * }
+ * ```
+ */
+export async function get<T = any>(key: string): Promise<T | null> {
+  const result = await invoke<T | null>('plugin:cache|get', {
+    key,
+  });
+  return result === undefined ? null : result;
+}
+
+/**
+ * Gets several items from the cache in a single call
+ * @param keys The keys to retrieve
+ * @returns The stored values keyed by key, with null for items that are missing or expired
+ * @example
+ * ```typescript
+ * const users = await cache.getMany<User>(['user:1', 'user:2']);
+ * if (users['user:1'] === null) {
+ *   // Not cached
+ * }
+ * ```
+ */
+export async function getMany<T = any>(
+  keys: string[]
+): Promise<Record<string, T | null>> {
+  return await invoke<Record<string, T | null>>('plugin:cache|get_many', {
+    keys,
+  });
+}
+
+/**
+ * Gets an item from the cache unless the caller already holds its current version, so large
+ * values that didn't change aren't sent again
+ * @param key The key to retrieve
+ * @param since The version returned by an earlier call, if any
+ * @returns `{ status: 'notModified' }`, the value with its new version, or null if not found or expired
+ * @example
+ * ```typescript
+ * const result = await cache.getIfChanged<Report>('report', version);
+ * if (result?.status === 'modified') {
+ *   report = result.value;
+ *   version = result.version;
+ * }
+ * ```
+ */
+export async function getIfChanged<T = any>(
+  key: string,
+  since?: number | null
+): Promise<ConditionalValue<T> | null> {
+  const result = await invoke<ConditionalValue<T> | null>(
+    'plugin:cache|get_if_changed',
+    {
+      key,
+      since,
+    }
+  );
+  return result === undefined ? null : result;
+}
+
+/**
+ * Gets the JSON type of a stored value without transferring the value itself, for cache inspectors
+ * @param key The key to inspect
+ * @returns `'null'`, `'boolean'`, `'number'`, `'string'`, `'array'` or `'object'`, or null if not found or expired
+ * @example
+ * ```typescript
+ * const type = await cache.valueType('report'); // 'object'
+ * ```
+ */
+export async function valueType(key: string): Promise<JsonType | null> {
+  const result = await invoke<JsonType | null>('plugin:cache|value_type', {
+    key,
+  });
+  return result === undefined ? null : result;
+}
+
+/**
+ * Gets an item from the cache along with its expiration time and the metadata stored with it
+ * @param key The key to retrieve
+ * @returns The stored value and its details, or null if not found or expired
+ * @example
+ * ```typescript
+ * await cache.set('feed', feed, { metadata: { etag: response.headers.get('etag') } });
+ * // ...later
+ * const item = await cache.getItem<Feed, { etag: string }>('feed');
+ * const headers = item ? { 'If-None-Match': item.metadata?.etag } : {};
+ * ```
+ */
+export async function getItem<T = any, M = any>(
+  key: string
+): Promise<CacheItem<T, M> | null> {
+  const result = await invoke<CacheItem<T, M> | null>('plugin:cache|get_item', {
+    key,
+  });
+  return result === undefined ? null : result;
+}
+
+/**
+ * Gets an item from the cache along with where it was found, useful for measuring
+ * how often reads are served from memory rather than disk
+ * @param key The key to retrieve
+ * @returns The stored value and its source, or null if not found or expired
+ * @example
+ * ```typescript
+ * const result = await cache.getWithSource<User>('user');
+ * if (result) {
+ *   console.log(`Served from ${result.source}`);
+ * }
+ * ```
+ */
+export async function getWithSource<T = any>(
+  key: string
+): Promise<SourcedValue<T> | null> {
+  const result = await invoke<SourcedValue<T> | null>(
+    'plugin:cache|get_with_source',
+    {
+      key,
+    }
+  );
+  return result === undefined ? null : result;
+}
+
+/**
+ * Gets a value, or an expired one that's still within the `staleTtl` it was stored with, so it
+ * can be shown while a fresh one is fetched
+ * @param key The key to retrieve
+ * @returns The value with `stale: true` if it has expired, or null if it's missing or past its
+ * stale window
+ * @example
+ * ```typescript
+ * const result = await cache.getStale<Report>('report');
+ * if (result?.stale) {
+ *   refreshReport();
+ * }
+ * ```
+ */
+export async function getStale<T = any>(
+  key: string
+): Promise<StaleValue<T> | null> {
+  const result = await invoke<StaleValue<T> | null>('plugin:cache|get_stale', {
+    key,
+  });
+  return result === undefined ? null : result;
+}
+
+/**
+ * Checks if an item exists in the cache and is not expired
+ * @param key The key to check
+ * @returns True if the item exists and is not expired
+ * @example
+ * ```typescript
+ * if (await cache.has('user')) {
+ *   // User exists in cache
+ * }
+ * ```
+ */
+export async function has(key: string): Promise<boolean> {
+  const response = await invoke<{ value: boolean }>('plugin:cache|has', {
+    key,
+  });
+  return response.value;
+}
+
+/**
+ * Gets the time left until an item expires, e.g. to refresh a token before it runs out
+ * @param key The key to inspect
+ * @returns `{ status: 'expiresIn', secs }`, `{ status: 'noExpiry' }` if the item never expires,
+ * or `{ status: 'missing' }` if it doesn't exist or has expired
+ * @example
+ * ```typescript
+ * const ttl = await cache.ttl('token');
+ * if (ttl.status !== 'expiresIn' || ttl.secs < 60) {
+ *   await refreshToken();
+ * }
+ * ```
+ */
+export async function ttl(key: string): Promise<Ttl> {
+  return await invoke<Ttl>('plugin:cache|ttl', {
+    key,
+  });
+}
+
+/**
+ * Sets an item to expire after a new TTL without sending its value again, e.g. to extend an
+ * active session
+ * @param key The key of the item
+ * @param ttl Time-to-live in seconds from now
+ * @returns True if the item was updated, false if it doesn't exist or has expired
+ * @example
+ * ```typescript
+ * await cache.expire('session', 30 * 60);
+ * ```
+ */
+export async function expire(key: string, ttl: number): Promise<boolean> {
+  const response = await invoke<BooleanResponse>('plugin:cache|expire', {
+    key,
+    ttl,
+  });
+  return response.value;
+}
+
+/**
+ * Keeps an item until it's removed, dropping its expiration time
+ * @param key The key of the item
+ * @returns True if the item was updated, false if it doesn't exist or has expired
+ * @example
+ * ```typescript
+ * await cache.persist('session');
+ * ```
+ */
+export async function persist(key: string): Promise<boolean> {
+  const response = await invoke<BooleanResponse>('plugin:cache|persist', {
+    key,
+  });
+  return response.value;
+}
+
+/**
+ * Removes an item from the cache
+ * @param key The key to remove
+ * @returns A promise that resolves when the operation is complete
+ * @example
+ * ```typescript
+ * await cache.remove('user');
+ * ```
+ */
+export async function remove(key: string): Promise<void> {
+  await invoke('plugin:cache|remove', {
+    key,
+  });
+}
+
+/**
+ * Removes several items from the cache at once
+ * @param keys The keys to remove
+ * @returns A promise that resolves when the operation is complete
+ * @example
+ * ```typescript
+ * await cache.removeMany(['user', 'settings']);
+ * ```
+ */
+export async function removeMany(keys: string[]): Promise<void> {
+  await invoke('plugin:cache|remove_many', {
+    keys,
+  });
+}
+
+/**
+ * Counts a call against a fixed-window rate limit, atomically with respect to other calls
+ * @param key The key holding the counter, used for nothing else
+ * @param max Maximum number of calls allowed per window
+ * @param windowSecs Length of the window in seconds, starting with the first call
+ * @returns Whether the call is allowed, how many calls remain and when the window ends
+ * @example
+ * ```typescript
+ * const { allowed } = await cache.rateLimit('ratelimit:search', 10, 60);
+ * if (!allowed) {
+ *   throw new Error('Too many searches, try again in a minute');
+ * }
+ * ```
+ */
+export async function rateLimit(
+  key: string,
+  max: number,
+  windowSecs: number
+): Promise<RateLimitResult> {
+  return await invoke<RateLimitResult>('plugin:cache|rate_limit', {
+    key,
+    max,
+    windowSecs,
+  });
+}
+
+/**
+ * Moves the cache file to a new path (desktop only)
+ * @param path The new path of the cache file
+ * @param copyExisting Whether the current items are copied to the new file (defaults to true)
+ * @example
+ * ```typescript
+ * await cache.relocate('/new/data/dir/cache.json');
+ * ```
+ */
+export async function relocate(path: string, copyExisting?: boolean): Promise<void> {
+  await invoke('plugin:cache|relocate', {
+    path,
+    copyExisting,
+  });
+}
+
+/**
+ * Moves an item to another key in a single operation, keeping its TTL and compression
+ * @param from The key to move the item from
+ * @param to The key to move the item to
+ * @param overwrite Whether an existing item under `to` is replaced (defaults to false)
+ * @returns True if the item was moved, false if `from` doesn't exist. Rejects if `to` holds a
+ * live item and `overwrite` is false
+ * @example
+ * ```typescript
+ * // Publish a value computed under a temporary key
+ * await cache.set('report:tmp', report);
+ * await cache.rename('report:tmp', 'report', true);
+ * ```
+ */
+export async function rename(
+  from: string,
+  to: string,
+  overwrite?: boolean
+): Promise<boolean> {
+  const response = await invoke<{ value: boolean }>('plugin:cache|rename', {
+    from,
+    to,
+    overwrite,
+  });
+  return response.value;
+}
+
+/**
+ * Makes sure every write so far has reached the disk, e.g. before the app exits. Does nothing if
+ * nothing was written since the last flush
+ * @returns A promise that resolves once the cache is on disk
+ * @example
+ * ```typescript
+ * await getCurrentWindow().onCloseRequested(async () => {
+ *   await cache.flush();
+ * });
+ * ```
+ */
+export async function flush(): Promise<void> {
+  await invoke('plugin:cache|flush');
+}
+
+/**
+ * Writes every item to a JSON file for backups or debugging, as an array of
+ * `{ key, value, expiresAt, createdAt?, metadata? }` sorted by key, with values decompressed
+ * (desktop only)
+ * @param path The file to write
+ * @param includeExpired Whether to include expired items that haven't been cleaned up yet
+ * (defaults to false)
+ * @example
+ * ```typescript
+ * await cache.exportCache('/backups/cache.json');
+ * ```
+ */
+export async function exportCache(path: string, includeExpired?: boolean): Promise<void> {
+  await invoke('plugin:cache|export', {
+    path,
+    includeExpired,
+  });
+}
+
+/**
+ * Stores the items of a file written by `exportCache`, keeping their expiration times and
+ * compressing them according to the current settings (desktop only)
+ * @param path The file to read
+ * @param overwrite Whether to replace existing items with the same keys (defaults to false)
+ * @returns How many items were stored; items that expired since the export are skipped
+ * @example
+ * ```typescript
+ * await cache.importCache('/backups/cache.json', true);
+ * ```
+ */
+export async function importCache(path: string, overwrite?: boolean): Promise<number> {
+  return await invoke<number>('plugin:cache|import', {
+    path,
+    overwrite,
+  });
+}
+
+/**
+ * Clears all items from the cache
+ * @returns A promise that resolves when the operation is complete
+ * @example
+ * ```typescript
+ * await cache.clear();
+ * ```
+ */
+export async function clear(): Promise<void> {
+  await invoke('plugin:cache|clear');
+}
+
+/**
+ * Removes all items whose keys start with the prefix, e.g. to invalidate a namespace
+ * @param prefix The key prefix of the items to remove
+ * @returns The number of removed items
+ * @example
+ * ```typescript
+ * const removed = await cache.clearPrefix('session:');
+ * ```
+ */
+export async function clearPrefix(prefix: string): Promise<number> {
+  return await invoke<number>('plugin:cache|clear_prefix', {
+    prefix,
+  });
+}
+
+/**
+ * Removes all items whose keys match a glob pattern, where `*` matches any run of characters and
+ * `?` a single one. The pattern must match the whole key, and every key is checked
+ * @param pattern The glob pattern, e.g. `'thumb:*:small'`
+ * @returns The number of removed items
+ * @example
+ * ```typescript
+ * const removed = await cache.removeMatching('thumb:*:small');
+ * ```
+ */
+export async function removeMatching(pattern: string): Promise<number> {
+  return await invoke<number>('plugin:cache|remove_matching', {
+    pattern,
+  });
+}
+
+/**
+ * Removes all items stored with the tag, e.g. everything belonging to a user on logout.
+ * Desktop only.
+ * @param tag The tag of the items to remove
+ * @returns The number of removed items
+ * @example
+ * ```typescript
+ * await cache.set('user-42:profile', profile, { tags: ['user-42'] });
+ * const removed = await cache.invalidateTag('user-42');
+ * ```
+ */
+export async function invalidateTag(tag: string): Promise<number> {
+  return await invoke<number>('plugin:cache|invalidate_tag', {
+    tag,
+  });
+}
+
+/**
+ * Removes all items stored more than the given number of seconds ago, regardless of their TTL
+ * @param ageSecs Minimum age in seconds of the items to remove
+ * @returns The number of removed items
+ * @example
+ * ```typescript
+ * // Remove everything stored more than an hour ago
+ * const removed = await cache.clearOlderThan(3600);
+ * ```
+ */
+export async function clearOlderThan(ageSecs: number): Promise<number> {
+  return await invoke<number>('plugin:cache|clear_older_than', {
+    ageSecs,
+  });
+}
+
+/**
+ * Finds the first key, in lexicographic order, of a live item holding the value, e.g. to avoid
+ * caching the same value twice. Every item is scanned and decompressed, so this takes time
+ * proportional to the size of the cache
+ * @param value The value to look for
+ * @returns The key, or null if no live item holds the value
+ * @example
+ * ```typescript
+ * const key = await cache.findKeyByValue({ id: 42 });
+ * ```
+ */
+export async function findKeyByValue(value: unknown): Promise<string | null> {
+  const result = await invoke<string | null>('plugin:cache|find_key_by_value', {
+    value,
+  });
+  return result === undefined ? null : result;
+}
+
+/**
+ * Changes how often the background cleanup removes expired items, e.g. more often when memory
+ * is low. Takes effect within a second; 0 stops the background cleanup. Desktop only
+ * @param secs The new cleanup interval in seconds
+ * @example
+ * ```typescript
+ * await cache.setCleanupInterval(10);
+ * ```
+ */
+export async function setCleanupInterval(secs: number): Promise<void> {
+  await invoke('plugin:cache|set_cleanup_interval', {
+    secs,
+  });
+}
+
+/**
+ * Removes expired items right away instead of waiting for the background cleanup, e.g. right
+ * before reading `stats()` so its numbers are accurate
+ * @returns The number of removed items
+ * @example
+ * ```typescript
+ * await cache.cleanup();
+ * const statistics = await cache.stats();
+ * ```
+ */
+export async function cleanup(): Promise<number> {
+  return await invoke<number>('plugin:cache|cleanup');
+}
+
+/**
+ * Removes expired items right away, like `cleanup()`, and reports how many active items are left
+ * @returns How many items were removed and how many active items remain
+ * @example
+ * ```typescript
+ * const { removed, remaining } = await cache.prune();
+ * console.log(`Removed ${removed} expired items, ${remaining} left`);
+ * ```
+ */
+export async function prune(): Promise<PruneResult> {
+  return await invoke<PruneResult>('plugin:cache|prune');
+}
+
+/**
+ * Gets the total number of items in the cache, including expired ones not yet cleaned up
+ * @returns The number of items
+ * @example
+ * ```typescript
+ * const count = await cache.size();
+ * ```
+ */
+export async function size(): Promise<number> {
+  return await invoke<number>('plugin:cache|size');
+}
+
+/**
+ * Gets the number of active (non-expired) items in the cache
+ * @returns The number of active items
+ * @example
+ * ```typescript
+ * const count = await cache.activeSize();
+ * ```
+ */
+export async function activeSize(): Promise<number> {
+  return await invoke<number>('plugin:cache|active_size');
+}
+
+/**
+ * Gets statistics about the cache
+ * @returns Cache statistics including the number of active and total items
+ * @example
+ * ```typescript
+ * const stats = await cache.stats();
+ * console.log(`Cache has ${stats.totalSize} items (${stats.activeSize} active)`);
+ * ```
+ */
+export async function stats(): Promise<CacheStats> {
+  return await invoke<CacheStats>('plugin:cache|stats');
+}
+
+/**
+ * Gets a token that increases whenever the cache is written. Keep the token and
+ * compare it later to cheaply detect whether the cache changed in the meantime
+ * @returns The current change token and the time of the last write
+ * @example
+ * ```typescript
+ * const { token } = await cache.changeToken();
+ * // ...later
+ * if ((await cache.changeToken()).token !== token) {
+ *   // Refresh state derived from the cache
+ * }
+ * ```
+ */
+export async function changeToken(): Promise<ChangeToken> {
+  return await invoke<ChangeToken>('plugin:cache|change_token');
+}
+
+/**
+ * Gets where the cache is stored on disk, e.g. to show it to the user
+ * @returns The cache directory and file, and whether the file exists yet
+ * @example
+ * ```typescript
+ * const { dir } = await cache.cachePath();
+ * console.log(`Cache stored in ${dir}`);
+ * ```
+ */
+export async function cachePath(): Promise<CachePath> {
+  return await invoke<CachePath>('plugin:cache|cache_path');
+}
+
+/**
+ * Resets the compression metrics reported by `stats`
+ * @returns A promise that resolves when the operation is complete
+ * @example
+ * ```typescript
+ * await cache.resetStats();
+ * ```
+ */
+export async function resetStats(): Promise<void> {
+  await invoke('plugin:cache|reset_stats');
+}
+
+/**
+ * Gets statistics about the cache items whose keys start with a prefix
+ * @param prefix The key prefix to restrict the statistics to
+ * @returns Cache statistics including the number of active and total matching items
+ * @example
+ * ```typescript
+ * const userStats = await cache.statsPrefix('user:');
+ * console.log(`User cache has ${userStats.totalSize} items (${userStats.activeSize} active)`);
+ * ```
+ */
+export async function statsPrefix(prefix: string): Promise<CacheStats> {
+  return await invoke<CacheStats>('plugin:cache|stats_prefix', {
+    prefix,
+  });
+}
+
+/**
+ * Changes the compression settings without restarting the app.
+ * New items use the new settings, existing items keep the compression they were stored with.
+ * Requires the `cache:allow-reconfigure` permission, which is not part of the default set.
+ * @param settings The new compression settings
+ * @returns A promise that resolves when the settings have been applied
+ * @example
+ * ```typescript
+ * await cache.reconfigure({ defaultCompression: true, compressionMethod: CompressionMethod.Lzma2 });
+ * ```
+ */
+export async function reconfigure(settings: CompressionSettings): Promise<void> {
+  await invoke('plugin:cache|reconfigure', {
+    ...settings,
+  });
+}
+
+/**
+ * Lists the keys of all live items in lexicographic order
+ * @param prefix Only list keys starting with this prefix
+ * @returns The keys
+ * @example
+ * ```typescript
+ * const userKeys = await cache.keys('user:');
+ * ```
+ */
+export async function keys(prefix?: string): Promise<string[]> {
+  return await invoke<string[]>('plugin:cache|keys', {
+    prefix,
+  });
+}
+
+/**
+ * Lists the keys of all live items matching a glob pattern, in lexicographic order. `*` matches
+ * any run of characters and `?` a single one. The pattern must match the whole key, and every
+ * key is checked
+ * @param pattern The glob pattern, e.g. `'thumb:*:small'`
+ * @returns The matching keys
+ * @example
+ * ```typescript
+ * const smallThumbs = await cache.keysMatching('thumb:*:small');
+ * ```
+ */
+export async function keysMatching(pattern: string): Promise<string[]> {
+  return await invoke<string[]>('plugin:cache|keys_matching', {
+    pattern,
+  });
+}
+
+/**
+ * Gets a page of cache keys in lexicographic order.
+ * The order is stable, so consecutive pages are consistent as long as the cache isn't modified in between.
+ * @param offset Number of keys to skip
+ * @param limit Maximum number of keys to return
+ * @param includeExpired Whether expired items that haven't been cleaned up yet are included
+ * @returns The keys in the page and the total number of keys
+ * @example
+ * ```typescript
+ * const { keys, total } = await cache.keysPage(0, 50);
+ * console.log(`Showing ${keys.length} of ${total} keys`);
+ * ```
+ */
+export async function keysPage(offset: number, limit: number, includeExpired = false): Promise<KeysPage> {
+  return await invoke<KeysPage>('plugin:cache|keys_page', {
+    offset,
+    limit,
+    includeExpired,
+  });
 }
\ No newline at end of file
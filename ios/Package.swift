@@ -29,6 +29,9 @@ let package = Package(
                 .byName(name: "Tauri"),
                 .product(name: "PLzmaSDK", package: "PLzmaSDK")
             ],
-            path: "Sources")
+            path: "Sources"),
+        .testTarget(
+            name: "PluginTests",
+            dependencies: ["tauri-plugin-cache"])
     ]
 )
\ No newline at end of file
@@ -0,0 +1,49 @@
+import Foundation
+import CommonCrypto
+
+// Maps cache keys to the names of the files holding their entries
+//
+// Keys made of file name safe characters are used as the file name directly, so files written by
+// earlier versions stay readable. Other keys (path separators, unicode, very long keys) map to a
+// short readable prefix plus a hash of the whole key; the "~" separator can't occur in a plain key,
+// so the two kinds of names never collide.
+enum CacheFileNames {
+    private static let safe = CharacterSet(charactersIn: "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789._-")
+    
+    // Get the file in a directory holding the entry for a key
+    static func fileURL(forKey key: String, in directory: URL) -> URL {
+        if !key.isEmpty && key.count <= 100 && key != "." && key != ".." &&
+            key.unicodeScalars.allSatisfy({ safe.contains($0) }) {
+            return directory.appendingPathComponent(key)
+        }
+        
+        let prefix = String(String.UnicodeScalarView(key.unicodeScalars.prefix(32).map { safe.contains($0) ? $0 : "_" }))
+        var digest = [UInt8](repeating: 0, count: Int(CC_SHA256_DIGEST_LENGTH))
+        let keyData = Array(key.utf8)
+        CC_SHA256(keyData, CC_LONG(keyData.count), &digest)
+        let hash = digest.prefix(16).map { String(format: "%02x", $0) }.joined()
+        return directory.appendingPathComponent("\(prefix)~\(hash)")
+    }
+    
+    // Get the key of the entry stored in a cache file, reversing fileURL(forKey:in:)
+    static func key(forFile fileURL: URL) -> String {
+        let name = fileURL.lastPathComponent
+        guard name.contains("~"),
+              let data = try? Data(contentsOf: fileURL),
+              let entryDict = (try? JSONSerialization.jsonObject(with: data)) as? [String: Any],
+              let key = entryDict["key"] as? String else {
+            return name
+        }
+        return key
+    }
+    
+    // Get a copy of a stored entry that records another key, for moving it to that key's file.
+    // Returns nil if the data isn't an entry
+    static func entry(_ data: Data, withKey key: String) throws -> Data? {
+        guard var entryDict = try JSONSerialization.jsonObject(with: data) as? [String: Any] else {
+            return nil
+        }
+        entryDict["key"] = key
+        return try JSONSerialization.data(withJSONObject: entryDict)
+    }
+}
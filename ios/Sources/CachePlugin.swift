@@ -37,6 +37,10 @@ class GetRequest: Decodable {
     let key: String
 }
 
+class GetManyRequest: Decodable {
+    let keys: [String]
+}
+
 class HasRequest: Decodable {
     let key: String
 }
@@ -45,6 +49,49 @@ class RemoveRequest: Decodable {
     let key: String
 }
 
+class RenameRequest: Decodable {
+    let from: String
+    let to: String
+    let overwrite: Bool
+}
+
+class RateLimitRequest: Decodable {
+    let key: String
+    let max: Int
+    let windowSecs: TimeInterval
+}
+
+class SetExpiryRequest: Decodable {
+    let key: String
+    let ttl: TimeInterval?
+}
+
+class TtlRequest: Decodable {
+    let key: String
+}
+
+class ClearPrefixRequest: Decodable {
+    let prefix: String
+}
+
+class ClearOlderThanRequest: Decodable {
+    let ageSecs: TimeInterval
+}
+
+class StatsPrefixRequest: Decodable {
+    let prefix: String
+}
+
+class KeysRequest: Decodable {
+    let prefix: String?
+}
+
+class KeysPageRequest: Decodable {
+    let offset: Int
+    let limit: Int
+    let includeExpired: Bool
+}
+
 class EmptyResponse: Encodable {
 }
 
@@ -57,12 +104,59 @@ class BooleanResponse: Encodable {
 }
 
 class CacheStats: Encodable {
-    let totalSize: Int
-    let activeSize: Int
+    var totalSize = 0
+    var activeSize = 0
+    var compressionRatio: Double = 0
+    var bytesSaved = 0
+    var compressedCount = 0
+    var uncompressedCount = 0
+    var compressedBytes = 0
+    var uncompressedBytes = 0
+    var decompressedBytes = 0
+    var totalBytesOnDisk = 0
+}
+
+class CachePathResponse: Encodable {
+    let dir: String
+    let file: String
+    let exists: Bool
+    
+    init(dir: String, file: String, exists: Bool) {
+        self.dir = dir
+        self.file = file
+        self.exists = exists
+    }
+}
+
+class ChangeTokenResponse: Encodable {
+    let token: UInt64
+    let lastModified: UInt64?
+    
+    init(token: UInt64, lastModified: UInt64?) {
+        self.token = token
+        self.lastModified = lastModified
+    }
+}
+
+class RateLimitResponse: Encodable {
+    let allowed: Bool
+    let remaining: Int
+    let resetAt: UInt64
+    
+    init(allowed: Bool, remaining: Int, resetAt: UInt64) {
+        self.allowed = allowed
+        self.remaining = remaining
+        self.resetAt = resetAt
+    }
+}
+
+class KeysPageResponse: Encodable {
+    let keys: [String]
+    let total: Int
     
-    init(totalSize: Int, activeSize: Int) {
-        self.totalSize = totalSize
-        self.activeSize = activeSize
+    init(keys: [String], total: Int) {
+        self.keys = keys
+        self.total = total
     }
 }
 
@@ -77,6 +171,15 @@ class CachePlugin: Plugin {
     
     private let fileManager = FileManager.default
     private let syncQueue = DispatchQueue(label: "app.tauri.plugin.cache.sync")
+    private let rateLimitLock = NSLock()
+    
+    // Cumulative sizes of the values that went through compression, guarded by syncQueue
+    private var compressedBytesIn = 0
+    private var compressedBytesOut = 0
+    
+    // Increases on every write, guarded by syncQueue; based on the current time so it keeps increasing across restarts
+    private var currentChangeToken = UInt64(Date().timeIntervalSince1970 * 1000)
+    private var lastModified: UInt64? = nil
     
     override init() {
         // Create cache directory
@@ -157,70 +260,84 @@ class CachePlugin: Plugin {
             }
         }
         
-        // Store as JSON data
-        let valueData: Data
-        if let value = dict["value"] {
-            valueData = try JSONSerialization.data(withJSONObject: value)
-        } else {
-            valueData = "null".data(using: .utf8)!
+        try writeEntry(key: key, value: dict["value"], ttl: ttl, shouldCompress: shouldCompress, method: compressionMethodToUse)
+        
+        invoke.resolve(EmptyResponse())
+    }
+    
+    @objc public func loadFromJson(_ invoke: Invoke) throws {
+        let json = invoke.argsJson
+        guard let jsonData = json.data(using: .utf8),
+              let dict = try JSONSerialization.jsonObject(with: jsonData) as? [String: Any],
+              let map = dict["map"] as? [String: Any] else {
+            throw PluginError(code: .invalidArgs, message: "Missing map")
         }
+        let ttl = dict["defaultTtl"] as? TimeInterval
+        let skipExisting = dict["skipExisting"] as? Bool ?? false
         
-        // Apply compression
-        let finalData: Data
-        var isCompressed = false
+        let now = Date().timeIntervalSince1970
+        var loaded = 0
         
-        if shouldCompress && valueData.count > compressionThreshold {
-            // Choose compression method
-            if compressionMethodToUse.lowercased() == "lzma2" {
-                finalData = compressWithLZMA2(data: valueData)
-                isCompressed = true
-            } else {
-                finalData = compressWithZlib(data: valueData)
-                isCompressed = true
+        for (key, value) in map {
+            let fileURL = fileURL(forKey: key)
+            if skipExisting && fileManager.fileExists(atPath: fileURL.path) && !isExpired(fileURL, now: now) {
+                continue
             }
-        } else {
-            finalData = valueData
+            
+            try writeEntry(key: key, value: value, ttl: ttl, shouldCompress: self.defaultCompression, method: self.compressionMethod)
+            loaded += 1
         }
         
-        // Create cache entry
-        var cacheEntry: [String: Any] = [
-            "value": finalData.base64EncodedString(),
-            "is_compressed": isCompressed
-        ]
+        print("Loaded \(loaded) cache items")
+        invoke.resolve(loaded)
+    }
+    
+    @objc public func get(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(GetRequest.self)
         
-        // Add expiration time
-        if let ttl = ttl {
-            let expiresAt = Date().timeIntervalSince1970 + ttl
-            cacheEntry["expires_at"] = expiresAt
+        guard let finalData = readItemData(forKey: args.key) else {
+            invoke.resolveNil()
+            return
         }
         
-        // Save as JSON
-        let entryData = try JSONSerialization.data(withJSONObject: cacheEntry)
+        // Parse JSON data
+        if let jsonObject = try? JSONSerialization.jsonObject(with: finalData),
+           let jsonString = String(data: try JSONSerialization.data(withJSONObject: jsonObject), encoding: .utf8) {
+            invoke.resolveString(jsonString)
+        } else if let stringValue = String(data: finalData, encoding: .utf8) {
+            // Accept as direct string
+            invoke.resolveString(stringValue)
+        } else {
+            invoke.resolveNil()
+        }
+    }
+    
+    @objc public func getMany(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(GetManyRequest.self)
         
-        // Save the file
-        let fileURL = cacheDirectory.appendingPathComponent(key)
-        syncQueue.sync {
-            do {
-                try entryData.write(to: fileURL)
-                print("Cache item saved to \(fileURL.path)")
-            } catch {
-                print("Failed to write cache file: \(error)")
+        // Missing and expired items are reported as null rather than left out
+        var values: JsonObject = [:]
+        for key in args.keys {
+            guard let finalData = readItemData(forKey: key) else {
+                values[key] = NSNull()
+                continue
+            }
+            if let jsonObject = try? JSONSerialization.jsonObject(with: finalData, options: .fragmentsAllowed) {
+                values[key] = jsonObject
+            } else {
+                values[key] = String(data: finalData, encoding: .utf8) ?? NSNull()
             }
         }
-        
-        invoke.resolve(EmptyResponse())
+        invoke.resolve(values)
     }
     
-    @objc public func get(_ invoke: Invoke) throws {
-        let args = try invoke.parseArgs(GetRequest.self)
-        let key = args.key
-        
-        let fileURL = cacheDirectory.appendingPathComponent(key)
+    // Reads the stored value of a live item, deleting the item if it has expired
+    private func readItemData(forKey key: String) -> Data? {
+        let fileURL = fileURL(forKey: key)
         
         // Check if file exists
         guard fileManager.fileExists(atPath: fileURL.path) else {
-            invoke.resolveNil()
-            return
+            return nil
         }
         
         // Read the file
@@ -229,14 +346,12 @@ class CachePlugin: Plugin {
             data = try Data(contentsOf: fileURL)
         } catch {
             print("Failed to read cache file: \(error)")
-            invoke.resolveNil()
-            return
+            return nil
         }
         
         // Parse as JSON
-        guard let entryDict = try JSONSerialization.jsonObject(with: data) as? [String: Any] else {
-            invoke.resolveNil()
-            return
+        guard let entryDict = (try? JSONSerialization.jsonObject(with: data)) as? [String: Any] else {
+            return nil
         }
         
         // Check expiration time
@@ -245,43 +360,27 @@ class CachePlugin: Plugin {
             if now > expiresAt {
                 // Item expired, delete it
                 try? fileManager.removeItem(at: fileURL)
-                invoke.resolveNil()
-                return
+                return nil
             }
         }
         
         // Extract value
         guard let valueBase64 = entryDict["value"] as? String,
               let valueData = Data(base64Encoded: valueBase64) else {
-            invoke.resolveNil()
-            return
+            return nil
         }
         
         // Check if compressed
         let isCompressed = entryDict["is_compressed"] as? Bool ?? false
-        
-        let finalData: Data
-        if isCompressed {
-            do {
-                finalData = try decompressData(valueData)
-            } catch {
-                print("Failed to decompress data: \(error)")
-                invoke.resolveNil()
-                return
-            }
-        } else {
-            finalData = valueData
+        guard isCompressed else {
+            return valueData
         }
         
-        // Parse JSON data
-        if let jsonObject = try? JSONSerialization.jsonObject(with: finalData),
-           let jsonString = String(data: try JSONSerialization.data(withJSONObject: jsonObject), encoding: .utf8) {
-            invoke.resolveString(jsonString)
-        } else if let stringValue = String(data: finalData, encoding: .utf8) {
-            // Accept as direct string
-            invoke.resolveString(stringValue)
-        } else {
-            invoke.resolveNil()
+        do {
+            return try decompressData(valueData)
+        } catch {
+            print("Failed to decompress data: \(error)")
+            return nil
         }
     }
     
@@ -289,7 +388,7 @@ class CachePlugin: Plugin {
         let args = try invoke.parseArgs(HasRequest.self)
         let key = args.key
         
-        let fileURL = cacheDirectory.appendingPathComponent(key)
+        let fileURL = fileURL(forKey: key)
         
         // Check if file exists
         guard fileManager.fileExists(atPath: fileURL.path) else {
@@ -327,11 +426,12 @@ class CachePlugin: Plugin {
         let args = try invoke.parseArgs(RemoveRequest.self)
         let key = args.key
         
-        let fileURL = cacheDirectory.appendingPathComponent(key)
+        let fileURL = fileURL(forKey: key)
         
         if fileManager.fileExists(atPath: fileURL.path) {
             do {
                 try fileManager.removeItem(at: fileURL)
+                recordWrite()
                 print("Cache item removed: \(key)")
             } catch {
                 print("Failed to remove cache item: \(error)")
@@ -341,12 +441,132 @@ class CachePlugin: Plugin {
         invoke.resolve(EmptyResponse())
     }
     
+    @objc public func rename(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(RenameRequest.self)
+        
+        let fromURL = fileURL(forKey: args.from)
+        let toURL = fileURL(forKey: args.to)
+        let now = Date().timeIntervalSince1970
+        
+        // Nothing to move
+        guard fileManager.fileExists(atPath: fromURL.path), !isExpired(fromURL, now: now) else {
+            invoke.resolve(BooleanResponse(value: false))
+            return
+        }
+        
+        // Already under that key; moving it would delete it
+        if fromURL == toURL {
+            invoke.resolve(BooleanResponse(value: true))
+            return
+        }
+        
+        if fileManager.fileExists(atPath: toURL.path) && !args.overwrite && !isExpired(toURL, now: now) {
+            invoke.reject("Key already exists: \(args.to)")
+            return
+        }
+        
+        do {
+            // The entry records its key, so rewrite it under the new key. An atomic write swaps the
+            // file in place so readers see either the old or the new item
+            let data = try Data(contentsOf: fromURL)
+            guard let renamed = try CacheFileNames.entry(data, withKey: args.to) else {
+                invoke.resolve(BooleanResponse(value: false))
+                return
+            }
+            try renamed.write(to: toURL, options: .atomic)
+            try fileManager.removeItem(at: fromURL)
+            recordWrite()
+            print("Cache item renamed: \(args.from) -> \(args.to)")
+            invoke.resolve(BooleanResponse(value: true))
+        } catch {
+            invoke.reject("Failed to rename item: \(error)")
+        }
+    }
+    
+    @objc public func setExpiry(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(SetExpiryRequest.self)
+        
+        let fileURL = fileURL(forKey: args.key)
+        let now = Date().timeIntervalSince1970
+        
+        guard fileManager.fileExists(atPath: fileURL.path), !isExpired(fileURL, now: now) else {
+            invoke.resolve(BooleanResponse(value: false))
+            return
+        }
+        
+        do {
+            // Only the expiration time changes, the stored value is written back as is
+            let data = try Data(contentsOf: fileURL)
+            guard var entryDict = try JSONSerialization.jsonObject(with: data) as? [String: Any] else {
+                invoke.resolve(BooleanResponse(value: false))
+                return
+            }
+            if let ttl = args.ttl {
+                entryDict["expires_at"] = now + ttl
+            } else {
+                entryDict.removeValue(forKey: "expires_at")
+            }
+            try JSONSerialization.data(withJSONObject: entryDict).write(to: fileURL, options: .atomic)
+            recordWrite()
+            invoke.resolve(BooleanResponse(value: true))
+        } catch {
+            invoke.reject("Failed to update expiration: \(error)")
+        }
+    }
+    
+    @objc public func rateLimit(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(RateLimitRequest.self)
+        
+        // Commands can run concurrently, so the read and increment happen under a lock
+        rateLimitLock.lock()
+        defer { rateLimitLock.unlock() }
+        
+        let fileURL = fileURL(forKey: args.key)
+        let now = Date().timeIntervalSince1970
+        
+        // Continue the current window, or start a new one
+        var count = 0
+        var expiresAt = now + args.windowSecs
+        if fileManager.fileExists(atPath: fileURL.path) && !isExpired(fileURL, now: now),
+           let data = try? Data(contentsOf: fileURL),
+           let entryDict = (try? JSONSerialization.jsonObject(with: data)) as? [String: Any],
+           let storedExpiresAt = entryDict["expires_at"] as? TimeInterval,
+           !(entryDict["is_compressed"] as? Bool ?? false),
+           let valueBase64 = entryDict["value"] as? String,
+           let valueData = Data(base64Encoded: valueBase64),
+           let valueString = String(data: valueData, encoding: .utf8) {
+            count = Int(valueString) ?? 0
+            expiresAt = storedExpiresAt
+        }
+        
+        let allowed = count < args.max
+        if allowed {
+            count += 1
+            let cacheEntry: [String: Any] = [
+                "key": args.key,
+                "value": Data(String(count).utf8).base64EncodedString(),
+                "is_compressed": false,
+                "expires_at": expiresAt
+            ]
+            do {
+                try JSONSerialization.data(withJSONObject: cacheEntry).write(to: fileURL, options: .atomic)
+                recordWrite()
+            } catch {
+                invoke.reject("Failed to apply rate limit: \(error)")
+                return
+            }
+        }
+        
+        invoke.resolve(RateLimitResponse(allowed: allowed, remaining: allowed ? args.max - count : 0, resetAt: UInt64(expiresAt)))
+    }
+    
     @objc public func clear(_ invoke: Invoke) throws {
         do {
             let contents = try fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)
             for fileURL in contents {
                 try fileManager.removeItem(at: fileURL)
             }
+            recordWrite()
             print("Removed \(contents.count) cache items")
         } catch {
             print("Failed to clear cache: \(error)")
@@ -355,40 +575,313 @@ class CachePlugin: Plugin {
         invoke.resolve(EmptyResponse())
     }
     
-    @objc public func stats(_ invoke: Invoke) throws {
-        var totalSize = 0
-        var activeSize = 0
-        let now = Date().timeIntervalSince1970
+    @objc public func clearPrefix(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(ClearPrefixRequest.self)
+        var removed = 0
         
         do {
             let contents = try fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)
-            totalSize = contents.count
-            
+            for fileURL in contents where key(forFile: fileURL).hasPrefix(args.prefix) {
+                try fileManager.removeItem(at: fileURL)
+                removed += 1
+            }
+            if removed > 0 {
+                recordWrite()
+            }
+            print("Removed \(removed) cache items under \(args.prefix)")
+        } catch {
+            print("Failed to clear cache items: \(error)")
+        }
+        
+        invoke.resolve(removed)
+    }
+    
+    @objc public func clearOlderThan(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(ClearOlderThanRequest.self)
+        
+        // Files are rewritten on every set, so their modification date is the date the item was stored
+        let cutoff = Date().addingTimeInterval(-args.ageSecs)
+        var removed = 0
+        
+        do {
+            let contents = try fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: [.contentModificationDateKey])
             for fileURL in contents {
-                do {
-                    let data = try Data(contentsOf: fileURL)
-                    if let entryDict = try JSONSerialization.jsonObject(with: data) as? [String: Any] {
-                        if let expiresAt = entryDict["expires_at"] as? TimeInterval {
-                            if now <= expiresAt {
-                                activeSize += 1
-                            }
-                        } else {
-                            activeSize += 1
-                        }
-                    }
-                } catch {
-                    // Could not read file, skip
+                let modified = try? fileURL.resourceValues(forKeys: [.contentModificationDateKey]).contentModificationDate
+                if let modified = modified, modified < cutoff {
+                    try fileManager.removeItem(at: fileURL)
+                    removed += 1
                 }
             }
+            if removed > 0 {
+                recordWrite()
+            }
+            print("Removed \(removed) old cache items")
+        } catch {
+            print("Failed to clear old cache items: \(error)")
+        }
+        
+        invoke.resolve(removed)
+    }
+    
+    @objc public func stats(_ invoke: Invoke) throws {
+        var stats = CacheStats()
+        
+        do {
+            let contents = try fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)
+            stats = countEntries(contents)
         } catch {
             print("Failed to get stats: \(error)")
         }
         
-        invoke.resolve(CacheStats(totalSize: totalSize, activeSize: activeSize))
+        let (bytesIn, bytesOut) = syncQueue.sync { (compressedBytesIn, compressedBytesOut) }
+        stats.compressionRatio = bytesIn == 0 ? 0 : Double(bytesOut) / Double(bytesIn)
+        stats.bytesSaved = max(bytesIn - bytesOut, 0)
+        
+        invoke.resolve(stats)
+    }
+    
+    @objc public func size(_ invoke: Invoke) throws {
+        // Every item has its own file, so counting them needs no parsing
+        let contents = (try? fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)) ?? []
+        invoke.resolve(contents.count)
+    }
+    
+    @objc public func activeSize(_ invoke: Invoke) throws {
+        let contents = (try? fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)) ?? []
+        invoke.resolve(countEntries(contents).activeSize)
+    }
+    
+    @objc public func changeToken(_ invoke: Invoke) throws {
+        let (token, modified) = syncQueue.sync { (currentChangeToken, lastModified) }
+        invoke.resolve(ChangeTokenResponse(token: token, lastModified: modified))
+    }
+    
+    @objc public func ttl(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(TtlRequest.self)
+        let fileURL = fileURL(forKey: args.key)
+        
+        guard let data = try? Data(contentsOf: fileURL),
+              let entryDict = (try? JSONSerialization.jsonObject(with: data)) as? [String: Any] else {
+            invoke.resolve(["status": "missing"])
+            return
+        }
+        
+        guard let expiresAt = entryDict["expires_at"] as? TimeInterval else {
+            invoke.resolve(["status": "noExpiry"])
+            return
+        }
+        
+        let remaining = expiresAt - Date().timeIntervalSince1970
+        if remaining < 0 {
+            // Item expired, delete it
+            try? fileManager.removeItem(at: fileURL)
+            invoke.resolve(["status": "missing"])
+        } else {
+            invoke.resolve(["status": "expiresIn", "secs": Int(remaining)])
+        }
+    }
+    
+    @objc public func cachePath(_ invoke: Invoke) throws {
+        let path = cacheDirectory.path
+        invoke.resolve(CachePathResponse(dir: path, file: path, exists: fileManager.fileExists(atPath: path)))
+    }
+    
+    @objc public func resetStats(_ invoke: Invoke) throws {
+        syncQueue.sync {
+            compressedBytesIn = 0
+            compressedBytesOut = 0
+        }
+        invoke.resolve(EmptyResponse())
+    }
+    
+    @objc public func statsPrefix(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(StatsPrefixRequest.self)
+        var stats = CacheStats()
+        
+        do {
+            let contents = try fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)
+            let matching = contents.filter { key(forFile: $0).hasPrefix(args.prefix) }
+            stats = countEntries(matching)
+        } catch {
+            print("Failed to get stats for prefix \(args.prefix): \(error)")
+        }
+        
+        invoke.resolve(stats)
+    }
+    
+    @objc public func keys(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(KeysRequest.self)
+        let prefix = args.prefix ?? ""
+        let now = Date().timeIntervalSince1970
+        var keys: [String] = []
+        
+        do {
+            let contents = try fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)
+            keys = contents
+                .filter { key(forFile: $0).hasPrefix(prefix) && !isExpired($0, now: now) }
+                .map { key(forFile: $0) }
+                .sorted()
+        } catch {
+            print("Failed to list cache keys: \(error)")
+        }
+        
+        invoke.resolve(KeysPageResponse(keys: keys, total: keys.count))
+    }
+    
+    @objc public func keysPage(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(KeysPageRequest.self)
+        let now = Date().timeIntervalSince1970
+        var keys: [String] = []
+        
+        do {
+            let contents = try fileManager.contentsOfDirectory(at: cacheDirectory, includingPropertiesForKeys: nil)
+            
+            // Sort the keys so pages stay consistent between calls
+            keys = contents
+                .filter { args.includeExpired || !isExpired($0, now: now) }
+                .map { key(forFile: $0) }
+                .sorted()
+        } catch {
+            print("Failed to list cache keys: \(error)")
+        }
+        
+        let page = Array(keys.dropFirst(args.offset).prefix(args.limit))
+        invoke.resolve(KeysPageResponse(keys: page, total: keys.count))
     }
     
     // MARK: - Helper Methods
     
+    // Build a cache entry for the value and save it to the key's file
+    private func writeEntry(key: String, value: Any?, ttl: TimeInterval?, shouldCompress: Bool, method: String) throws {
+        // Store as JSON data
+        let valueData: Data
+        if let value = value {
+            valueData = try JSONSerialization.data(withJSONObject: value)
+        } else {
+            valueData = "null".data(using: .utf8)!
+        }
+        
+        // Apply compression
+        let finalData: Data
+        var isCompressed = false
+        
+        if shouldCompress && valueData.count > compressionThreshold {
+            // Choose compression method
+            if method.lowercased() == "lzma2" {
+                finalData = compressWithLZMA2(data: valueData)
+                isCompressed = true
+            } else {
+                finalData = compressWithZlib(data: valueData)
+                isCompressed = true
+            }
+            
+            syncQueue.sync {
+                compressedBytesIn += valueData.count
+                compressedBytesOut += finalData.count
+            }
+        } else {
+            finalData = valueData
+        }
+        
+        // Create cache entry, recording the key since the file name may not reveal it
+        var cacheEntry: [String: Any] = [
+            "key": key,
+            "value": finalData.base64EncodedString(),
+            "is_compressed": isCompressed
+        ]
+        
+        // Add expiration time
+        if let ttl = ttl {
+            let expiresAt = Date().timeIntervalSince1970 + ttl
+            cacheEntry["expires_at"] = expiresAt
+        }
+        
+        // Save as JSON
+        let entryData = try JSONSerialization.data(withJSONObject: cacheEntry)
+        
+        // Save the file
+        let fileURL = fileURL(forKey: key)
+        syncQueue.sync {
+            do {
+                try entryData.write(to: fileURL)
+                print("Cache item saved to \(fileURL.path)")
+            } catch {
+                print("Failed to write cache file: \(error)")
+            }
+        }
+        recordWrite()
+    }
+    
+    // Get the file holding the entry for a key
+    private func fileURL(forKey key: String) -> URL {
+        return CacheFileNames.fileURL(forKey: key, in: cacheDirectory)
+    }
+    
+    // Get the key of the entry stored in a cache file, reversing fileURL(forKey:)
+    private func key(forFile fileURL: URL) -> String {
+        return CacheFileNames.key(forFile: fileURL)
+    }
+    
+    // Record a write to the cache directory
+    private func recordWrite() {
+        let now = Date().timeIntervalSince1970
+        syncQueue.sync {
+            currentChangeToken = max(currentChangeToken + 1, UInt64(now * 1000))
+            lastModified = UInt64(now)
+        }
+    }
+    
+    // Check whether a cache file holds an expired entry
+    private func isExpired(_ fileURL: URL, now: TimeInterval) -> Bool {
+        guard let data = try? Data(contentsOf: fileURL),
+              let entryDict = (try? JSONSerialization.jsonObject(with: data)) as? [String: Any],
+              let expiresAt = entryDict["expires_at"] as? TimeInterval else {
+            return false
+        }
+        return now > expiresAt
+    }
+    
+    // Count total and active (non-expired) entries among the given cache files, and their sizes by compression
+    private func countEntries(_ files: [URL]) -> CacheStats {
+        let stats = CacheStats()
+        stats.totalSize = files.count
+        let now = Date().timeIntervalSince1970
+        
+        for fileURL in files {
+            do {
+                let data = try Data(contentsOf: fileURL)
+                stats.totalBytesOnDisk += data.count
+                if let entryDict = try JSONSerialization.jsonObject(with: data) as? [String: Any] {
+                    if let expiresAt = entryDict["expires_at"] as? TimeInterval {
+                        if now <= expiresAt {
+                            stats.activeSize += 1
+                        }
+                    } else {
+                        stats.activeSize += 1
+                    }
+                    
+                    // Break the stored values down by compression
+                    let storedBytes = (entryDict["value"] as? String)?.utf8.count ?? 0
+                    if entryDict["is_compressed"] as? Bool ?? false {
+                        stats.compressedCount += 1
+                        stats.compressedBytes += storedBytes
+                        if let encoded = entryDict["value"] as? String, let payload = Data(base64Encoded: encoded) {
+                            stats.decompressedBytes += (try? decompressData(payload))?.count ?? storedBytes
+                        }
+                    } else {
+                        stats.uncompressedCount += 1
+                        stats.uncompressedBytes += storedBytes
+                        stats.decompressedBytes += storedBytes
+                    }
+                }
+            } catch {
+                // Could not read file, skip
+            }
+        }
+        
+        return stats
+    }
+    
     // Compression with Zlib
     private func compressWithZlib(data: Data) -> Data {
         var compressedData = Data()
@@ -0,0 +1,81 @@
+import XCTest
+@testable import tauri_plugin_cache
+
+final class CacheFileNamesTests: XCTestCase {
+    private var directory: URL!
+    
+    private let keys = [
+        "plain-key_1.txt",
+        "user/42/profile",
+        "../../etc/passwd",
+        "..",
+        ".",
+        "thumb:1:small",
+        "thumb_1_small",
+        "ключ",
+        "日本語🔑",
+        String(repeating: "a", count: 100),
+        String(repeating: "a", count: 101),
+        String(repeating: "a", count: 500),
+    ]
+    
+    override func setUpWithError() throws {
+        directory = FileManager.default.temporaryDirectory
+            .appendingPathComponent(UUID().uuidString, isDirectory: true)
+        try FileManager.default.createDirectory(at: directory, withIntermediateDirectories: true)
+    }
+    
+    override func tearDownWithError() throws {
+        try FileManager.default.removeItem(at: directory)
+    }
+    
+    // Write an entry the way the plugin does, recording its key
+    private func store(_ key: String) throws -> URL {
+        let fileURL = CacheFileNames.fileURL(forKey: key, in: directory)
+        let entry: [String: Any] = ["key": key, "value": "\"v\""]
+        try JSONSerialization.data(withJSONObject: entry).write(to: fileURL)
+        return fileURL
+    }
+    
+    func testEveryKeyMapsToAValidFileInTheCacheDirectory() {
+        let valid = try! NSRegularExpression(pattern: "^[A-Za-z0-9._~-]{1,100}$")
+        for key in keys {
+            let fileURL = CacheFileNames.fileURL(forKey: key, in: directory)
+            let name = fileURL.lastPathComponent
+            XCTAssertEqual(fileURL.deletingLastPathComponent().standardizedFileURL, directory.standardizedFileURL, key)
+            XCTAssertFalse(name == "." || name == "..", key)
+            XCTAssertNotNil(valid.firstMatch(in: name, range: NSRange(name.startIndex..., in: name)), key)
+        }
+    }
+    
+    func testKeysMapToUniqueFiles() {
+        let names = Set(keys.map { CacheFileNames.fileURL(forKey: $0, in: directory).lastPathComponent })
+        XCTAssertEqual(names.count, keys.count)
+    }
+    
+    func testPlainKeysKeepTheirFileNames() {
+        XCTAssertEqual(CacheFileNames.fileURL(forKey: "plain-key_1.txt", in: directory).lastPathComponent, "plain-key_1.txt")
+        let long = String(repeating: "a", count: 100)
+        XCTAssertEqual(CacheFileNames.fileURL(forKey: long, in: directory).lastPathComponent, long)
+    }
+    
+    func testKeyForFileReversesFileURLForKey() throws {
+        for key in keys {
+            XCTAssertEqual(CacheFileNames.key(forFile: try store(key)), key)
+        }
+        let files = try FileManager.default.contentsOfDirectory(atPath: directory.path)
+        XCTAssertEqual(files.count, keys.count)
+    }
+    
+    func testRenamedEntriesRecordTheNewKey() throws {
+        let from = try store("draft/1")
+        
+        let to = CacheFileNames.fileURL(forKey: "final:1", in: directory)
+        let renamed = try CacheFileNames.entry(Data(contentsOf: from), withKey: "final:1")
+        try XCTUnwrap(renamed).write(to: to)
+        
+        XCTAssertEqual(CacheFileNames.key(forFile: to), "final:1")
+        let entry = try JSONSerialization.jsonObject(with: Data(contentsOf: to)) as? [String: Any]
+        XCTAssertEqual(entry?["value"] as? String, "\"v\"")
+    }
+}
@@ -1,20 +1,182 @@
-use tauri::{command, AppHandle, Runtime};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Runtime, State};
 
 use crate::models::*;
 use crate::CacheExt;
-use crate::Result;
+use crate::{Error, Result};
+
+/// Key prefixes the frontend can read but not modify
+pub(crate) struct ReadOnlyPrefixes(pub(crate) Vec<String>);
+
+impl ReadOnlyPrefixes {
+    /// Fail if the key falls under a read-only prefix
+    fn check(&self, key: &str) -> Result<()> {
+        if self.0.iter().any(|prefix| key.starts_with(prefix.as_str())) {
+            return Err(Error::ReadOnly(key.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Fail if any key under the prefix could be read-only
+    fn check_prefix(&self, prefix: &str) -> Result<()> {
+        if self
+            .0
+            .iter()
+            .any(|readonly| prefix.starts_with(readonly.as_str()) || readonly.starts_with(prefix))
+        {
+            return Err(Error::ReadOnly(format!("{}*", prefix)));
+        }
+        Ok(())
+    }
+
+    /// Fail if any prefix is read-only, for commands that modify keys they aren't given
+    fn check_all(&self) -> Result<()> {
+        match self.0.first() {
+            Some(prefix) => Err(Error::ReadOnly(format!("{}*", prefix))),
+            None => Ok(()),
+        }
+    }
+}
 
 /// Set a value in the cache with optional TTL
 #[command]
 pub(crate) async fn set<R: Runtime>(
     app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
     key: String,
     value: serde_json::Value,
     options: Option<SetItemOptions>,
 ) -> Result<EmptyResponse> {
+    readonly.check(&key)?;
     app.cache().set(key, value, options)
 }
 
+/// Get a value, storing the given one first if the key is missing or expired
+#[command]
+pub(crate) async fn get_or_set<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+    value: serde_json::Value,
+    options: Option<SetItemOptions>,
+) -> Result<serde_json::Value> {
+    readonly.check(&key)?;
+    app.cache().get_or_set(key, value, options)
+}
+
+/// Store a value only if the key is missing or expired, returning whether it was stored
+#[command]
+pub(crate) async fn set_if_absent<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+    value: serde_json::Value,
+    options: Option<SetItemOptions>,
+) -> Result<BooleanResponse> {
+    readonly.check(&key)?;
+    app.cache().set_if_absent(key, value, options)
+}
+
+/// Store a value only if the key holds a live item, returning whether it was stored
+#[command]
+pub(crate) async fn replace<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+    value: serde_json::Value,
+    options: Option<SetItemOptions>,
+) -> Result<BooleanResponse> {
+    readonly.check(&key)?;
+    app.cache().replace(key, value, options)
+}
+
+/// Store a value, returning the value it replaced
+#[command]
+pub(crate) async fn getset<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+    value: serde_json::Value,
+    options: Option<SetItemOptions>,
+) -> Result<Option<serde_json::Value>> {
+    readonly.check(&key)?;
+    app.cache().getset(key, value, options)
+}
+
+/// Append a value to the array stored under a key, returning the array's new length
+#[command]
+pub(crate) async fn push<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+    value: serde_json::Value,
+    options: Option<SetItemOptions>,
+) -> Result<usize> {
+    readonly.check(&key)?;
+    app.cache().push(&key, value, options)
+}
+
+/// Remove and return the last element of the array stored under a key
+#[command]
+pub(crate) async fn pop<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+) -> Result<Option<serde_json::Value>> {
+    readonly.check(&key)?;
+    app.cache().pop(&key)
+}
+
+/// Seed the cache with every key/value pair of a JSON object
+#[command]
+pub(crate) async fn load_from_json<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    map: serde_json::Map<String, serde_json::Value>,
+    default_ttl: Option<u64>,
+    skip_existing: Option<bool>,
+) -> Result<usize> {
+    for key in map.keys() {
+        readonly.check(key)?;
+    }
+    app.cache()
+        .load_from_json(map, default_ttl, skip_existing.unwrap_or(false))
+}
+
+/// Store every value with the same TTL in a single write
+#[command]
+pub(crate) async fn set_all<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    values: HashMap<String, serde_json::Value>,
+    ttl: Option<u64>,
+) -> Result<EmptyResponse> {
+    for key in values.keys() {
+        readonly.check(key)?;
+    }
+    app.cache().set_all(values, ttl)?;
+    Ok(EmptyResponse::default())
+}
+
+/// Store several values in a single write, each with its own options
+#[command]
+pub(crate) async fn set_many<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    items: Vec<SetManyItem>,
+) -> Result<EmptyResponse> {
+    for item in &items {
+        readonly.check(&item.key)?;
+    }
+    app.cache().set_many(
+        items
+            .into_iter()
+            .map(|item| (item.key, item.value, item.options))
+            .collect(),
+    )
+}
+
 /// Get a value from the cache by key
 #[command]
 pub(crate) async fn get<R: Runtime>(
@@ -24,6 +186,73 @@ pub(crate) async fn get<R: Runtime>(
     app.cache().get(&key)
 }
 
+/// Get several values from the cache at once
+#[command]
+pub(crate) async fn get_many<R: Runtime>(
+    app: AppHandle<R>,
+    keys: Vec<String>,
+) -> Result<HashMap<String, Option<serde_json::Value>>> {
+    app.cache().get_many(&keys)
+}
+
+/// Get a value from the cache along with where it was found
+#[command]
+pub(crate) async fn get_with_source<R: Runtime>(
+    app: AppHandle<R>,
+    key: String,
+) -> Result<Option<SourcedValue>> {
+    Ok(app
+        .cache()
+        .get_with_source(&key)?
+        .map(|(value, source)| SourcedValue { value, source }))
+}
+
+/// Get a value, or an expired one still within its stale window, flagged as stale
+#[command]
+pub(crate) async fn get_stale<R: Runtime>(
+    app: AppHandle<R>,
+    key: String,
+) -> Result<Option<StaleValue>> {
+    Ok(app
+        .cache()
+        .get_stale(&key)?
+        .map(|(value, stale)| StaleValue { value, stale }))
+}
+
+/// Get a value from the cache along with its expiration time, compression and metadata
+#[command]
+pub(crate) async fn get_item<R: Runtime>(
+    app: AppHandle<R>,
+    key: String,
+) -> Result<Option<CacheItem<serde_json::Value>>> {
+    app.cache().get_item(&key)
+}
+
+/// Get a value from the cache unless the caller already holds its current version
+#[command]
+pub(crate) async fn get_if_changed<R: Runtime>(
+    app: AppHandle<R>,
+    key: String,
+    since: Option<u64>,
+) -> Result<Option<GetConditional>> {
+    app.cache().get_if_changed(&key, since)
+}
+
+/// Get the JSON type of a stored value without transferring the value
+#[command]
+pub(crate) async fn value_type<R: Runtime>(
+    app: AppHandle<R>,
+    key: String,
+) -> Result<Option<String>> {
+    app.cache().value_type(&key)
+}
+
+/// Get the time left until an item expires
+#[command]
+pub(crate) async fn ttl<R: Runtime>(app: AppHandle<R>, key: String) -> Result<Ttl> {
+    app.cache().ttl(&key)
+}
+
 /// Check if a key exists in the cache and is not expired
 #[command]
 pub(crate) async fn has<R: Runtime>(app: AppHandle<R>, key: String) -> Result<BooleanResponse> {
@@ -32,31 +261,301 @@ pub(crate) async fn has<R: Runtime>(app: AppHandle<R>, key: String) -> Result<Bo
 
 /// Remove a value from the cache
 #[command]
-pub(crate) async fn remove<R: Runtime>(app: AppHandle<R>, key: String) -> Result<EmptyResponse> {
+pub(crate) async fn remove<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+) -> Result<EmptyResponse> {
+    readonly.check(&key)?;
     app.cache().remove(&key)
 }
 
+/// Remove several values from the cache
+#[command]
+pub(crate) async fn remove_many<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    keys: Vec<String>,
+) -> Result<EmptyResponse> {
+    for key in &keys {
+        readonly.check(key)?;
+    }
+    app.cache().remove_many(&keys)
+}
+
+/// Set an item to expire after a new TTL without rewriting its value
+#[command]
+pub(crate) async fn expire<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+    ttl: u64,
+) -> Result<BooleanResponse> {
+    readonly.check(&key)?;
+    app.cache().expire(&key, ttl)
+}
+
+/// Keep an item until it's removed
+#[command]
+pub(crate) async fn persist<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+) -> Result<BooleanResponse> {
+    readonly.check(&key)?;
+    app.cache().persist(&key)
+}
+
+/// Move a value to another key
+#[command]
+pub(crate) async fn rename<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    from: String,
+    to: String,
+    overwrite: Option<bool>,
+) -> Result<BooleanResponse> {
+    readonly.check(&from)?;
+    readonly.check(&to)?;
+    app.cache().rename(&from, &to, overwrite.unwrap_or(false))
+}
+
+/// Count a call against a fixed-window rate limit
+#[command]
+pub(crate) async fn rate_limit<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    key: String,
+    max: u32,
+    window_secs: u64,
+) -> Result<RateLimitResult> {
+    readonly.check(&key)?;
+    app.cache().rate_limit(&key, max, window_secs)
+}
+
+/// Move the cache file to a new path, optionally copying the current items
+#[command]
+pub(crate) async fn relocate<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    copy_existing: Option<bool>,
+) -> Result<EmptyResponse> {
+    app.cache()
+        .relocate(PathBuf::from(path), copy_existing.unwrap_or(true))?;
+    Ok(EmptyResponse::default())
+}
+
+/// Write every item to a JSON file, for backups or debugging
+#[command]
+pub(crate) async fn export<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    include_expired: Option<bool>,
+) -> Result<EmptyResponse> {
+    app.cache()
+        .export(PathBuf::from(path), include_expired.unwrap_or(false))
+}
+
+/// Store the items of a file written by `export`, returning how many were stored
+#[command]
+pub(crate) async fn import<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<usize> {
+    readonly.check_all()?;
+    app.cache()
+        .import(PathBuf::from(path), overwrite.unwrap_or(false))
+}
+
 /// Clear all values from the cache
 #[command]
-pub(crate) async fn clear<R: Runtime>(app: AppHandle<R>) -> Result<EmptyResponse> {
+pub(crate) async fn clear<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+) -> Result<EmptyResponse> {
+    readonly.check_all()?;
     app.cache().clear()
 }
 
+/// Clear all values stored more than the given number of seconds ago
+#[command]
+pub(crate) async fn clear_older_than<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    age_secs: u64,
+) -> Result<usize> {
+    readonly.check_all()?;
+    app.cache().clear_older_than(age_secs)
+}
+
+/// Clear all values whose keys start with the prefix
+#[command]
+pub(crate) async fn clear_prefix<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    prefix: String,
+) -> Result<usize> {
+    readonly.check_prefix(&prefix)?;
+    app.cache().clear_prefix(&prefix)
+}
+
+/// Remove all values whose keys match a glob pattern
+#[command]
+pub(crate) async fn remove_matching<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    pattern: String,
+) -> Result<usize> {
+    // A pattern can match keys under any prefix
+    readonly.check_all()?;
+    app.cache().remove_matching(&pattern)
+}
+
+/// Clear all values stored with the tag
+#[command]
+pub(crate) async fn invalidate_tag<R: Runtime>(
+    app: AppHandle<R>,
+    readonly: State<'_, ReadOnlyPrefixes>,
+    tag: String,
+) -> Result<usize> {
+    // Tagged items can fall under any prefix
+    readonly.check_all()?;
+    app.cache().invalidate_tag(&tag)
+}
+
+/// Find the first key holding a value
+#[command]
+pub(crate) async fn find_key_by_value<R: Runtime>(
+    app: AppHandle<R>,
+    value: serde_json::Value,
+) -> Result<Option<String>> {
+    app.cache().find_key_by_value(&value)
+}
+
+/// Change how often the background cleanup runs, in seconds; 0 stops it
+#[command]
+pub(crate) async fn set_cleanup_interval<R: Runtime>(
+    app: AppHandle<R>,
+    secs: u64,
+) -> Result<EmptyResponse> {
+    app.cache().set_cleanup_interval(secs);
+    Ok(EmptyResponse::default())
+}
+
+/// Remove expired items right away instead of waiting for the background cleanup
+#[command]
+pub(crate) async fn cleanup<R: Runtime>(app: AppHandle<R>) -> Result<usize> {
+    app.cache().cleanup()
+}
+
+/// Remove expired items right away, reporting how many were removed and how many are left
+#[command]
+pub(crate) async fn prune<R: Runtime>(app: AppHandle<R>) -> Result<PruneResult> {
+    app.cache().prune()
+}
+
+/// Get the total number of items in the cache
+#[command]
+pub(crate) async fn size<R: Runtime>(app: AppHandle<R>) -> Result<usize> {
+    app.cache().size()
+}
+
+/// Get the number of non-expired items in the cache
+#[command]
+pub(crate) async fn active_size<R: Runtime>(app: AppHandle<R>) -> Result<usize> {
+    app.cache().active_size()
+}
+
 /// Get cache statistics
 #[command]
 pub(crate) async fn stats<R: Runtime>(app: AppHandle<R>) -> Result<CacheStats> {
-    #[cfg(desktop)]
-    {
-        let total_size = app.cache().size()?;
-        let active_size = app.cache().active_size()?;
-        Ok(CacheStats {
-            total_size,
-            active_size,
-        })
-    }
+    app.cache().stats()
+}
 
-    #[cfg(mobile)]
-    {
-        app.cache().stats()
-    }
+/// Get a token that changes whenever the cache is written
+#[command]
+pub(crate) async fn change_token<R: Runtime>(app: AppHandle<R>) -> Result<ChangeToken> {
+    Ok(ChangeToken {
+        token: app.cache().change_token()?,
+        last_modified: app.cache().last_modified()?,
+    })
+}
+
+/// Get where the cache is stored on disk
+#[command]
+pub(crate) async fn cache_path<R: Runtime>(app: AppHandle<R>) -> Result<CachePath> {
+    app.cache().cache_path()
+}
+
+/// Reset the compression metrics reported by `stats`
+#[command]
+pub(crate) async fn reset_stats<R: Runtime>(app: AppHandle<R>) -> Result<EmptyResponse> {
+    app.cache().reset_stats()
+}
+
+/// Get cache statistics for keys starting with a prefix
+#[command]
+pub(crate) async fn stats_prefix<R: Runtime>(
+    app: AppHandle<R>,
+    prefix: String,
+) -> Result<CacheStats> {
+    app.cache().stats_prefix(&prefix)
+}
+
+/// Change the compression settings used for new writes
+#[command]
+pub(crate) async fn reconfigure<R: Runtime>(
+    app: AppHandle<R>,
+    default_compression: bool,
+    compression_level: Option<u32>,
+    compression_threshold: Option<usize>,
+    compression_method: Option<CompressionMethod>,
+) -> Result<EmptyResponse> {
+    app.cache().reconfigure(
+        default_compression,
+        compression_level,
+        compression_threshold,
+        compression_method,
+    )?;
+    Ok(EmptyResponse::default())
+}
+
+/// List the keys of all live items, optionally only those under a prefix
+#[command]
+pub(crate) async fn keys<R: Runtime>(
+    app: AppHandle<R>,
+    prefix: Option<String>,
+) -> Result<Vec<String>> {
+    app.cache().keys(prefix.as_deref())
+}
+
+/// List the keys of all live items matching a glob pattern
+#[command]
+pub(crate) async fn keys_matching<R: Runtime>(
+    app: AppHandle<R>,
+    pattern: String,
+) -> Result<Vec<String>> {
+    app.cache().keys_matching(&pattern)
+}
+
+/// Get a page of keys in lexicographic order
+#[command]
+pub(crate) async fn keys_page<R: Runtime>(
+    app: AppHandle<R>,
+    offset: usize,
+    limit: usize,
+    include_expired: Option<bool>,
+) -> Result<KeysPage> {
+    let (keys, total) = app
+        .cache()
+        .keys_page(offset, limit, include_expired.unwrap_or(false))?;
+    Ok(KeysPage { keys, total })
+}
+
+/// Make sure every write so far has reached the disk, e.g. before the app exits
+#[command]
+pub(crate) async fn flush<R: Runtime>(app: AppHandle<R>) -> Result<EmptyResponse> {
+    app.cache().flush()
 }
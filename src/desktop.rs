@@ -1,680 +1,5974 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
 use base64::{engine::general_purpose::STANDARD, Engine as _};
-use flate2::read::ZlibDecoder;
-use flate2::write::ZlibEncoder;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
 use flate2::Compression;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{self, BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tauri::{plugin::PluginApi, AppHandle, Runtime};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{plugin::PluginApi, AppHandle, Emitter, Runtime};
 use xz2::read::XzDecoder;
 use xz2::write::XzEncoder;
 
+use crate::glob;
 use crate::models::*;
-use crate::Error;
+use crate::schema::SchemaRegistry;
+use crate::{check_key, BackingStore, Clock, Error, SystemClock};
 
 // Define a type alias for the complex cache value type
-type CacheValueEntry = (serde_json::Value, Option<u64>);
 type CacheValueMap = HashMap<String, CacheValueEntry>;
 type ThreadSafeCacheMap = Arc<Mutex<CacheValueMap>>;
+// Holds the items in place of the cache file when the cache isn't persisted, or with write-behind
+// until they're written to it
+type MemoryStore = Arc<Mutex<HashMap<String, CacheEntry>>>;
+type JitterRng = Box<dyn FnMut() -> u64 + Send>;
+type SharedClock = Arc<Mutex<Arc<dyn Clock>>>;
 
-// Store the value and its optional expiry time in a single struct for better organization
-#[derive(Clone, Serialize, Deserialize)]
-struct CacheEntry {
+// Locks are taken with `unwrap_or_else(PoisonError::into_inner)`: a thread that panicked while
+// holding one can't leave the file half-written, since writes replace it in a single rename, and
+// at worst leaves an in-memory copy stale, so later calls carry on rather than panic too
+
+// Suffixes of the write-ahead log, of a checkpoint in progress and of a rewrite in progress,
+// appended to the cache file name
+const WAL_SUFFIX: &str = ".wal";
+const CHECKPOINT_SUFFIX: &str = ".tmp";
+const REWRITE_SUFFIX: &str = ".new";
+
+// Binary cache files start with this, followed by a byte naming the format; JSON files have no
+// header, so files written before binary formats existed are still read
+const FORMAT_MAGIC: &[u8; 3] = b"TCF";
+const FORMAT_MESSAGEPACK: u8 = 1;
+const FORMAT_CBOR: u8 = 2;
+
+// The write-ahead log is never folded into a smaller cache file until it reaches this size
+const WAL_MIN_CHECKPOINT_BYTES: u64 = 64 * 1024;
+
+// Why the cache file couldn't be read: it couldn't be accessed, or its contents are corrupt
+enum FileError {
+    Io(io::Error),
+    Invalid(String),
+}
+
+// A change appended to the write-ahead log
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum WalRecord {
+    Set { key: String, entry: Box<CacheEntry> },
+    Remove { key: String },
+}
+
+// Path of a file kept next to the cache file, named after it
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Random numbers for TTL jitter, seeded per process by the standard library's hasher keys
+fn default_jitter_rng() -> JitterRng {
+    let state = RandomState::new();
+    let mut counter = 0u64;
+    Box::new(move || {
+        counter = counter.wrapping_add(1);
+        let mut hasher = state.build_hasher();
+        hasher.write_u64(counter);
+        hasher.finish()
+    })
+}
+
+// A value found by a lookup, along with where it was found and the details stored with it
+struct StoredValue {
     value: serde_json::Value,
+    source: CacheSource,
+    version: Option<u64>,
     expires_at: Option<u64>,
     is_compressed: Option<bool>,
+    metadata: Option<serde_json::Value>,
 }
 
-// Initialize the cache with a custom configuration
-pub fn init_with_config<R: Runtime, C: DeserializeOwned>(
-    app: &AppHandle<R>,
-    _api: PluginApi<R, C>,
-    cache_file_path: PathBuf,
-    cleanup_interval: u64,
-) -> crate::Result<Cache<R>> {
-    let cache = Cache {
-        app: app.clone(),
-        cache_file_path,
-        cleanup_interval,
-        file_mutex: Arc::new(Mutex::new(())),
-        compression: CompressionConfig::default(),
-        value_cache: Arc::new(Mutex::new(HashMap::new())),
-    };
-
-    // Set up a background task to clean expired entries periodically
-    cache.start_cleanup_task();
+// Changes to the cache file made while reading from it, saved once the reads are done
+#[derive(Default)]
+struct ReadChanges {
+    // Undecodable entries were removed
+    evicted: bool,
+    // Entries were recompressed with the current settings
+    migrated: bool,
+}
 
-    Ok(cache)
+// A decoded value kept in memory along with the times needed to validate it
+#[derive(Clone)]
+struct CacheValueEntry {
+    value: serde_json::Value,
+    expires_at: Option<u64>,
+    stale_at: Option<u64>,
+    version: Option<u64>,
+    is_compressed: Option<bool>,
+    metadata: Option<serde_json::Value>,
+    last_access: u64,
 }
 
-/// Access to the cache APIs.
-#[allow(dead_code)]
-pub struct Cache<R: Runtime> {
-    app: AppHandle<R>,
-    cache_file_path: PathBuf,
-    cleanup_interval: u64,
-    file_mutex: Arc<Mutex<()>>,
-    compression: CompressionConfig,
-    value_cache: ThreadSafeCacheMap,
+// Header written before every value passed through the compressor
+//
+// Laid out as the magic bytes, format version, compression method and flags. Values
+// written before the header existed start with a two-byte marker instead (compressed
+// flag, then method), which never collides with the magic. Gzip data is stored without
+// a header so external tools can read it, and is recognized by the gzip magic instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PayloadHeader {
+    version: u8,
+    method: u8,
+    flags: u8,
 }
 
-impl<R: Runtime> Cache<R> {
-    /// Start a background task to periodically clean up expired cache entries
-    fn start_cleanup_task(&self) {
-        let file_mutex = self.file_mutex.clone();
-        let value_cache = self.value_cache.clone();
-        let interval = self.cleanup_interval;
-        let cache_file_path = self.cache_file_path.clone();
+impl PayloadHeader {
+    const MAGIC: [u8; 2] = *b"TC";
+    const VERSION: u8 = 1;
+    const LEN: usize = 5;
+    const LEGACY_LEN: usize = 2;
 
-        // Use a background thread to periodically clean up expired items
-        std::thread::spawn(move || {
-            loop {
-                std::thread::sleep(Duration::from_secs(interval));
+    const METHOD_NONE: u8 = 0;
+    const METHOD_ZLIB: u8 = 1;
+    const METHOD_LZMA2: u8 = 2;
+    const METHOD_GZIP: u8 = 3;
+    const METHOD_ZSTD: u8 = 4;
 
-                // Clean up expired entries
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
-                // Also clean up the in-memory value cache
-                {
-                    let mut cache = value_cache.lock().unwrap();
-                    let expired_keys: Vec<String> = cache
-                        .iter()
-                        .filter_map(|(key, (_, expires_at))| {
-                            if let Some(expires) = expires_at {
-                                if *expires < now {
-                                    Some(key.clone())
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
-
-                    for key in expired_keys {
-                        cache.remove(&key);
-                    }
-                }
+    const FLAG_COMPRESSED: u8 = 1;
+    const FLAG_ENCRYPTED: u8 = 2;
 
-                // Lock the file for exclusive access
-                let _guard = file_mutex.lock().unwrap();
+    // Encrypted payloads start with the AES-GCM nonce, followed by the ciphertext and tag
+    const NONCE_LEN: usize = 12;
 
-                // Read the current cache
-                let mut data: HashMap<String, CacheEntry> =
-                    match Self::read_from_file(&cache_file_path) {
-                        Ok(data) => data,
-                        Err(_) => continue, // Skip this cleanup cycle if file cannot be read
-                    };
+    /// Header for a payload in the current format version
+    fn new(method: u8, compressed: bool) -> Self {
+        Self {
+            version: Self::VERSION,
+            method,
+            flags: if compressed { Self::FLAG_COMPRESSED } else { 0 },
+        }
+    }
 
-                // Filter out expired entries
-                let expired_keys: Vec<String> = data
-                    .iter()
-                    .filter_map(|(key, entry)| {
-                        if let Some(expires_at) = entry.expires_at {
-                            if expires_at < now {
-                                Some(key.clone())
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+    fn is_compressed(&self) -> bool {
+        self.flags & Self::FLAG_COMPRESSED != 0
+    }
 
-                let mut modified = false;
-                for key in expired_keys {
-                    data.remove(&key);
-                    modified = true;
-                }
+    fn is_encrypted(&self) -> bool {
+        self.flags & Self::FLAG_ENCRYPTED != 0
+    }
 
-                // Save to file if cache was modified
-                if modified {
-                    let _ = Self::write_to_file(&cache_file_path, &data);
-                }
+    /// The header as written in front of the payload
+    fn bytes(&self) -> [u8; Self::LEN] {
+        [
+            Self::MAGIC[0],
+            Self::MAGIC[1],
+            self.version,
+            self.method,
+            self.flags,
+        ]
+    }
+
+    /// Prepend the header to a payload
+    fn wrap(&self, payload: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::LEN + payload.len());
+        result.extend_from_slice(&self.bytes());
+        result.extend_from_slice(payload);
+        result
+    }
+
+    /// Split data into its header and payload, accepting the legacy marker as version 0
+    fn parse(data: &[u8]) -> crate::Result<(Self, &[u8])> {
+        if data.starts_with(&Self::GZIP_MAGIC) {
+            return Ok((Self::new(Self::METHOD_GZIP, true), data));
+        }
+
+        if data.starts_with(&Self::MAGIC) {
+            if data.len() < Self::LEN {
+                return Err(Error::Cache("Truncated payload header".to_string()));
             }
-        });
+
+            let header = Self {
+                version: data[2],
+                method: data[3],
+                flags: data[4],
+            };
+            if header.version > Self::VERSION {
+                return Err(Error::Cache(format!(
+                    "Unsupported payload version: {}",
+                    header.version
+                )));
+            }
+            return Ok((header, &data[Self::LEN..]));
+        }
+
+        match data {
+            [compressed @ (0 | 1), method, ..] => {
+                let compressed = *compressed == 1;
+                let header = Self {
+                    version: 0,
+                    method: if compressed {
+                        *method
+                    } else {
+                        Self::METHOD_NONE
+                    },
+                    flags: if compressed { Self::FLAG_COMPRESSED } else { 0 },
+                };
+                Ok((header, &data[Self::LEGACY_LEN..]))
+            }
+            _ => Err(Error::Cache("Unrecognized payload header".to_string())),
+        }
     }
+}
 
-    /// Read cache data from file
-    fn read_from_file(path: &PathBuf) -> io::Result<HashMap<String, CacheEntry>> {
-        if !path.exists() {
-            return Ok(HashMap::new());
+// Store the value and its optional expiry time in a single struct for better organization
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct CacheEntry {
+    value: serde_json::Value,
+    expires_at: Option<u64>,
+    is_compressed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression_method: Option<CompressionMethod>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression_level: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    is_encrypted: Option<bool>,
+    // Length of the value's JSON before it was compressed or encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    original_len: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    soft_ttl: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stale_ttl: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_accessed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    access_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+}
+
+impl CacheEntry {
+    /// Whether the entry's expiration time has passed
+    fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at < now)
+    }
+
+    /// Whether the entry has expired and its stale window has passed too, so it can be removed
+    fn is_past_stale_window(&self, now: u64) -> bool {
+        matches!(
+            self.expires_at,
+            Some(expires_at) if expires_at.saturating_add(self.stale_ttl.unwrap_or(0)) < now
+        )
+    }
+
+    /// Time after which the entry is still served but should be refreshed
+    fn stale_at(&self) -> Option<u64> {
+        Some(self.created_at? + self.soft_ttl?)
+    }
+
+    /// Whether storing `other` over this entry would change nothing but its creation time
+    ///
+    /// Entries with a soft TTL never match, since storing them again resets their age.
+    fn same_contents(&self, other: &CacheEntry) -> bool {
+        self.soft_ttl.is_none()
+            && other.soft_ttl.is_none()
+            && self.value == other.value
+            && self.expires_at == other.expires_at
+            && self.stale_ttl == other.stale_ttl
+            && self.is_compressed == other.is_compressed
+            && self.compression_method == other.compression_method
+            && self.compression_level == other.compression_level
+            && self.is_encrypted == other.is_encrypted
+            && self.metadata == other.metadata
+            && self.tags == other.tags
+    }
+
+    /// Whether the entry was stored with the tag
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|t| t == tag))
+    }
+
+    /// Whether the value is stored as a base64 string of a compressed or encrypted payload
+    fn is_encoded(&self) -> bool {
+        self.is_compressed.unwrap_or(false) || self.is_encrypted.unwrap_or(false)
+    }
+
+    /// Size of the stored value in the cache file, compressed and encrypted values being base64
+    /// strings
+    fn stored_len(&self) -> u64 {
+        match &self.value {
+            serde_json::Value::String(encoded) if self.is_encoded() => encoded.len() as u64,
+            value => json_len(value),
         }
+    }
 
-        let file = fs::File::open(path)?;
-        let file_size = file.metadata()?.len();
+    /// Size of the value's JSON once decoded
+    ///
+    /// Encoded values written before their original length was recorded count at their stored size.
+    fn decoded_len(&self) -> u64 {
+        match self.original_len {
+            Some(len) if self.is_encoded() => len,
+            _ => self.stored_len(),
+        }
+    }
 
-        // For large files, use a buffered reader for better performance
-        let mut reader = BufReader::with_capacity(
-            std::cmp::min(file_size as usize, 128 * 1024), // 128KB buffer or file size
-            file,
-        );
+    /// Sort key for choosing eviction victims, lowest first
+    fn eviction_rank(&self, policy: EvictionPolicy) -> (u64, u64) {
+        let created_at = self.created_at.unwrap_or(0);
+        let last_accessed = self.last_accessed.unwrap_or(created_at);
 
-        let mut contents = String::with_capacity(file_size as usize);
-        reader.read_to_string(&mut contents)?;
+        match policy {
+            EvictionPolicy::Lru => (last_accessed, 0),
+            EvictionPolicy::Lfu => (self.access_count.unwrap_or(0), last_accessed),
+            EvictionPolicy::Fifo => (created_at, 0),
+        }
+    }
 
-        if contents.is_empty() {
-            return Ok(HashMap::new());
+    /// Build the in-memory form of this entry from its decoded value
+    fn to_memory(&self, value: serde_json::Value, now: u64) -> CacheValueEntry {
+        CacheValueEntry {
+            value,
+            expires_at: self.expires_at,
+            stale_at: self.stale_at(),
+            version: self.version,
+            is_compressed: self.is_compressed,
+            metadata: self.metadata.clone(),
+            last_access: now,
         }
+    }
+}
 
-        match serde_json::from_str(&contents) {
-            Ok(data) => Ok(data),
-            Err(_) => Ok(HashMap::new()),
+// Cache items serialized as a map in key order
+struct SortedEntries<'a>(Vec<(&'a String, &'a CacheEntry)>);
+
+impl<'a> SortedEntries<'a> {
+    fn new(data: &'a HashMap<String, CacheEntry>) -> Self {
+        let mut entries: Vec<_> = data.iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        Self(entries)
+    }
+}
+
+impl Serialize for SortedEntries<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().copied())
+    }
+}
+
+// Cumulative sizes of the values that went through compression, used to report its effectiveness
+#[derive(Default)]
+struct CompressionMetrics {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+impl CompressionMetrics {
+    /// Record a compressed write
+    fn record(&self, bytes_in: usize, bytes_out: usize) {
+        self.bytes_in.fetch_add(bytes_in as u64, Ordering::Relaxed);
+        self.bytes_out
+            .fetch_add(bytes_out as u64, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        self.bytes_in.store(0, Ordering::Relaxed);
+        self.bytes_out.store(0, Ordering::Relaxed);
+    }
+}
+
+// Outcomes of reads since the last reset, used to report how effective the cache is
+#[derive(Default)]
+struct AccessMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    expirations: AtomicU64,
+}
+
+impl AccessMetrics {
+    /// Record a read that found a live item
+    fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a read that found nothing, noting whether the item was there but had expired
+    fn miss(&self, expired: bool) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        if expired {
+            self.expirations.fetch_add(1, Ordering::Relaxed);
         }
     }
 
-    /// Write cache data to file
-    fn write_to_file(path: &PathBuf, data: &HashMap<String, CacheEntry>) -> io::Result<()> {
-        let file = fs::File::create(path)?;
+    fn reset(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.expirations.store(0, Ordering::Relaxed);
+    }
+}
 
-        // Use a buffered writer for better performance
-        let mut writer = BufWriter::with_capacity(128 * 1024, file); // 128KB buffer
+// Counts the bytes written to it, to measure serialized sizes without allocating
+struct ByteCounter(u64);
 
-        serde_json::to_writer(&mut writer, data)?;
-        writer.flush()?;
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
+}
 
-    /// Compress a JSON value using a specific configuration
-    fn compress_value_with_config(
-        &self,
-        value: &serde_json::Value,
-        config: &CompressionConfig,
-    ) -> crate::Result<Vec<u8>> {
-        // First serialize to JSON string to determine size
-        let json_string = serde_json::to_string(value)
-            .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
+/// Length of the value serialized as JSON
+fn json_len(value: &serde_json::Value) -> u64 {
+    let mut counter = ByteCounter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
 
-        // Check if value is below the compression threshold
-        if !config.enabled || json_string.len() < config.threshold {
-            // Return a special marker that indicates this value wasn't compressed
-            let mut result = Vec::with_capacity(json_string.len() + 2);
-            result.push(0); // Marker for uncompressed data
-            result.push(0); // Method marker (unused for uncompressed)
-            result.extend_from_slice(json_string.as_bytes());
-            return Ok(result);
+// Item counts of the cache file, so stats don't need to read it
+struct EntryCounts {
+    total: usize,
+    // Expiration times of the items that have one, in ascending order
+    expiries: Vec<u64>,
+    compressed_count: usize,
+    compressed_bytes: u64,
+    uncompressed_bytes: u64,
+    decompressed_bytes: u64,
+}
+
+impl EntryCounts {
+    fn new(data: &HashMap<String, CacheEntry>) -> Self {
+        let mut expiries: Vec<u64> = data.values().filter_map(|entry| entry.expires_at).collect();
+        expiries.sort_unstable();
+
+        let mut compressed_count = 0;
+        let mut compressed_bytes = 0;
+        let mut uncompressed_bytes = 0;
+        let mut decompressed_bytes = 0;
+        for entry in data.values() {
+            if entry.is_compressed.unwrap_or(false) {
+                compressed_count += 1;
+                compressed_bytes += entry.stored_len();
+            } else {
+                uncompressed_bytes += entry.stored_len();
+            }
+            decompressed_bytes += entry.decoded_len();
         }
 
-        // For large data, use chunked processing to avoid memory spikes
-        let bytes = json_string.as_bytes();
-        const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+        Self {
+            total: data.len(),
+            expiries,
+            compressed_count,
+            compressed_bytes,
+            uncompressed_bytes,
+            decompressed_bytes,
+        }
+    }
 
-        match config.method {
-            CompressionMethod::Zlib => {
-                // Apply Zlib compression with the configured level
-                let zlib_level = Compression::new(config.level);
-                let mut encoder = ZlibEncoder::new(Vec::new(), zlib_level);
+    /// Number of items that haven't expired at `now`
+    fn active(&self, now: u64) -> usize {
+        self.total
+            - self
+                .expiries
+                .partition_point(|&expires_at| expires_at < now)
+    }
+}
 
-                if bytes.len() > CHUNK_SIZE {
-                    // Process in chunks for large data
-                    for chunk in bytes.chunks(CHUNK_SIZE) {
-                        encoder.write_all(chunk).map_err(|e| {
-                            Error::Cache(format!("Failed to compress value chunk: {}", e))
-                        })?;
-                    }
-                } else {
-                    // Small data can be written at once
-                    encoder
-                        .write_all(bytes)
-                        .map_err(|e| Error::Cache(format!("Failed to compress value: {}", e)))?;
-                }
+// Change token and time of the last write, so windows can cheaply detect that the cache changed
+struct ChangeTracker {
+    token: AtomicU64,
+    last_modified: AtomicU64,
+    // Token as of the last flush, nothing written before it is left to flush
+    flushed_token: AtomicU64,
+    // Unknown until the file is first written or counted
+    counts: Mutex<Option<EntryCounts>>,
+}
 
-                // Add marker for compressed data
-                let mut compressed = encoder
-                    .finish()
-                    .map_err(|e| Error::Cache(format!("Failed to finish compression: {}", e)))?;
+impl ChangeTracker {
+    fn new(path: Option<&Path>) -> Self {
+        // Tokens are based on the current time in milliseconds so they keep increasing across
+        // restarts; with a clock set before 1970 they still increase within a run
+        let now_millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let last_modified = path
+            .and_then(|path| fs::metadata(path).ok())
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_secs());
 
-                // Prepend markers (1 = compressed, 1 = Zlib)
-                let mut result = Vec::with_capacity(compressed.len() + 2);
-                result.push(1); // Compressed marker
-                result.push(1); // Method marker: 1 = Zlib
-                result.append(&mut compressed);
+        Self {
+            token: AtomicU64::new(now_millis),
+            last_modified: AtomicU64::new(last_modified),
+            flushed_token: AtomicU64::new(now_millis),
+            counts: Mutex::new(None),
+        }
+    }
 
-                Ok(result)
-            }
-            CompressionMethod::Lzma2 => {
-                // Apply LZMA2 compression with the configured level
-                let mut encoder = XzEncoder::new(Vec::new(), config.level);
+    /// Record a write to the cache file
+    fn record_write(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.next_token();
+        self.last_modified.store(now.as_secs(), Ordering::SeqCst);
+    }
 
-                if bytes.len() > CHUNK_SIZE {
-                    // Process in chunks for large data
-                    for chunk in bytes.chunks(CHUNK_SIZE) {
-                        encoder.write_all(chunk).map_err(|e| {
-                            Error::Cache(format!("Failed to compress value chunk: {}", e))
-                        })?;
-                    }
-                } else {
-                    // Small data can be written at once
-                    encoder
-                        .write_all(bytes)
-                        .map_err(|e| Error::Cache(format!("Failed to compress value: {}", e)))?;
-                }
+    /// Whether anything was written since the last flush
+    fn is_dirty(&self) -> bool {
+        self.flushed_token.load(Ordering::SeqCst) != self.token.load(Ordering::SeqCst)
+    }
 
-                // Add marker for compressed data
-                let mut compressed = encoder
-                    .finish()
-                    .map_err(|e| Error::Cache(format!("Failed to finish compression: {}", e)))?;
+    /// Record that everything written so far has been flushed
+    fn mark_flushed(&self) {
+        let token = self.token.load(Ordering::SeqCst);
+        self.flushed_token.store(token, Ordering::SeqCst);
+    }
+
+    /// Advance the change token and return it, also used as the version of a stored value
+    ///
+    /// Tokens never repeat, so a version can't match a value that was removed and stored again.
+    fn next_token(&self) -> u64 {
+        let now_millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let next = |token: u64| (token + 1).max(now_millis);
+
+        let previous = self
+            .token
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |token| {
+                Some(next(token))
+            })
+            .unwrap();
+        next(previous)
+    }
+}
+
+// Holds the file lock, shared by reads unless they may have to rewrite the file
+enum FileGuard<'a> {
+    Shared(RwLockReadGuard<'a, PathBuf>),
+    Exclusive(RwLockWriteGuard<'a, PathBuf>),
+}
+
+impl Deref for FileGuard<'_> {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        match self {
+            FileGuard::Shared(guard) => guard,
+            FileGuard::Exclusive(guard) => guard,
+        }
+    }
+}
+
+// The items an operation works on: the memory store itself, locked so changes happen in place, or
+// a copy read from the cache file
+enum Items<'a> {
+    Memory(MutexGuard<'a, HashMap<String, CacheEntry>>),
+    File(HashMap<String, CacheEntry>),
+}
+
+impl Deref for Items<'_> {
+    type Target = HashMap<String, CacheEntry>;
+
+    fn deref(&self) -> &HashMap<String, CacheEntry> {
+        match self {
+            Items::Memory(guard) => guard,
+            Items::File(data) => data,
+        }
+    }
+}
+
+impl DerefMut for Items<'_> {
+    fn deref_mut(&mut self) -> &mut HashMap<String, CacheEntry> {
+        match self {
+            Items::Memory(guard) => guard,
+            Items::File(data) => data,
+        }
+    }
+}
+
+// Removes expired items from memory and from the cache file, and with write-behind saves the items
+struct Cleanup<R: Runtime> {
+    app: AppHandle<R>,
+    changes: Arc<ChangeTracker>,
+    file_lock: Arc<RwLock<PathBuf>>,
+    memory_store: Option<MemoryStore>,
+    write_behind: bool,
+    value_cache: ThreadSafeCacheMap,
+    clock: SharedClock,
+    fsync_on_write: bool,
+    storage_format: StorageFormat,
+    stale_grace_secs: u64,
+    memory_idle_secs: Option<u64>,
+    expiry_events: ExpiryEvents,
+    emit_events: bool,
+}
+
+impl<R: Runtime> Cleanup<R> {
+    /// Run one cleanup cycle, returning how many expired items were removed from the file and
+    /// how many active items are left in it
+    fn run(&self) -> io::Result<PruneResult> {
+        // Clean up expired entries; with the clock set before 1970 nothing can be judged expired,
+        // so the cycle is skipped
+        let clock = self
+            .clock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let now = clock
+            .now_secs()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        // Also clean up the in-memory value cache, keeping values still within the grace period
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let expired_keys: Vec<String> = cache
+                .iter()
+                .filter_map(|(key, entry)| {
+                    if let Some(expires) = entry.expires_at {
+                        if expires.saturating_add(self.stale_grace_secs) < now {
+                            Some(key.clone())
+                        } else {
+                            None
+                        }
+                    } else {
+                        None
+                    }
+                })
+                .collect();
 
-                // Prepend markers (1 = compressed, 2 = LZMA2)
-                let mut result = Vec::with_capacity(compressed.len() + 2);
-                result.push(1); // Compressed marker
-                result.push(2); // Method marker: 2 = LZMA2
-                result.append(&mut compressed);
+            for key in expired_keys {
+                cache.remove(&key);
+            }
 
-                Ok(result)
+            // Drop values that haven't been read for a while, they stay on disk
+            if let Some(idle_secs) = self.memory_idle_secs {
+                cache.retain(|_, entry| entry.last_access.saturating_add(idle_secs) >= now);
             }
         }
+
+        // Lock the file for exclusive access
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Read the current cache
+        let mut data = Cache::<R>::read_items(self.memory_store.as_ref(), &cache_file_path)?;
+
+        // Filter out expired entries, keeping those still within their stale window
+        let expired_keys: Vec<String> = data
+            .iter()
+            .filter(|(_, entry)| entry.is_past_stale_window(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        // Counted under the same lock, so nothing can expire or be stored in between
+        let remaining = data.values().filter(|entry| !entry.is_expired(now)).count();
+
+        // Save to file if cache was modified
+        if expired_keys.is_empty() {
+            self.flush_pending(&cache_file_path, &data)?;
+            return Ok(PruneResult {
+                removed: 0,
+                remaining,
+            });
+        }
+        for key in &expired_keys {
+            data.remove(key);
+        }
+        Cache::<R>::write_items(
+            &cache_file_path,
+            &data,
+            self.fsync_on_write,
+            self.storage_format,
+            &self.changes,
+        )?;
+        self.flush_pending(&cache_file_path, &data)?;
+        drop(data);
+        drop(cache_file_path);
+
+        // Let the frontend know which items are gone
+        let result = PruneResult {
+            removed: expired_keys.len(),
+            remaining,
+        };
+        if !self.emit_events {
+            return Ok(result);
+        }
+        match self.expiry_events {
+            ExpiryEvents::PerKey => {
+                for key in expired_keys {
+                    let _ = self.app.emit(EXPIRED_EVENT, CacheEventPayload { key });
+                }
+            }
+            ExpiryEvents::Batched => {
+                let _ = self.app.emit(
+                    EXPIRED_BATCH_EVENT,
+                    ExpiredBatchPayload { keys: expired_keys },
+                );
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// With write-behind, write the items held in memory to the cache file if they changed
+    fn flush_pending(&self, path: &PathBuf, data: &Items) -> io::Result<()> {
+        Cache::<R>::flush_items(
+            self.write_behind,
+            data,
+            path,
+            self.fsync_on_write,
+            self.storage_format,
+            &self.changes,
+        )
+    }
+}
+
+// Emits throttled progress events for a bulk operation
+struct ProgressReporter<'a, R: Runtime> {
+    app: &'a AppHandle<R>,
+    operation: &'static str,
+    total: usize,
+    last_emit: Instant,
+}
+
+impl<'a, R: Runtime> ProgressReporter<'a, R> {
+    /// Emit at most this often, and at least every `EMIT_EVERY` items
+    const EMIT_INTERVAL: Duration = Duration::from_millis(250);
+    const EMIT_EVERY: usize = 1000;
+
+    fn new(app: &'a AppHandle<R>, operation: &'static str, total: usize) -> Self {
+        Self {
+            app,
+            operation,
+            total,
+            last_emit: Instant::now(),
+        }
+    }
+
+    /// Report the number of processed items, emitting an event if one is due
+    fn update(&mut self, processed: usize) {
+        if processed % Self::EMIT_EVERY == 0 || self.last_emit.elapsed() >= Self::EMIT_INTERVAL {
+            self.emit(processed);
+        }
+    }
+
+    /// Report that every item has been processed
+    fn finish(&mut self) {
+        self.emit(self.total);
+    }
+
+    fn emit(&mut self, processed: usize) {
+        let _ = self.app.emit(
+            PROGRESS_EVENT,
+            ProgressPayload {
+                processed,
+                total: self.total,
+                operation: self.operation.to_string(),
+            },
+        );
+        self.last_emit = Instant::now();
+    }
+}
+
+// Initialize the cache with a custom configuration
+pub fn init_with_config<R: Runtime, C: DeserializeOwned>(
+    app: &AppHandle<R>,
+    _api: PluginApi<R, C>,
+    cache_file_path: PathBuf,
+    config: &CacheConfig,
+) -> crate::Result<Cache<R>> {
+    let persist = config.persist.unwrap_or(true);
+    if persist {
+        Cache::<R>::recover_interrupted_writes(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to recover cache file: {}", e)))?;
+    }
+
+    // With write-behind the items are loaded once, then read and written in memory
+    let durability = config.durability.unwrap_or_default();
+    let write_behind = persist && durability == Durability::WriteBehind;
+    let memory_store = if write_behind {
+        let data = match Cache::<R>::read_from_file(&cache_file_path) {
+            Ok(data) => data,
+            // A corrupt file was moved aside already, so start over rather than keep the app
+            // from starting
+            Err(e) if e.kind() == io::ErrorKind::InvalidData && !cache_file_path.exists() => {
+                HashMap::new()
+            }
+            Err(e) => return Err(Error::Cache(format!("Failed to read cache file: {}", e))),
+        };
+        Some(Arc::new(Mutex::new(data)))
+    } else {
+        (!persist).then(|| Arc::new(Mutex::new(HashMap::new())))
+    };
+
+    let cache = Cache {
+        app: app.clone(),
+        changes: Arc::new(ChangeTracker::new(
+            persist.then_some(cache_file_path.as_path()),
+        )),
+        cleanup_interval: Arc::new(AtomicU64::new(config.cleanup_interval.unwrap_or(60))),
+        cleanup_running: Arc::new(AtomicBool::new(false)),
+        fsync_on_write: config.fsync_on_write.unwrap_or(false),
+        storage_format: config.storage_format.unwrap_or_default(),
+        recompress_on_read: config.recompress_on_read.unwrap_or(false),
+        skip_unchanged_writes: config.skip_unchanged_writes.unwrap_or(true),
+        expiry_events: config.expiry_events.unwrap_or_default(),
+        emit_events: config.emit_events.unwrap_or(false),
+        on_decode_error: config.on_decode_error.unwrap_or_default(),
+        durability,
+        default_ttl: config.default_ttl.filter(|&ttl| ttl > 0),
+        max_ttl: config.max_ttl,
+        ttl_jitter_secs: config.ttl_jitter_secs.filter(|&jitter| jitter > 0),
+        jitter_rng: Mutex::new(default_jitter_rng()),
+        clock: Arc::new(Mutex::new(Arc::new(SystemClock))),
+        max_entries: config.max_entries,
+        max_bytes: config.max_bytes.map(|bytes| bytes as u64),
+        max_value_bytes: config.max_value_bytes.map(|bytes| bytes as u64),
+        max_key_len: config.max_key_len,
+        cipher: config
+            .encryption_key
+            .as_ref()
+            .map(|key| Aes256Gcm::new(&key.0.into())),
+        eviction_policy: config.eviction_policy.unwrap_or_default(),
+        access_log: Mutex::new(HashMap::new()),
+        memory_idle_secs: config.memory_idle_secs,
+        stale_grace_secs: config.stale_grace_secs.unwrap_or(0),
+        backing_store: None,
+        backing_store_ttl: None,
+        file_lock: Arc::new(RwLock::new(cache_file_path)),
+        memory_store,
+        write_behind,
+        cleanup_paused: Arc::new(AtomicBool::new(false)),
+        compression: Mutex::new(CompressionConfig::default()),
+        compression_metrics: CompressionMetrics::default(),
+        access_metrics: AccessMetrics::default(),
+        schemas: SchemaRegistry::new(config.schemas.as_ref())?,
+        value_cache: Arc::new(Mutex::new(HashMap::new())),
+    };
+
+    // Set up a background task to clean expired entries periodically
+    cache.start_cleanup_task();
+
+    Ok(cache)
+}
+
+/// Access to the cache APIs.
+#[allow(dead_code)]
+pub struct Cache<R: Runtime> {
+    app: AppHandle<R>,
+    changes: Arc<ChangeTracker>,
+    // Read by the cleanup thread on every tick, so changes take effect without a restart
+    cleanup_interval: Arc<AtomicU64>,
+    cleanup_running: Arc<AtomicBool>,
+    fsync_on_write: bool,
+    storage_format: StorageFormat,
+    recompress_on_read: bool,
+    skip_unchanged_writes: bool,
+    expiry_events: ExpiryEvents,
+    emit_events: bool,
+    on_decode_error: DecodeErrorPolicy,
+    durability: Durability,
+    default_ttl: Option<u64>,
+    max_ttl: Option<u64>,
+    ttl_jitter_secs: Option<u64>,
+    jitter_rng: Mutex<JitterRng>,
+    // Shared with the cleanup thread, so a replaced clock reaches it too
+    clock: SharedClock,
+    max_entries: Option<usize>,
+    max_bytes: Option<u64>,
+    max_value_bytes: Option<u64>,
+    max_key_len: Option<usize>,
+    eviction_policy: EvictionPolicy,
+    cipher: Option<Aes256Gcm>,
+    // Reads since the last write as (last read time, read count), saved with the next write
+    access_log: Mutex<HashMap<String, (u64, u64)>>,
+    memory_idle_secs: Option<u64>,
+    stale_grace_secs: u64,
+    backing_store: Option<Arc<dyn BackingStore>>,
+    backing_store_ttl: Option<u64>,
+    // Guards access to the cache file and holds its current path; reads share it, writes take it
+    // exclusively
+    file_lock: Arc<RwLock<PathBuf>>,
+    memory_store: Option<MemoryStore>,
+    write_behind: bool,
+    cleanup_paused: Arc<AtomicBool>,
+    compression: Mutex<CompressionConfig>,
+    compression_metrics: CompressionMetrics,
+    access_metrics: AccessMetrics,
+    schemas: SchemaRegistry,
+    value_cache: ThreadSafeCacheMap,
+}
+
+impl<R: Runtime> Cache<R> {
+    /// Expiration time for an item stored now with the given TTL, or none if it's kept forever
+    ///
+    /// With a `max_ttl` every item expires, at the latest `max_ttl` seconds from now.
+    fn expires_at(&self, now: u64, ttl: Option<u64>) -> Option<u64> {
+        ttl.or(self.max_ttl).map(|ttl| self.expiry_for(now, ttl))
+    }
+
+    /// The TTL an item stored with the given one gets: the `default_ttl` if none is given, and no
+    /// expiration for an explicit zero
+    fn resolve_ttl(&self, ttl: Option<u64>) -> Option<u64> {
+        match ttl {
+            None => self.default_ttl,
+            Some(0) => None,
+            ttl => ttl,
+        }
+    }
+
+    /// A recorded expiration time moved forward to at most `max_ttl` seconds from now, e.g. for
+    /// imported items
+    fn capped_expiry(&self, now: u64, expires_at: Option<u64>) -> Option<u64> {
+        match self.max_ttl {
+            Some(max) => {
+                let latest = now.saturating_add(max);
+                Some(expires_at.map_or(latest, |expires_at| expires_at.min(latest)))
+            }
+            None => expires_at,
+        }
+    }
+
+    /// Expiration time for an item stored now with the given TTL, spread by the configured jitter
+    /// and capped by `max_ttl`
+    fn expiry_for(&self, now: u64, ttl: u64) -> u64 {
+        // Cap the TTL before adding it, so a huge one from the frontend can't overflow
+        let ttl = self.max_ttl.map_or(ttl, |max| ttl.min(max));
+        let jitter = match self.ttl_jitter_secs {
+            Some(max) => {
+                (self
+                    .jitter_rng
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner))()
+                    % max.saturating_add(1)
+            }
+            None => 0,
+        };
+        let expires_at = now.saturating_add(ttl).saturating_add(jitter);
+        match self.max_ttl {
+            Some(max) => expires_at.min(now.saturating_add(max)),
+            None => expires_at,
+        }
+    }
+
+    /// Start a background task to periodically clean up expired cache entries, unless one is
+    /// already running
+    fn start_cleanup_task(&self) {
+        // An interval of zero disables the background cleanup, rather than busy-looping
+        if self.cleanup_interval.load(Ordering::SeqCst) == 0
+            || self.cleanup_running.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+
+        let cleanup = self.cleanup_cycle();
+        let cleanup_paused = self.cleanup_paused.clone();
+        let cleanup_interval = self.cleanup_interval.clone();
+        let cleanup_running = self.cleanup_running.clone();
+
+        // Use a background thread to periodically clean up expired items. It wakes every second
+        // to pick up a changed interval, rather than sleeping through a long one
+        std::thread::spawn(move || {
+            let mut elapsed = 0;
+            loop {
+                std::thread::sleep(Duration::from_secs(1));
+
+                let interval = cleanup_interval.load(Ordering::SeqCst);
+                if interval == 0 {
+                    cleanup_running.store(false, Ordering::SeqCst);
+
+                    // Keep going if an interval was set again before the flag was cleared, since
+                    // no other thread was started for it
+                    if cleanup_interval.load(Ordering::SeqCst) == 0
+                        || cleanup_running.swap(true, Ordering::SeqCst)
+                    {
+                        return;
+                    }
+                    continue;
+                }
+
+                elapsed += 1;
+                if elapsed < interval {
+                    continue;
+                }
+                elapsed = 0;
+
+                // Skip this cleanup cycle while paused
+                if cleanup_paused.load(Ordering::SeqCst) {
+                    continue;
+                }
+
+                // Skip this cleanup cycle if the file cannot be read or written
+                let _ = cleanup.run();
+            }
+        });
+    }
+
+    /// Change how often the background cleanup runs, in seconds
+    ///
+    /// Takes effect within a second. Zero stops the background cleanup after the cycle in
+    /// progress, if any; setting a non-zero interval again starts it.
+    pub fn set_cleanup_interval(&self, secs: u64) {
+        self.cleanup_interval.store(secs, Ordering::SeqCst);
+        self.start_cleanup_task();
+    }
+
+    /// Everything a cleanup cycle needs, detached from the cache so it can move to the thread
+    fn cleanup_cycle(&self) -> Cleanup<R> {
+        Cleanup {
+            app: self.app.clone(),
+            changes: self.changes.clone(),
+            file_lock: self.file_lock.clone(),
+            memory_store: self.memory_store.clone(),
+            write_behind: self.write_behind,
+            value_cache: self.value_cache.clone(),
+            clock: self.clock.clone(),
+            fsync_on_write: self.fsync_on_write,
+            storage_format: self.storage_format,
+            stale_grace_secs: self.stale_grace_secs,
+            memory_idle_secs: self.memory_idle_secs,
+            expiry_events: self.expiry_events,
+            emit_events: self.emit_events,
+        }
+    }
+
+    /// Run a cleanup cycle right away, returning how many expired items were removed
+    ///
+    /// With `cleanup_interval: Some(0)` no background cleanup runs, so call this whenever
+    /// expired items should be purged. It also runs while the background cleanup is paused.
+    pub fn cleanup(&self) -> crate::Result<usize> {
+        Ok(self.prune()?.removed)
+    }
+
+    /// Run a cleanup cycle right away, returning how many expired items were removed and how
+    /// many active items are left
+    ///
+    /// Items in their stale window are kept but don't count as active.
+    pub fn prune(&self) -> crate::Result<PruneResult> {
+        self.cleanup_cycle()
+            .run()
+            .map_err(|e| Error::Cache(format!("Failed to clean up cache file: {}", e)))
+    }
+
+    /// Make sure every write so far has reached the disk, e.g. before the app exits
+    ///
+    /// Writes the items held back by write-behind or folds a write-ahead log into the cache file,
+    /// and waits until the file is on disk. Does
+    /// nothing if nothing was written since the last flush, or if the cache isn't persisted.
+    pub fn flush(&self) -> crate::Result<EmptyResponse> {
+        if !self.is_persisted() {
+            return Ok(EmptyResponse {});
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !self.changes.is_dirty() {
+            return Ok(EmptyResponse {});
+        }
+
+        if self.write_behind {
+            Self::flush_memory_store(
+                true,
+                self.memory_store.as_ref(),
+                &cache_file_path,
+                true,
+                self.storage_format,
+                &self.changes,
+            )
+            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+            return Ok(EmptyResponse {});
+        }
+
+        if sibling_path(&cache_file_path, WAL_SUFFIX).exists() {
+            let data = Self::read_from_file(&cache_file_path)
+                .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+            Self::write_to_file(
+                &cache_file_path,
+                &data,
+                true,
+                self.storage_format,
+                &self.changes,
+            )
+            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+        } else if cache_file_path.exists() {
+            // Windows only syncs files opened for writing
+            fs::OpenOptions::new()
+                .write(true)
+                .open(&*cache_file_path)
+                .and_then(|file| file.sync_all())
+                .map_err(|e| Error::Cache(format!("Failed to flush cache file: {}", e)))?;
+        }
+
+        self.changes.mark_flushed();
+        Ok(EmptyResponse {})
+    }
+
+    /// Stop the background cleanup from running until [`Cache::resume_cleanup`] is called
+    ///
+    /// Useful around bulk operations, so the cleanup doesn't contend for the file lock or rewrite
+    /// the file in between.
+    pub fn pause_cleanup(&self) {
+        self.cleanup_paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Let the background cleanup run again after [`Cache::pause_cleanup`]
+    pub fn resume_cleanup(&self) {
+        self.cleanup_paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Read the items, from memory if the cache isn't persisted
+    fn read_data(&self, path: &PathBuf) -> io::Result<Items<'_>> {
+        Self::read_items(self.memory_store.as_ref(), path)
+    }
+
+    /// Save the items, in memory if the cache isn't persisted
+    fn write_data(&self, path: &PathBuf, data: &Items) -> io::Result<()> {
+        Self::write_items(
+            path,
+            data,
+            self.fsync_on_write,
+            self.storage_format,
+            &self.changes,
+        )
+    }
+
+    /// Save the items, with write-behind also to the cache file right away
+    ///
+    /// Used for removals, so removed items can't come back after a crash.
+    fn write_through(&self, path: &PathBuf, data: &Items) -> io::Result<()> {
+        self.write_data(path, data)?;
+        Self::flush_items(
+            self.write_behind,
+            data,
+            path,
+            self.fsync_on_write,
+            self.storage_format,
+            &self.changes,
+        )
+    }
+
+    /// Whether the items are saved to the cache file, now or with write-behind later
+    fn is_persisted(&self) -> bool {
+        self.memory_store.is_none() || self.write_behind
+    }
+
+    /// With write-behind, write the items held in memory to the cache file if anything changed
+    /// since the last flush
+    fn flush_memory_store(
+        write_behind: bool,
+        memory_store: Option<&MemoryStore>,
+        path: &PathBuf,
+        fsync: bool,
+        format: StorageFormat,
+        changes: &ChangeTracker,
+    ) -> io::Result<()> {
+        let Some(store) = memory_store else {
+            return Ok(());
+        };
+        let data = Items::Memory(store.lock().unwrap_or_else(PoisonError::into_inner));
+        Self::flush_items(write_behind, &data, path, fsync, format, changes)
+    }
+
+    /// With write-behind, write the items of the locked memory store to the cache file if
+    /// anything changed since the last flush
+    fn flush_items(
+        write_behind: bool,
+        data: &Items,
+        path: &PathBuf,
+        fsync: bool,
+        format: StorageFormat,
+        changes: &ChangeTracker,
+    ) -> io::Result<()> {
+        if !write_behind || !matches!(data, Items::Memory(_)) || !changes.is_dirty() {
+            return Ok(());
+        }
+
+        Self::write_to_file(path, data, fsync, format, changes)?;
+        changes.mark_flushed();
+        Ok(())
+    }
+
+    /// Lock the memory store if there is one, otherwise read the items from the cache file
+    fn read_items<'a>(
+        memory_store: Option<&'a MemoryStore>,
+        path: &PathBuf,
+    ) -> io::Result<Items<'a>> {
+        match memory_store {
+            Some(store) => Ok(Items::Memory(
+                store.lock().unwrap_or_else(PoisonError::into_inner),
+            )),
+            None => Self::read_from_file(path).map(Items::File),
+        }
+    }
+
+    /// Save the items to the cache file, unless they were changed in the memory store in place
+    fn write_items(
+        path: &PathBuf,
+        data: &Items,
+        fsync: bool,
+        format: StorageFormat,
+        changes: &ChangeTracker,
+    ) -> io::Result<()> {
+        let Items::Memory(store) = data else {
+            return Self::write_to_file(path, data, fsync, format, changes);
+        };
+
+        changes.record_write();
+        *changes
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(EntryCounts::new(store));
+        Ok(())
+    }
+
+    /// Read cache data from file, including changes still in the write-ahead log
+    fn read_from_file(path: &PathBuf) -> io::Result<HashMap<String, CacheEntry>> {
+        let mut data = Self::read_main_file(path)?;
+        Self::replay_wal(path, &mut data)?;
+        Ok(data)
+    }
+
+    /// Read the cache file itself
+    ///
+    /// A file that isn't valid JSON is moved aside to `<file>.corrupt-<timestamp>` rather than
+    /// overwritten by the next write, and the read fails. Later reads start from an empty cache.
+    fn read_main_file(path: &PathBuf) -> io::Result<HashMap<String, CacheEntry>> {
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let file = fs::File::open(path)?;
+        let file_size = file.metadata()?.len();
+
+        if file_size == 0 {
+            return Ok(HashMap::new());
+        }
+
+        // Parse straight from a buffered reader so a large file is never held in memory twice
+        let mut reader = BufReader::with_capacity(
+            std::cmp::min(file_size as usize, 128 * 1024), // 128KB buffer or file size
+            file,
+        );
+
+        match Self::parse_contents(&mut reader) {
+            Ok(data) => Ok(data),
+            Err(FileError::Io(e)) => Err(e),
+            Err(FileError::Invalid(e)) => {
+                let millis = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                let backup_path = sibling_path(path, &format!(".corrupt-{}", millis));
+                fs::rename(path, &backup_path)?;
+                eprintln!(
+                    "Warning: Cache file is corrupt ({}), moved it to {}",
+                    e,
+                    backup_path.display()
+                );
+
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "cache file is corrupt ({}), moved it to {} and starting over",
+                        e,
+                        backup_path.display()
+                    ),
+                ))
+            }
+        }
+    }
+
+    /// Parse the items in whichever format the cache file was written in, told by its header
+    fn parse_contents(
+        reader: &mut BufReader<fs::File>,
+    ) -> Result<HashMap<String, CacheEntry>, FileError> {
+        let header = reader.fill_buf().map_err(FileError::Io)?;
+        let format = match header.strip_prefix(FORMAT_MAGIC.as_slice()) {
+            Some([FORMAT_MESSAGEPACK, ..]) => StorageFormat::MessagePack,
+            Some([FORMAT_CBOR, ..]) => StorageFormat::Cbor,
+            Some(_) => {
+                return Err(FileError::Invalid(
+                    "unknown storage format in header".to_string(),
+                ))
+            }
+            None => StorageFormat::Json,
+        };
+        if format != StorageFormat::Json {
+            reader.consume(FORMAT_MAGIC.len() + 1);
+        }
+
+        match format {
+            StorageFormat::Json => serde_json::from_reader(reader).map_err(|e| {
+                if e.is_io() {
+                    FileError::Io(e.into())
+                } else {
+                    FileError::Invalid(e.to_string())
+                }
+            }),
+            StorageFormat::MessagePack => rmp_serde::from_read(reader).map_err(|e| match e {
+                rmp_serde::decode::Error::InvalidMarkerRead(e)
+                | rmp_serde::decode::Error::InvalidDataRead(e)
+                    if e.kind() != io::ErrorKind::UnexpectedEof =>
+                {
+                    FileError::Io(e)
+                }
+                e => FileError::Invalid(e.to_string()),
+            }),
+            StorageFormat::Cbor => ciborium::from_reader(reader).map_err(|e| match e {
+                ciborium::de::Error::Io(e) if e.kind() != io::ErrorKind::UnexpectedEof => {
+                    FileError::Io(e)
+                }
+                e => FileError::Invalid(e.to_string()),
+            }),
+        }
+    }
+
+    /// Apply the changes recorded in the write-ahead log, if there is one
+    fn replay_wal(path: &Path, data: &mut HashMap<String, CacheEntry>) -> io::Result<()> {
+        let file = match fs::File::open(sibling_path(path, WAL_SUFFIX)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for line in BufReader::new(file).split(b'\n') {
+            // Records that were only partly written when the app stopped are skipped
+            match serde_json::from_slice(&line?) {
+                Ok(WalRecord::Set { key, entry }) => {
+                    data.insert(key, *entry);
+                }
+                Ok(WalRecord::Remove { key }) => {
+                    data.remove(&key);
+                }
+                Err(_) => continue,
+            }
+        }
+        Ok(())
+    }
+
+    /// Append a change to the write-ahead log, folding the log into the cache file once it has
+    /// grown larger than the file
+    fn append_to_wal(&self, path: &PathBuf, record: &WalRecord, data: &Items) -> io::Result<()> {
+        // Without a file there's no log either, the items are simply replaced
+        if self.memory_store.is_some() {
+            return self.write_data(path, data);
+        }
+
+        // Each record starts on a new line, so one cut short by a crash never swallows the next
+        let mut line = vec![b'\n'];
+        serde_json::to_writer(&mut line, record)?;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(sibling_path(path, WAL_SUFFIX))?;
+        file.write_all(&line)?;
+        if self.fsync_on_write {
+            file.sync_data()?;
+        }
+
+        let wal_len = file.metadata()?.len();
+        let file_len = fs::metadata(path).map_or(0, |metadata| metadata.len());
+        if wal_len >= file_len.max(WAL_MIN_CHECKPOINT_BYTES) {
+            return Self::write_to_file(
+                path,
+                data,
+                self.fsync_on_write,
+                self.storage_format,
+                &self.changes,
+            );
+        }
+
+        self.changes.record_write();
+        *self
+            .changes
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(EntryCounts::new(data));
+        Ok(())
+    }
+
+    /// Finish or discard a checkpoint or rewrite that was interrupted, e.g. by a crash
+    fn recover_interrupted_writes(path: &Path) -> io::Result<()> {
+        // A rewrite only replaces the file once it's complete, so a leftover copy is discarded
+        let rewrite_path = sibling_path(path, REWRITE_SUFFIX);
+        if rewrite_path.exists() {
+            fs::remove_file(rewrite_path)?;
+        }
+
+        let checkpoint_path = sibling_path(path, CHECKPOINT_SUFFIX);
+        if !checkpoint_path.exists() {
+            return Ok(());
+        }
+
+        if sibling_path(path, WAL_SUFFIX).exists() {
+            // The log is only removed once the checkpoint is complete, so it's still authoritative
+            fs::remove_file(checkpoint_path)
+        } else {
+            fs::rename(checkpoint_path, path)
+        }
+    }
+
+    /// Write cache data to file, optionally waiting until it has reached the disk
+    ///
+    /// The data is written to a copy next to the file, which then replaces it in a single rename,
+    /// so a crash mid-write leaves the previous file intact instead of a truncated one. If there
+    /// is a write-ahead log, the data replaces it: the new file is completed on disk before the
+    /// log is removed, so a crash at any point leaves one of them to recover from.
+    fn write_to_file(
+        path: &PathBuf,
+        data: &HashMap<String, CacheEntry>,
+        fsync: bool,
+        format: StorageFormat,
+        changes: &ChangeTracker,
+    ) -> io::Result<()> {
+        let wal_path = sibling_path(path, WAL_SUFFIX);
+        if wal_path.exists() {
+            let checkpoint_path = sibling_path(path, CHECKPOINT_SUFFIX);
+            Self::write_contents(&checkpoint_path, data, true, format)?;
+            fs::remove_file(&wal_path)?;
+            fs::rename(&checkpoint_path, path)?;
+        } else {
+            let rewrite_path = sibling_path(path, REWRITE_SUFFIX);
+            Self::write_contents(&rewrite_path, data, fsync, format)?;
+            fs::rename(&rewrite_path, path)?;
+        }
+
+        changes.record_write();
+        *changes
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(EntryCounts::new(data));
+        Ok(())
+    }
+
+    /// Write the cache data as a whole to a file
+    fn write_contents(
+        path: &Path,
+        data: &HashMap<String, CacheEntry>,
+        fsync: bool,
+        format: StorageFormat,
+    ) -> io::Result<()> {
+        let file = fs::File::create(path)?;
+
+        // Use a buffered writer for better performance
+        let mut writer = BufWriter::with_capacity(128 * 1024, file); // 128KB buffer
+
+        // Write the items sorted by key, so the same contents always produce the same file
+        let entries = SortedEntries::new(data);
+        match format {
+            StorageFormat::Json => serde_json::to_writer(&mut writer, &entries)?,
+            StorageFormat::MessagePack => {
+                writer.write_all(FORMAT_MAGIC)?;
+                writer.write_all(&[FORMAT_MESSAGEPACK])?;
+                // Entries are written as maps, since fields left out when empty would misalign
+                // positional arrays
+                rmp_serde::encode::write_named(&mut writer, &entries).map_err(io::Error::other)?;
+            }
+            StorageFormat::Cbor => {
+                writer.write_all(FORMAT_MAGIC)?;
+                writer.write_all(&[FORMAT_CBOR])?;
+                ciborium::into_writer(&entries, &mut writer).map_err(io::Error::other)?;
+            }
+        }
+        writer.flush()?;
+
+        // Flushing only hands the data to the OS, sync_all makes it durable
+        if fsync {
+            writer.get_ref().sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Compress a JSON value using a specific configuration
+    fn compress_value_with_config(
+        &self,
+        value: &serde_json::Value,
+        config: &CompressionConfig,
+    ) -> crate::Result<Vec<u8>> {
+        // First serialize to JSON string to determine size
+        let json_string = serde_json::to_string(value)
+            .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
+
+        // Check if value is below the compression threshold
+        if !config.enabled || json_string.len() < config.threshold {
+            // Return the JSON under a header that marks it as uncompressed
+            let header = PayloadHeader::new(PayloadHeader::METHOD_NONE, false);
+            return Ok(header.wrap(json_string.as_bytes()));
+        }
+
+        // For large data, use chunked processing to avoid memory spikes
+        let bytes = json_string.as_bytes();
+        const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+
+        match config.method {
+            CompressionMethod::Zlib => {
+                // Apply Zlib compression with the configured level
+                let zlib_level = Compression::new(config.level);
+                let mut encoder = ZlibEncoder::new(Vec::new(), zlib_level);
+
+                if bytes.len() > CHUNK_SIZE {
+                    // Process in chunks for large data
+                    for chunk in bytes.chunks(CHUNK_SIZE) {
+                        encoder.write_all(chunk).map_err(|e| {
+                            Error::Cache(format!("Failed to compress value chunk: {}", e))
+                        })?;
+                    }
+                } else {
+                    // Small data can be written at once
+                    encoder
+                        .write_all(bytes)
+                        .map_err(|e| Error::Cache(format!("Failed to compress value: {}", e)))?;
+                }
+
+                let compressed = encoder
+                    .finish()
+                    .map_err(|e| Error::Cache(format!("Failed to finish compression: {}", e)))?;
+                self.compression_metrics
+                    .record(bytes.len(), compressed.len());
+
+                // Prepend the header for compressed data
+                let header = PayloadHeader::new(PayloadHeader::METHOD_ZLIB, true);
+                Ok(header.wrap(&compressed))
+            }
+            CompressionMethod::Lzma2 => {
+                // Apply LZMA2 compression with the configured level
+                let mut encoder = XzEncoder::new(Vec::new(), config.level);
+
+                if bytes.len() > CHUNK_SIZE {
+                    // Process in chunks for large data
+                    for chunk in bytes.chunks(CHUNK_SIZE) {
+                        encoder.write_all(chunk).map_err(|e| {
+                            Error::Cache(format!("Failed to compress value chunk: {}", e))
+                        })?;
+                    }
+                } else {
+                    // Small data can be written at once
+                    encoder
+                        .write_all(bytes)
+                        .map_err(|e| Error::Cache(format!("Failed to compress value: {}", e)))?;
+                }
+
+                let compressed = encoder
+                    .finish()
+                    .map_err(|e| Error::Cache(format!("Failed to finish compression: {}", e)))?;
+                self.compression_metrics
+                    .record(bytes.len(), compressed.len());
+
+                // Prepend the header for compressed data
+                let header = PayloadHeader::new(PayloadHeader::METHOD_LZMA2, true);
+                Ok(header.wrap(&compressed))
+            }
+            CompressionMethod::Gzip => {
+                // Apply gzip compression with the configured level
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::new(config.level));
+
+                if bytes.len() > CHUNK_SIZE {
+                    // Process in chunks for large data
+                    for chunk in bytes.chunks(CHUNK_SIZE) {
+                        encoder.write_all(chunk).map_err(|e| {
+                            Error::Cache(format!("Failed to compress value chunk: {}", e))
+                        })?;
+                    }
+                } else {
+                    // Small data can be written at once
+                    encoder
+                        .write_all(bytes)
+                        .map_err(|e| Error::Cache(format!("Failed to compress value: {}", e)))?;
+                }
+
+                let compressed = encoder
+                    .finish()
+                    .map_err(|e| Error::Cache(format!("Failed to finish compression: {}", e)))?;
+                self.compression_metrics
+                    .record(bytes.len(), compressed.len());
+
+                // Stored without a header, the gzip format identifies itself
+                Ok(compressed)
+            }
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => {
+                // Apply zstd compression with the configured level, streaming the input
+                let compressed = zstd::stream::encode_all(bytes, config.level as i32)
+                    .map_err(|e| Error::Cache(format!("Failed to compress value: {}", e)))?;
+                self.compression_metrics
+                    .record(bytes.len(), compressed.len());
+
+                // Prepend the header for compressed data
+                let header = PayloadHeader::new(PayloadHeader::METHOD_ZSTD, true);
+                Ok(header.wrap(&compressed))
+            }
+            #[cfg(not(feature = "zstd"))]
+            CompressionMethod::Zstd => {
+                // Built without zstd support, so fall back to Zlib like mobile platforms do. Zstd
+                // levels go higher than Zlib's
+                let fallback = CompressionConfig {
+                    method: CompressionMethod::Zlib,
+                    level: config.level.min(CompressionMethod::Zlib.max_level()),
+                    ..config.clone()
+                };
+                self.compress_value_with_config(value, &fallback)
+            }
+        }
+    }
+
+    /// Compress a JSON value using the default compression configuration
+    #[allow(dead_code)]
+    fn compress_value(&self, value: &serde_json::Value) -> crate::Result<Vec<u8>> {
+        let compression = self
+            .compression
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        self.compress_value_with_config(value, &compression)
+    }
+
+    /// Decompress a compressed value back to JSON
+    fn decompress_value(&self, data: &[u8]) -> crate::Result<serde_json::Value> {
+        if data.is_empty() {
+            return Err(Error::Cache(
+                "Empty data provided for decompression".to_string(),
+            ));
+        }
+
+        let (header, payload) = PayloadHeader::parse(data)?;
+
+        if header.is_encrypted() {
+            let decrypted = self.decrypt_payload(&header, payload)?;
+            let header = PayloadHeader {
+                flags: header.flags & !PayloadHeader::FLAG_ENCRYPTED,
+                ..header
+            };
+            return self.decode_payload(header, &decrypted);
+        }
+
+        self.decode_payload(header, payload)
+    }
+
+    /// Decode the JSON held by a payload, decompressing it if its header says so
+    fn decode_payload(
+        &self,
+        header: PayloadHeader,
+        payload: &[u8],
+    ) -> crate::Result<serde_json::Value> {
+        if !header.is_compressed() {
+            // Data is not compressed - parse the JSON directly
+            let string_data = std::str::from_utf8(payload)
+                .map_err(|e| Error::Cache(format!("Failed to decode uncompressed data: {}", e)))?;
+
+            return serde_json::from_str(string_data)
+                .map_err(|e| Error::Cache(format!("Failed to deserialize value: {}", e)));
+        }
+
+        match header.method {
+            PayloadHeader::METHOD_ZLIB => {
+                // Zlib decompression
+                let mut decoder = ZlibDecoder::new(payload);
+                let mut json_string = String::new();
+
+                decoder
+                    .read_to_string(&mut json_string)
+                    .map_err(|e| Error::Cache(format!("Failed to decompress Zlib data: {}", e)))?;
+
+                serde_json::from_str(&json_string)
+                    .map_err(|e| Error::Cache(format!("Failed to parse decompressed JSON: {}", e)))
+            }
+            PayloadHeader::METHOD_GZIP => {
+                // Gzip decompression
+                let mut decoder = GzDecoder::new(payload);
+                let mut json_string = String::new();
+
+                decoder
+                    .read_to_string(&mut json_string)
+                    .map_err(|e| Error::Cache(format!("Failed to decompress gzip data: {}", e)))?;
+
+                serde_json::from_str(&json_string)
+                    .map_err(|e| Error::Cache(format!("Failed to parse decompressed JSON: {}", e)))
+            }
+            PayloadHeader::METHOD_LZMA2 => {
+                // LZMA2 decompression
+                let mut decoder = XzDecoder::new(payload);
+                let mut json_string = String::new();
+
+                decoder
+                    .read_to_string(&mut json_string)
+                    .map_err(|e| Error::Cache(format!("Failed to decompress LZMA2 data: {}", e)))?;
+
+                serde_json::from_str(&json_string)
+                    .map_err(|e| Error::Cache(format!("Failed to parse decompressed JSON: {}", e)))
+            }
+            #[cfg(feature = "zstd")]
+            PayloadHeader::METHOD_ZSTD => {
+                // Zstd decompression
+                let json_bytes = zstd::stream::decode_all(payload)
+                    .map_err(|e| Error::Cache(format!("Failed to decompress zstd data: {}", e)))?;
+
+                serde_json::from_slice(&json_bytes)
+                    .map_err(|e| Error::Cache(format!("Failed to parse decompressed JSON: {}", e)))
+            }
+            #[cfg(not(feature = "zstd"))]
+            PayloadHeader::METHOD_ZSTD => Err(Error::Cache(
+                "Zstd data can't be decompressed without the `zstd` feature".to_string(),
+            )),
+            method => Err(Error::Cache(format!(
+                "Unknown compression method marker: {}",
+                method
+            ))),
+        }
+    }
+
+    /// Encrypt a framed payload, keeping its compression marker in the header so it can be
+    /// decoded once decrypted
+    fn encrypt_payload(&self, cipher: &Aes256Gcm, data: &[u8]) -> crate::Result<Vec<u8>> {
+        let (header, payload) = PayloadHeader::parse(data)?;
+        let header = PayloadHeader {
+            flags: header.flags | PayloadHeader::FLAG_ENCRYPTED,
+            ..header
+        };
+
+        // The header is authenticated along with the value, so it can't be altered unnoticed
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: payload,
+                    aad: &header.bytes(),
+                },
+            )
+            .map_err(|_| Error::Cache("Failed to encrypt value".to_string()))?;
+
+        let mut sealed = Vec::with_capacity(nonce.len() + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(header.wrap(&sealed))
+    }
+
+    /// Decrypt the payload of an encrypted value
+    fn decrypt_payload(&self, header: &PayloadHeader, payload: &[u8]) -> crate::Result<Vec<u8>> {
+        let Some(cipher) = &self.cipher else {
+            return Err(Error::Decryption(
+                "value is encrypted but no encryption key is configured".to_string(),
+            ));
+        };
+        if payload.len() < PayloadHeader::NONCE_LEN {
+            return Err(Error::Decryption(
+                "encrypted value is truncated".to_string(),
+            ));
+        }
+
+        let (nonce, ciphertext) = payload.split_at(PayloadHeader::NONCE_LEN);
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &header.bytes(),
+                },
+            )
+            .map_err(|_| {
+                Error::Decryption("wrong encryption key or the value was modified".to_string())
+            })
+    }
+
+    /// Store a value in an entry, compressing it if enabled and the value exceeds the threshold,
+    /// then encrypting it if a key is configured
+    fn encode_value(
+        &self,
+        entry: &mut CacheEntry,
+        value: serde_json::Value,
+        config: &CompressionConfig,
+    ) -> crate::Result<()> {
+        if let Some(cipher) = &self.cipher {
+            // Encrypted values are always framed, uncompressed ones under a header that says so
+            let processed_data = self.compress_value_with_config(&value, config)?;
+            let compressed = PayloadHeader::parse(&processed_data)?.0.is_compressed();
+            let sealed = self.encrypt_payload(cipher, &processed_data)?;
+
+            entry.original_len = Some(json_len(&value));
+            entry.value = serde_json::Value::String(STANDARD.encode(&sealed));
+            entry.is_encrypted = Some(true);
+            entry.is_compressed = Some(compressed);
+            entry.compression_method = compressed.then(|| config.method.clone());
+            entry.compression_level = compressed.then_some(config.level);
+            return Ok(());
+        }
+
+        entry.is_encrypted = None;
+        if config.enabled {
+            let processed_data = self.compress_value_with_config(&value, config)?;
+
+            // Values below the threshold come back marked as uncompressed, store those
+            // as plain JSON rather than inflating them by a third with base64
+            if PayloadHeader::parse(&processed_data)?.0.is_compressed() {
+                entry.original_len = Some(json_len(&value));
+                entry.value = serde_json::Value::String(STANDARD.encode(&processed_data));
+                entry.is_compressed = Some(true);
+                entry.compression_method = Some(config.method.clone());
+                entry.compression_level = Some(config.level);
+                return Ok(());
+            }
+        }
+
+        entry.value = value;
+        entry.original_len = None;
+        entry.is_compressed = Some(false);
+        entry.compression_method = None;
+        entry.compression_level = None;
+
+        Ok(())
+    }
+
+    /// Let the frontend know items were stored or removed, if `emit_events` is enabled
+    fn emit_changes<K: AsRef<str>>(
+        &self,
+        event: &str,
+        keys: impl IntoIterator<Item = K>,
+        reason: ChangeReason,
+    ) {
+        if !self.emit_events {
+            return;
+        }
+        for key in keys {
+            let _ = self.app.emit(
+                event,
+                ChangeEventPayload {
+                    key: key.as_ref().to_string(),
+                    reason,
+                },
+            );
+        }
+    }
+
+    /// Emit a stale event if the value has outlived its soft TTL and `emit_events` is enabled
+    fn notify_if_stale(&self, key: &str, stale_at: Option<u64>, now: u64) {
+        if self.emit_events && stale_at.is_some_and(|stale_at| stale_at <= now) {
+            let _ = self.app.emit(
+                STALE_EVENT,
+                CacheEventPayload {
+                    key: key.to_string(),
+                },
+            );
+        }
+    }
+
+    /// Whether an in-memory value has expired and can no longer be served during the grace period
+    fn past_grace(&self, entry: &CacheValueEntry, now: u64) -> bool {
+        entry
+            .expires_at
+            .is_some_and(|expires| expires.saturating_add(self.stale_grace_secs) < now)
+    }
+
+    /// Note a read of the item for the eviction policy
+    fn record_access(&self, key: &str, now: u64) {
+        if !self.is_bounded() {
+            return;
+        }
+
+        let mut log = self
+            .access_log
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (last_read, reads) = log.entry(key.to_string()).or_insert((now, 0));
+        *last_read = now;
+        *reads += 1;
+    }
+
+    /// Lock the cache file for a read
+    ///
+    /// Reads share the lock, so they run in parallel, unless a read may rewrite the file to
+    /// migrate or evict the items it loads.
+    fn lock_for_read(&self) -> FileGuard<'_> {
+        if self.recompress_on_read || self.on_decode_error == DecodeErrorPolicy::EvictAndMiss {
+            FileGuard::Exclusive(
+                self.file_lock
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner),
+            )
+        } else {
+            FileGuard::Shared(
+                self.file_lock
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner),
+            )
+        }
+    }
+
+    /// Whether the cache has a size limit, so writes may evict other items
+    fn is_bounded(&self) -> bool {
+        self.max_entries.is_some() || self.max_bytes.is_some()
+    }
+
+    /// Bytes an item counts against `max_bytes`: its key and its value as stored
+    fn footprint(key: &str, entry: &CacheEntry) -> u64 {
+        key.len() as u64 + entry.stored_len()
+    }
+
+    /// Reject an item that couldn't fit within `max_bytes` even in an otherwise empty cache
+    fn check_fits(&self, key: &str, entry: &CacheEntry) -> crate::Result<()> {
+        match self.max_bytes {
+            Some(max_bytes) if Self::footprint(key, entry) > max_bytes => {
+                Err(Error::Cache(format!(
+                    "Item \"{}\" takes {} bytes, more than max_bytes ({})",
+                    key,
+                    Self::footprint(key, entry),
+                    max_bytes
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject a value whose JSON is larger than `max_value_bytes`, before it's compressed
+    fn check_value_size(&self, key: &str, value: &serde_json::Value) -> crate::Result<()> {
+        let Some(max_value_bytes) = self.max_value_bytes else {
+            return Ok(());
+        };
+        let len = json_len(value);
+        if len > max_value_bytes {
+            return Err(Error::Cache(format!(
+                "Item \"{}\" value exceeds max_value_bytes ({} > {})",
+                key, len, max_value_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /// Evict items until the cache fits in `max_entries` and `max_bytes`, returning the evicted
+    /// keys
+    ///
+    /// Expired items go first, then the rest in the order given by the eviction policy.
+    /// The `protect` key is never evicted. Reads noted since the last write are saved
+    /// into the entries first so they're persisted along with the write.
+    fn evict_over_capacity(
+        &self,
+        data: &mut HashMap<String, CacheEntry>,
+        protect: Option<&str>,
+        now: u64,
+    ) -> Vec<String> {
+        if !self.is_bounded() {
+            return Vec::new();
+        }
+        let max_entries = self.max_entries.unwrap_or(usize::MAX);
+        let max_bytes = self.max_bytes.unwrap_or(u64::MAX);
+
+        for (key, (last_read, reads)) in self
+            .access_log
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain()
+        {
+            if let Some(entry) = data.get_mut(&key) {
+                entry.last_accessed = Some(last_read);
+                entry.access_count = Some(entry.access_count.unwrap_or(0) + reads);
+            }
+        }
+
+        let mut bytes: u64 = data
+            .iter()
+            .map(|(key, entry)| Self::footprint(key, entry))
+            .sum();
+        if data.len() <= max_entries && bytes <= max_bytes {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<(bool, (u64, u64), String)> = data
+            .iter()
+            .filter(|(key, _)| Some(key.as_str()) != protect)
+            .map(|(key, entry)| {
+                (
+                    !entry.is_expired(now),
+                    entry.eviction_rank(self.eviction_policy),
+                    key.clone(),
+                )
+            })
+            .collect();
+        candidates.sort_unstable();
+
+        let mut evicted = Vec::new();
+        for (_, _, key) in candidates {
+            if data.len() <= max_entries && bytes <= max_bytes {
+                break;
+            }
+            if let Some(entry) = data.remove(&key) {
+                bytes -= Self::footprint(&key, &entry);
+                evicted.push(key);
+            }
+        }
+
+        evicted
+    }
+
+    /// Get the original value of a stored entry, decrypting and decompressing it if needed
+    fn decode_entry(&self, entry: &CacheEntry) -> crate::Result<serde_json::Value> {
+        if !entry.is_encoded() {
+            return Ok(entry.value.clone());
+        }
+
+        if let serde_json::Value::String(compressed_str) = &entry.value {
+            // Decode base64
+            let compressed_data = STANDARD
+                .decode(compressed_str)
+                .map_err(|e| Error::Cache(format!("Failed to decode base64: {}", e)))?;
+
+            // Decompress
+            self.decompress_value(&compressed_data)
+        } else {
+            Err(Error::Cache(
+                "Compressed value is not in expected format".to_string(),
+            ))
+        }
+    }
+
+    /// Sets a value in the cache with an optional TTL
+    pub fn set<T: Serialize + std::fmt::Debug>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<EmptyResponse> {
+        check_key(&key, self.max_key_len)?;
+
+        // Serialize the value to JSON first (do this outside the lock)
+        let value_json = serde_json::to_value(value)
+            .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
+
+        // Reject values that don't match the schema registered for their key, or are too large
+        self.schemas.validate(&key, &value_json)?;
+        self.check_value_size(&key, &value_json)?;
+
+        let now = self.now()?;
+
+        // Process the value based on compression settings
+        let entry = self.new_entry(value_json.clone(), options.as_ref(), now)?;
+        self.check_fits(&key, &entry)?;
+
+        // Update the in-memory cache first
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            cache.insert(key.clone(), entry.to_memory(value_json, now));
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let (version, changed) = self.place_entry(&mut data, &key, entry);
+        if let Some(memory_entry) = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(&key)
+        {
+            memory_entry.version = version;
+        }
+        if !changed {
+            return Ok(EmptyResponse::default());
+        }
+
+        // Make room for the new item if the cache is full
+        let evicted = self.evict_over_capacity(&mut data, Some(&key), now);
+
+        // Save the updated cache to file. Without a size limit no other item changed, so
+        // logging the new one is enough
+        let written = if self.durability == Durability::WriteAheadLog && !self.is_bounded() {
+            let record = WalRecord::Set {
+                key: key.clone(),
+                entry: Box::new(data[&key].clone()),
+            };
+            self.append_to_wal(&cache_file_path, &record, &data)
+        } else {
+            self.write_data(&cache_file_path, &data)
+        };
+        written.map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        if !evicted.is_empty() {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            for key in &evicted {
+                cache.remove(key);
+            }
+        }
+
+        self.emit_changes(SET_EVENT, [&key], ChangeReason::Set);
+        self.emit_changes(REMOVED_EVENT, &evicted, ChangeReason::Evicted);
+        Ok(EmptyResponse::default())
+    }
+
+    /// Gets a value, first storing `value` if the key is missing or expired, and returns the
+    /// value now held by the cache
+    ///
+    /// The lookup and the insert happen while holding the file lock, so when several callers race
+    /// for the same key exactly one of their values is stored and all of them get that value back.
+    /// Unlike [`Cache::get`] the backing store isn't consulted.
+    pub fn get_or_set<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<serde_json::Value> {
+        self.get_or_insert(key, value, options)
+            .map(|(value, _)| value)
+    }
+
+    /// Stores `value` only if the key is missing or expired, returning whether it was stored
+    ///
+    /// The check and the insert happen while holding the file lock, so when several callers race
+    /// for the same key exactly one of them gets `true`, e.g. to take a lock held under that key.
+    pub fn set_if_absent<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<BooleanResponse> {
+        let (_, inserted) = self.get_or_insert(key, value, options)?;
+        Ok(BooleanResponse { value: inserted })
+    }
+
+    /// Stores `value` only if the key holds a live item, returning whether it was stored
+    ///
+    /// A missing or expired key is left alone and the cache file isn't written, so an item that
+    /// was removed in the meantime isn't brought back.
+    pub fn replace<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<BooleanResponse> {
+        check_key(&key, self.max_key_len)?;
+
+        // Serialize the value to JSON first (do this outside the lock)
+        let value_json = serde_json::to_value(value)
+            .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
+
+        let now = self.now()?;
+
+        // Acquire lock for file operations, held until the new value is written
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        if !data.get(&key).is_some_and(|entry| !entry.is_expired(now)) {
+            return Ok(BooleanResponse { value: false });
+        }
+
+        self.store_locked(&cache_file_path, &mut data, key, value_json, options, now)?;
+        Ok(BooleanResponse { value: true })
+    }
+
+    /// Stores `value` and returns the value it replaced, or `None` if the key was missing or
+    /// expired
+    ///
+    /// The read and the write happen while holding the file lock, so no other write can slip in
+    /// between, e.g. when swapping a feature flag.
+    pub fn getset<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<Option<serde_json::Value>> {
+        check_key(&key, self.max_key_len)?;
+
+        // Serialize the value to JSON first (do this outside the lock)
+        let value_json = serde_json::to_value(value)
+            .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
+
+        let now = self.now()?;
+
+        // Acquire lock for file operations, held until the new value is written
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // An undecodable previous value is handled like `get` handles it; the new value replaces
+        // it either way
+        let previous = match data.get(&key) {
+            Some(entry) if !entry.is_expired(now) => {
+                self.decode_stored(&key, &mut data, &mut ReadChanges::default())?
+            }
+            _ => None,
+        };
+
+        self.store_locked(&cache_file_path, &mut data, key, value_json, options, now)?;
+        Ok(previous)
+    }
+
+    /// Stores a value while the caller holds the file lock, validating it the way `set` does
+    fn store_locked(
+        &self,
+        cache_file_path: &PathBuf,
+        data: &mut Items,
+        key: String,
+        value_json: serde_json::Value,
+        options: Option<SetItemOptions>,
+        now: u64,
+    ) -> crate::Result<()> {
+        // Reject values that don't match the schema registered for their key, or are too large
+        self.schemas.validate(&key, &value_json)?;
+        self.check_value_size(&key, &value_json)?;
+
+        let entry = self.new_entry(value_json.clone(), options.as_ref(), now)?;
+        self.check_fits(&key, &entry)?;
+        let mut memory_entry = entry.to_memory(value_json, now);
+        let (version, changed) = self.place_entry(data, &key, entry);
+        memory_entry.version = version;
+        if !changed {
+            return Ok(());
+        }
+
+        // Make room for the new item if the cache is full
+        let evicted = self.evict_over_capacity(data, Some(&key), now);
+
+        // Save the updated cache to file. Without a size limit no other item changed, so
+        // logging the new value is enough
+        let written = if self.durability == Durability::WriteAheadLog && !self.is_bounded() {
+            let record = WalRecord::Set {
+                key: key.clone(),
+                entry: Box::new(data[&key].clone()),
+            };
+            self.append_to_wal(cache_file_path, &record, data)
+        } else {
+            self.write_data(cache_file_path, data)
+        };
+        written.map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        let mut cache = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for key in &evicted {
+            cache.remove(key);
+        }
+        cache.insert(key.clone(), memory_entry);
+        drop(cache);
+
+        self.emit_changes(SET_EVENT, [&key], ChangeReason::Set);
+        self.emit_changes(REMOVED_EVENT, &evicted, ChangeReason::Evicted);
+        Ok(())
+    }
+
+    /// Get the value held under `key`, first storing `value` if the key is missing or expired,
+    /// along with whether it was stored
+    fn get_or_insert<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<(serde_json::Value, bool)> {
+        check_key(&key, self.max_key_len)?;
+
+        // Serialize the value to JSON first (do this outside the lock)
+        let value_json = serde_json::to_value(value)
+            .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
+
+        let now = self.now()?;
+
+        // Acquire lock for file operations, held until the new item is written
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let mut changes = ReadChanges::default();
+        if let Some(stored) = self.load_from_data(&key, &mut data, &mut changes, now)? {
+            self.save_read_changes(&cache_file_path, &data, &changes)?;
+            return Ok((stored.value, false));
+        }
+
+        // Reject values that don't match the schema registered for their key, or are too large
+        self.schemas.validate(&key, &value_json)?;
+        self.check_value_size(&key, &value_json)?;
+
+        let entry = self.new_entry(value_json.clone(), options.as_ref(), now)?;
+        self.check_fits(&key, &entry)?;
+        let mut memory_entry = entry.to_memory(value_json.clone(), now);
+        let (version, _) = self.place_entry(&mut data, &key, entry);
+        memory_entry.version = version;
+
+        // Make room for the new item if the cache is full
+        let evicted = self.evict_over_capacity(&mut data, Some(&key), now);
+
+        // Save the updated cache to file. Without a size limit or a dropped undecodable item no
+        // other item changed, so logging the new one is enough
+        let written = if self.durability == Durability::WriteAheadLog
+            && !self.is_bounded()
+            && !changes.evicted
+        {
+            let record = WalRecord::Set {
+                key: key.clone(),
+                entry: Box::new(data[&key].clone()),
+            };
+            self.append_to_wal(&cache_file_path, &record, &data)
+        } else {
+            self.write_data(&cache_file_path, &data)
+        };
+        written.map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        let mut cache = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for key in &evicted {
+            cache.remove(key);
+        }
+        cache.insert(key.clone(), memory_entry);
+        drop(cache);
+
+        self.emit_changes(SET_EVENT, [&key], ChangeReason::Set);
+        self.emit_changes(REMOVED_EVENT, &evicted, ChangeReason::Evicted);
+        Ok((value_json, true))
+    }
+
+    /// Sets several values in a single write, each with its own options
+    ///
+    /// Either every item is stored or, if any value fails to serialize, validate or compress,
+    /// none is and the cache is left unchanged.
+    pub fn set_many<T: Serialize>(
+        &self,
+        items: Vec<(String, T, Option<SetItemOptions>)>,
+    ) -> crate::Result<EmptyResponse> {
+        let now = self.now()?;
+
+        // Prepare every entry before touching the cache, so a bad item can't leave it half updated
+        let mut prepared = Vec::with_capacity(items.len());
+        for (key, value, options) in items {
+            check_key(&key, self.max_key_len)?;
+            let value_json = serde_json::to_value(value)
+                .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
+            self.schemas.validate(&key, &value_json)?;
+            self.check_value_size(&key, &value_json)?;
+            let entry = self.new_entry(value_json.clone(), options.as_ref(), now)?;
+            self.check_fits(&key, &entry)?;
+            prepared.push((key, value_json, entry));
+        }
+        if prepared.is_empty() {
+            return Ok(EmptyResponse::default());
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let mut memory_entries = Vec::with_capacity(prepared.len());
+        let mut stored = Vec::new();
+        for (key, value, entry) in prepared {
+            let mut memory_entry = entry.to_memory(value, now);
+            let (version, placed) = self.place_entry(&mut data, &key, entry);
+            memory_entry.version = version;
+            if placed {
+                stored.push(key.clone());
+            }
+            memory_entries.push((key, memory_entry));
+        }
+
+        let mut evicted = Vec::new();
+        if !stored.is_empty() {
+            evicted = self.evict_over_capacity(&mut data, None, now);
+            self.write_data(&cache_file_path, &data)
+                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+        }
+
+        // The values are only held in memory once they're safely stored
+        let mut cache = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for (key, memory_entry) in memory_entries {
+            cache.insert(key, memory_entry);
+        }
+        for key in &evicted {
+            cache.remove(key);
+        }
+        drop(cache);
+
+        self.emit_changes(SET_EVENT, &stored, ChangeReason::Set);
+        self.emit_changes(REMOVED_EVENT, &evicted, ChangeReason::Evicted);
+        Ok(EmptyResponse::default())
+    }
+
+    /// Build the entry storing a value, with its expiration and compression taken from the options
+    fn new_entry(
+        &self,
+        value: serde_json::Value,
+        options: Option<&SetItemOptions>,
+        now: u64,
+    ) -> crate::Result<CacheEntry> {
+        let ttl = self.resolve_ttl(options.and_then(|opt| opt.ttl));
+
+        let mut entry = CacheEntry {
+            expires_at: self.expires_at(now, ttl),
+            created_at: Some(now),
+            soft_ttl: options.and_then(|opt| opt.soft_ttl),
+            stale_ttl: options.and_then(|opt| opt.stale_ttl),
+            metadata: options.and_then(|opt| opt.metadata.clone()),
+            tags: options.and_then(|opt| opt.tags.clone()),
+            ..Default::default()
+        };
+
+        // Take a snapshot of the current compression settings
+        let compression = self
+            .compression
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        // Create a temporary compression config based on options
+        let temp_compression = CompressionConfig {
+            enabled: options
+                .and_then(|opt| opt.compress)
+                .unwrap_or(compression.enabled),
+            level: compression.level,
+            threshold: compression.threshold,
+            method: options
+                .and_then(|opt| opt.compression_method.clone())
+                .unwrap_or(compression.method),
+        };
+
+        self.encode_value(&mut entry, value, &temp_compression)?;
+        Ok(entry)
+    }
+
+    /// Put an entry into the cache contents, returning its version and whether anything changed
+    ///
+    /// Writing an identical entry would only cost a file rewrite, so it's skipped and the
+    /// existing entry keeps its version.
+    fn place_entry(
+        &self,
+        data: &mut HashMap<String, CacheEntry>,
+        key: &str,
+        mut entry: CacheEntry,
+    ) -> (Option<u64>, bool) {
+        if let Some(existing) = data.get(key) {
+            if self.skip_unchanged_writes && existing.same_contents(&entry) {
+                return (existing.version, false);
+            }
+
+            // Keep the read count when replacing an item
+            entry.access_count = existing.access_count;
+        }
+
+        let version = Some(self.changes.next_token());
+        entry.version = version;
+        data.insert(key.to_string(), entry);
+        (version, true)
+    }
+
+    /// Inserts every key/value pair of a JSON object in a single write, returning how many were stored
+    ///
+    /// Items get the default TTL and the current compression settings. Existing live items are
+    /// overwritten unless `skip_existing` is set.
+    pub fn load_from_json(
+        &self,
+        map: serde_json::Map<String, serde_json::Value>,
+        default_ttl: Option<u64>,
+        skip_existing: bool,
+    ) -> crate::Result<usize> {
+        let items = self.items_with_ttl(map, default_ttl)?;
+        self.store_all(items, skip_existing, "loadFromJson")
+    }
+
+    /// Stores every value with the same TTL in a single write
+    ///
+    /// Items get the current compression settings, like with [`Cache::set`] without options.
+    pub fn set_all(
+        &self,
+        values: HashMap<String, serde_json::Value>,
+        ttl: Option<u64>,
+    ) -> crate::Result<()> {
+        let items = self.items_with_ttl(values, ttl)?;
+        self.store_all(items, false, "setAll").map(|_| ())
+    }
+
+    /// Reads a file written by [`Cache::export`] and stores its items in a single write,
+    /// returning how many were stored
+    ///
+    /// Items keep their expiration time, so those that have expired since the export are skipped.
+    /// Values are compressed according to the current settings. Existing live items are only
+    /// replaced with `overwrite`.
+    pub fn import(&self, src: PathBuf, overwrite: bool) -> crate::Result<usize> {
+        let file = fs::File::open(&src)
+            .map_err(|e| Error::Cache(format!("Failed to open import file: {}", e)))?;
+        let items: Vec<ExportedItem> = serde_json::from_reader(BufReader::new(file))?;
+
+        self.store_all(items, !overwrite, "import")
+    }
+
+    /// Batch items that all expire `ttl` seconds from now, with the configured jitter
+    fn items_with_ttl(
+        &self,
+        values: impl IntoIterator<Item = (String, serde_json::Value)>,
+        ttl: Option<u64>,
+    ) -> crate::Result<Vec<ExportedItem>> {
+        let now = self.now()?;
+        let ttl = self.resolve_ttl(ttl);
+        Ok(values
+            .into_iter()
+            .map(|(key, value)| ExportedItem {
+                key,
+                value,
+                expires_at: self.expires_at(now, ttl),
+                created_at: Some(now),
+                metadata: None,
+                tags: None,
+            })
+            .collect())
+    }
+
+    /// Stores a batch of items under a single lock and write, returning how many were stored
+    ///
+    /// Items that have already expired are skipped.
+    fn store_all(
+        &self,
+        items: Vec<ExportedItem>,
+        skip_existing: bool,
+        operation: &'static str,
+    ) -> crate::Result<usize> {
+        // Reject the whole batch if any value doesn't match its schema or is too large
+        for item in &items {
+            check_key(&item.key, self.max_key_len)?;
+            self.schemas.validate(&item.key, &item.value)?;
+            self.check_value_size(&item.key, &item.value)?;
+        }
+
+        let now = self.now()?;
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Take a snapshot of the current compression settings
+        let compression = self
+            .compression
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        // Encode every item before storing any, so a failure leaves the cache untouched
+        let mut progress = ProgressReporter::new(&self.app, operation, items.len());
+        let mut encoded = Vec::with_capacity(items.len());
+        let mut encoded_keys = HashSet::new();
+        for (index, item) in items.into_iter().enumerate() {
+            progress.update(index);
+            let key = item.key;
+            if skip_existing
+                && (data.get(&key).is_some_and(|entry| !entry.is_expired(now))
+                    || !encoded_keys.insert(key.clone()))
+            {
+                continue;
+            }
+
+            let mut entry = CacheEntry {
+                expires_at: self.capped_expiry(now, item.expires_at),
+                created_at: item.created_at.or(Some(now)),
+                metadata: item.metadata,
+                tags: item.tags,
+                version: Some(self.changes.next_token()),
+                ..Default::default()
+            };
+            if entry.is_expired(now) {
+                continue;
+            }
+            let memory_entry = entry.to_memory(item.value.clone(), now);
+            self.encode_value(&mut entry, item.value, &compression)?;
+            self.check_fits(&key, &entry)?;
+            encoded.push((key, entry, memory_entry));
+        }
+        progress.finish();
+
+        if encoded.is_empty() {
+            return Ok(0);
+        }
+        let mut loaded = Vec::with_capacity(encoded.len());
+        for (key, entry, memory_entry) in encoded {
+            data.insert(key.clone(), entry);
+            loaded.push((key, memory_entry));
+        }
+
+        // Items from this batch can be evicted too if it doesn't fit
+        let evicted = self.evict_over_capacity(&mut data, None, now);
+
+        // Save all items at once
+        self.write_data(&cache_file_path, &data)
+            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        let count = loaded.len();
+        let mut cache = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut stored = Vec::with_capacity(count);
+        for (key, memory_entry) in loaded {
+            cache.insert(key.clone(), memory_entry);
+            stored.push(key);
+        }
+        for key in &evicted {
+            cache.remove(key);
+        }
+        drop(cache);
+
+        self.emit_changes(SET_EVENT, &stored, ChangeReason::Set);
+        self.emit_changes(REMOVED_EVENT, &evicted, ChangeReason::Evicted);
+        Ok(count)
+    }
+
+    /// Gets a value from the cache
+    ///
+    /// If the value has outlived its soft TTL it's still returned, but a
+    /// [`STALE_EVENT`] is emitted so the frontend can refresh it. On a miss the
+    /// backing store is consulted, if one is configured, and its value cached.
+    pub fn get(&self, key: &str) -> crate::Result<Option<serde_json::Value>> {
+        Ok(self.get_with_source(key)?.map(|(value, _)| value))
+    }
+
+    /// Gets a value, or an expired one still within the `stale_ttl` it was stored with
+    ///
+    /// The flag is `true` if the value has expired, so the caller can serve it while refreshing
+    /// it. Items past their stale window return `None`. Values served during the
+    /// `stale_grace_secs` grace period are reported as stale too.
+    pub fn get_stale(&self, key: &str) -> crate::Result<Option<(serde_json::Value, bool)>> {
+        check_key(key, self.max_key_len)?;
+
+        let now = self.now()?;
+        if let Some(stored) = self.get_stored(key)? {
+            let stale = stored.expires_at.is_some_and(|expires_at| expires_at < now);
+            return Ok(Some((stored.value, stale)));
+        }
+
+        // Fall back to an expired item that's still within its stale window
+        let cache_file_path = self.lock_for_read();
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+        if !data
+            .get(key)
+            .is_some_and(|entry| !entry.is_past_stale_window(now))
+        {
+            return Ok(None);
+        }
+
+        let mut changes = ReadChanges::default();
+        let value = self.decode_stored(key, &mut data, &mut changes)?;
+        self.save_read_changes(&cache_file_path, &data, &changes)?;
+        Ok(value.map(|value| (value, true)))
+    }
+
+    /// Gets a value from the cache along with where it was found, for diagnosing hit ratios
+    pub fn get_with_source(
+        &self,
+        key: &str,
+    ) -> crate::Result<Option<(serde_json::Value, CacheSource)>> {
+        check_key(key, self.max_key_len)?;
+
+        if let Some(stored) = self.get_stored(key)? {
+            return Ok(Some((stored.value, stored.source)));
+        }
+
+        // Fall back to the backing store
+        if let Some(store) = &self.backing_store {
+            if let Some(value) = store.load(key)? {
+                let options = SetItemOptions {
+                    ttl: self.backing_store_ttl,
+                    ..Default::default()
+                };
+                self.set(key.to_string(), &value, Some(options))?;
+                return Ok(Some((value, CacheSource::BackingStore)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets several values at once, reading the cache file at most once
+    ///
+    /// Every requested key is in the result, with `None` for items that are missing or expired.
+    /// Unlike [`Cache::get`] the backing store isn't consulted.
+    pub fn get_many(
+        &self,
+        keys: &[String],
+    ) -> crate::Result<HashMap<String, Option<serde_json::Value>>> {
+        let now = self.now()?;
+
+        let mut values = HashMap::with_capacity(keys.len());
+        let mut misses = Vec::new();
+        for key in keys {
+            match self.get_from_memory(key, now) {
+                Some(stored) => {
+                    values.insert(key.clone(), Some(stored.value));
+                }
+                None => misses.push(key),
+            }
+        }
+
+        if !misses.is_empty() {
+            // Acquire lock for file operations
+            let cache_file_path = self.lock_for_read();
+
+            // Get current cache data
+            let mut data = self
+                .read_data(&cache_file_path)
+                .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+            // Items evicted before a failing read are saved all the same
+            let mut changes = ReadChanges::default();
+            let mut loaded = Ok(());
+            for key in misses {
+                match self.load_from_data(key, &mut data, &mut changes, now) {
+                    Ok(stored) => {
+                        values.insert(key.clone(), stored.map(|stored| stored.value));
+                    }
+                    Err(e) => {
+                        loaded = Err(e);
+                        break;
+                    }
+                }
+            }
+            self.save_read_changes(&cache_file_path, &data, &changes)?;
+            loaded?;
+        }
+
+        Ok(values)
+    }
+
+    /// Gets a value along with its expiration time, compression and metadata
+    ///
+    /// Reads like [`Cache::get`], including falling back to the backing store on a miss.
+    pub fn get_item(&self, key: &str) -> crate::Result<Option<CacheItem<serde_json::Value>>> {
+        let stored = match self.get_stored(key)? {
+            Some(stored) => stored,
+            // A value loaded from the backing store is stored first, so it can be looked up again
+            None => match self.get_with_source(key)? {
+                Some(_) => match self.get_stored(key)? {
+                    Some(stored) => stored,
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(CacheItem {
+            value: stored.value,
+            expires_at: stored.expires_at,
+            is_compressed: stored.is_compressed,
+            metadata: stored.metadata,
+        }))
+    }
+
+    /// Gets a value unless the caller already holds its current version
+    ///
+    /// `since` is the version returned by an earlier call. Values stored by older versions of the
+    /// plugin have no version and are always returned. On a miss the backing store is consulted
+    /// like in [`Cache::get`].
+    pub fn get_if_changed(
+        &self,
+        key: &str,
+        since: Option<u64>,
+    ) -> crate::Result<Option<GetConditional>> {
+        let stored = match self.get_stored(key)? {
+            Some(stored) => stored,
+            // A value loaded from the backing store is stored first, which assigns its version
+            None => match self.get_with_source(key)? {
+                Some(_) => match self.get_stored(key)? {
+                    Some(stored) => stored,
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            },
+        };
+
+        if stored.version.is_some() && stored.version == since {
+            return Ok(Some(GetConditional::NotModified));
+        }
+
+        Ok(Some(GetConditional::Modified {
+            value: stored.value,
+            version: stored.version,
+        }))
+    }
+
+    /// Gets the JSON type of a stored value, e.g. `"object"` or `"string"`, without returning it
+    ///
+    /// Unlike [`Cache::get`] this doesn't count as a read: it emits no events and doesn't load
+    /// the value into memory.
+    pub fn value_type(&self, key: &str) -> crate::Result<Option<String>> {
+        let now = self.now()?;
+
+        // Values held in memory are already decoded
+        if let Some(entry) = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)
+        {
+            if !self.past_grace(entry, now) {
+                return Ok(Some(crate::json_type_name(&entry.value).to_string()));
+            }
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self.lock_for_read();
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        if !data.get(key).is_some_and(|entry| !entry.is_expired(now)) {
+            return Ok(None);
+        }
+
+        // Compressed values have to be decoded to tell what they hold, and one that can't be
+        // decoded is handled like `get` handles it
+        let mut changes = ReadChanges::default();
+        let value = self.decode_stored(key, &mut data, &mut changes)?;
+        self.save_read_changes(&cache_file_path, &data, &changes)?;
+        Ok(value.map(|value| crate::json_type_name(&value).to_string()))
+    }
+
+    /// Gets the time left until an item expires
+    ///
+    /// Like [`Cache::value_type`] this doesn't count as a read. Items kept around during the stale
+    /// grace period have already expired and are reported as [`Ttl::Missing`].
+    pub fn ttl(&self, key: &str) -> crate::Result<Ttl> {
+        let now = self.now()?;
+
+        let remaining = |expires_at: Option<u64>| match expires_at {
+            Some(expires_at) => Ttl::ExpiresIn {
+                secs: expires_at - now,
+            },
+            None => Ttl::NoExpiry,
+        };
+
+        // First check the in-memory cache
+        if let Some(entry) = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)
+        {
+            if !entry.expires_at.is_some_and(|expires| expires < now) {
+                return Ok(remaining(entry.expires_at));
+            }
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Get current cache data
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        match data.get(key) {
+            Some(entry) if !entry.is_expired(now) => Ok(remaining(entry.expires_at)),
+            _ => Ok(Ttl::Missing),
+        }
+    }
+
+    /// Gets a value from the in-memory cache or the cache file
+    fn get_stored(&self, key: &str) -> crate::Result<Option<StoredValue>> {
+        let now = self.now()?;
+
+        // First check the in-memory cache
+        if let Some(stored) = self.get_from_memory(key, now) {
+            return Ok(Some(stored));
+        }
+
+        // If not in memory cache, check the file
+        // Acquire lock for file operations
+        let cache_file_path = self.lock_for_read();
+
+        // Get current cache data
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let mut changes = ReadChanges::default();
+        let stored = self.load_from_data(key, &mut data, &mut changes, now)?;
+        self.save_read_changes(&cache_file_path, &data, &changes)?;
+
+        Ok(stored)
+    }
+
+    /// Gets a value held in memory, unless it has expired
+    fn get_from_memory(&self, key: &str, now: u64) -> Option<StoredValue> {
+        let mut cache = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let entry = cache.get_mut(key)?;
+
+        // Check if expired, allowing for the grace period
+        if self.past_grace(entry, now) {
+            // Item has expired, remove from in-memory cache
+            cache.remove(key);
+            return None;
+        }
+
+        // Not expired, return the cached value
+        entry.last_access = now;
+        let entry = entry.clone();
+        drop(cache);
+        self.record_access(key, now);
+        self.access_metrics.hit();
+
+        // A value served during the grace period is always reported as stale
+        let stale_at = match entry.expires_at {
+            Some(expires) if expires < now => Some(expires),
+            _ => entry.stale_at,
+        };
+        self.notify_if_stale(key, stale_at, now);
+        Some(StoredValue {
+            value: entry.value,
+            source: CacheSource::Memory,
+            version: entry.version,
+            expires_at: entry.expires_at,
+            is_compressed: entry.is_compressed,
+            metadata: entry.metadata,
+        })
+    }
+
+    /// Gets a value from the contents of the cache file and keeps it in memory
+    ///
+    /// Entries removed or migrated along the way are recorded in `changes`, to be saved once
+    /// all reads are done.
+    fn load_from_data(
+        &self,
+        key: &str,
+        data: &mut HashMap<String, CacheEntry>,
+        changes: &mut ReadChanges,
+        now: u64,
+    ) -> crate::Result<Option<StoredValue>> {
+        let Some(entry) = data.get(key) else {
+            self.access_metrics.miss(false);
+            return Ok(None);
+        };
+
+        // Check if the item has expired
+        if entry.is_expired(now) {
+            self.access_metrics.miss(true);
+            return Ok(None);
+        }
+
+        // Decompress the value if needed
+        let Some(value) = self.decode_stored(key, data, changes)? else {
+            self.access_metrics.miss(false);
+            return Ok(None);
+        };
+        let entry = &data[key];
+        let memory_entry = entry.to_memory(value.clone(), now);
+
+        // Migrate the entry to the current compression settings if they changed
+        if self.recompress_on_read && entry.is_compressed.unwrap_or(false) {
+            let compression = self
+                .compression
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            if compression.enabled
+                && (entry.compression_method.as_ref() != Some(&compression.method)
+                    || entry.compression_level != Some(compression.level))
+            {
+                let mut migrated = entry.clone();
+                self.encode_value(&mut migrated, value.clone(), &compression)?;
+                data.insert(key.to_string(), migrated);
+                changes.migrated = true;
+            }
+        }
+
+        self.notify_if_stale(key, memory_entry.stale_at, now);
+        self.record_access(key, now);
+        self.access_metrics.hit();
+
+        // Cache the value in memory for future use
+        let stored = StoredValue {
+            value,
+            source: CacheSource::Disk,
+            version: memory_entry.version,
+            expires_at: memory_entry.expires_at,
+            is_compressed: memory_entry.is_compressed,
+            metadata: memory_entry.metadata.clone(),
+        };
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            cache.insert(key.to_string(), memory_entry);
+        }
+
+        Ok(Some(stored))
+    }
+
+    /// Decode a stored item's value, handling a failure the way `on_decode_error` says
+    ///
+    /// Returns `None` for an item that's treated as missing. With `EvictAndMiss` the item is also
+    /// removed from `data` and from memory, and `changes` notes that `data` must be saved.
+    fn decode_stored(
+        &self,
+        key: &str,
+        data: &mut HashMap<String, CacheEntry>,
+        changes: &mut ReadChanges,
+    ) -> crate::Result<Option<serde_json::Value>> {
+        let Some(entry) = data.get(key) else {
+            return Ok(None);
+        };
+        let e = match self.decode_entry(entry) {
+            Ok(value) => return Ok(Some(value)),
+            Err(e) => e,
+        };
+        if self.on_decode_error == DecodeErrorPolicy::Error {
+            return Err(e);
+        }
+        eprintln!(
+            "Warning: Treating undecodable cache item \"{}\" as missing: {}",
+            key, e
+        );
+
+        if self.on_decode_error == DecodeErrorPolicy::EvictAndMiss {
+            data.remove(key);
+            self.value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(key);
+            changes.evicted = true;
+        }
+        Ok(None)
+    }
+
+    /// Save the entries reads removed or migrated
+    fn save_read_changes(
+        &self,
+        cache_file_path: &PathBuf,
+        data: &Items,
+        changes: &ReadChanges,
+    ) -> crate::Result<()> {
+        if !changes.evicted && !changes.migrated {
+            return Ok(());
+        }
+
+        let written = self.write_data(cache_file_path, data);
+        if changes.evicted {
+            written.map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+        }
+        // The reads themselves succeeded, so a failed migration is simply retried next time
+        Ok(())
+    }
+
+    /// Gets a value deserialized into `T`, like [`Cache::get`] followed by `serde_json::from_value`
+    ///
+    /// Fails with [`Error::SerdeError`] if the stored value doesn't deserialize into `T`.
+    pub fn get_as<T: DeserializeOwned>(&self, key: &str) -> crate::Result<Option<T>> {
+        match self.get(key)? {
+            Some(value) => serde_json::from_value(value)
+                .map(Some)
+                .map_err(|e| Error::SerdeError(format!("item \"{}\": {}", key, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the outcome of a fallible operation, so known failures aren't retried until the TTL
+    ///
+    /// Read it back with [`Cache::get_result`], which keeps success and failure apart.
+    pub fn set_result<T: Serialize>(
+        &self,
+        key: String,
+        result: std::result::Result<T, String>,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<EmptyResponse> {
+        let value = serde_json::to_value(CachedResult::from(result))?;
+        self.set(key, value, options)
+    }
+
+    /// Gets an outcome stored by [`Cache::set_result`]
+    ///
+    /// Returns `Ok(Some(Err(message)))` for a cached failure. Fails if the stored value isn't an
+    /// outcome or doesn't deserialize into `T`.
+    pub fn get_result<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> crate::Result<Option<std::result::Result<T, String>>> {
+        match self.get(key)? {
+            Some(value) => Ok(Some(
+                serde_json::from_value::<CachedResult<T>>(value)?.into(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets every live value whose key starts with the prefix, deserialized into `T`
+    ///
+    /// Values that don't deserialize into `T` are skipped, and how many were skipped is logged.
+    pub fn get_all_as<T: DeserializeOwned>(
+        &self,
+        prefix: &str,
+    ) -> crate::Result<HashMap<String, T>> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        let mut values = HashMap::new();
+        let mut skipped = 0;
+        for (key, entry) in data.iter() {
+            if !key.starts_with(prefix) || entry.is_expired(now) {
+                continue;
+            }
+
+            match serde_json::from_value(self.decode_entry(entry)?) {
+                Ok(value) => {
+                    values.insert(key.clone(), value);
+                }
+                Err(_) => skipped += 1,
+            }
+        }
+
+        if skipped > 0 {
+            eprintln!(
+                "Warning: Skipped {} cache items under \"{}\" that don't match the requested type",
+                skipped, prefix
+            );
+        }
+
+        Ok(values)
+    }
+
+    /// Checks if a key exists in the cache and hasn't expired
+    ///
+    /// A live item counts as read for the eviction policy.
+    pub fn has(&self, key: &str) -> crate::Result<BooleanResponse> {
+        check_key(key, self.max_key_len)?;
+        let now = self.now()?;
+
+        // First check the in-memory cache
+        {
+            let cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(entry) = cache.get(key) {
+                // Check if expired
+                if entry.expires_at.is_some_and(|expires| expires < now) {
+                    // Item has expired, but a copy within the grace period is kept for get
+                    let past_grace = self.past_grace(entry, now);
+                    drop(cache); // Release the lock before modifying
+                    if past_grace {
+                        let mut cache = self
+                            .value_cache
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner);
+                        cache.remove(key);
+                    }
+                    self.access_metrics.miss(true);
+                    return Ok(BooleanResponse { value: false });
+                }
+
+                // Not expired, or no expiration
+                drop(cache);
+                self.record_access(key, now);
+                self.access_metrics.hit();
+                return Ok(BooleanResponse { value: true });
+            }
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self.lock_for_read();
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        match data.get(key) {
+            None => {
+                self.access_metrics.miss(false);
+                return Ok(BooleanResponse { value: false });
+            }
+            Some(entry) if entry.is_expired(now) => {
+                self.access_metrics.miss(true);
+                return Ok(BooleanResponse { value: false });
+            }
+            Some(_) => {}
+        }
+
+        // An item that can't be decoded is handled like `get` handles it
+        let mut changes = ReadChanges::default();
+        let Some(value) = self.decode_stored(key, &mut data, &mut changes)? else {
+            self.save_read_changes(&cache_file_path, &data, &changes)?;
+            self.access_metrics.miss(false);
+            return Ok(BooleanResponse { value: false });
+        };
+
+        // Add the decoded value to memory cache
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            cache.insert(key.to_string(), data[key].to_memory(value, now));
+        }
+        self.record_access(key, now);
+        self.access_metrics.hit();
+
+        Ok(BooleanResponse { value: true })
+    }
+
+    /// Removes a value from the cache
+    pub fn remove(&self, key: &str) -> crate::Result<EmptyResponse> {
+        check_key(key, self.max_key_len)?;
+
+        // Remove from in-memory cache first
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            cache.remove(key);
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Remove item if exists
+        if data.remove(key).is_some() {
+            // Save changes to file
+            let written = if self.durability == Durability::WriteAheadLog {
+                let record = WalRecord::Remove {
+                    key: key.to_string(),
+                };
+                self.append_to_wal(&cache_file_path, &record, &data)
+            } else {
+                self.write_through(&cache_file_path, &data)
+            };
+            written.map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+            self.emit_changes(REMOVED_EVENT, [key], ChangeReason::Removed);
+        }
+
+        Ok(EmptyResponse {})
+    }
+
+    /// Removes several values from the cache with a single file write
+    ///
+    /// The file is left untouched if none of the keys existed.
+    pub fn remove_many(&self, keys: &[String]) -> crate::Result<EmptyResponse> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let removed: Vec<&String> = keys
+            .iter()
+            .filter(|key| data.remove(*key).is_some())
+            .collect();
+
+        // Only rewrite the file if something was removed
+        if !removed.is_empty() {
+            self.write_through(&cache_file_path, &data)
+                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+        }
+
+        // Values can be held in memory after their item was cleaned up from disk
+        let mut cache = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for key in keys {
+            cache.remove(key);
+        }
+        drop(cache);
+
+        self.emit_changes(REMOVED_EVENT, removed, ChangeReason::Removed);
+        Ok(EmptyResponse {})
+    }
+
+    /// Sets an item to expire exactly `ttl` seconds from now, without rewriting its value
+    ///
+    /// The TTL jitter doesn't apply, since the caller asked for this item alone; `max_ttl` still
+    /// caps it. Returns `false` if the item doesn't exist or has already expired.
+    pub fn expire(&self, key: &str, ttl: u64) -> crate::Result<BooleanResponse> {
+        let now = self.now()?;
+        let expires_at = self.capped_expiry(now, Some(now.saturating_add(ttl)));
+        self.update_expiry(key, expires_at, now)
+    }
+
+    /// Keeps an item until it's removed, dropping its expiration time
+    ///
+    /// With a `max_ttl` the item expires `max_ttl` seconds from now instead. Returns `false` if
+    /// the item doesn't exist or has already expired.
+    pub fn persist(&self, key: &str) -> crate::Result<BooleanResponse> {
+        let now = self.now()?;
+        self.update_expiry(key, self.expires_at(now, None), now)
+    }
+
+    /// Replaces the expiration time of a live item in memory and on disk
+    fn update_expiry(
+        &self,
+        key: &str,
+        expires_at: Option<u64>,
+        now: u64,
+    ) -> crate::Result<BooleanResponse> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        match data.get_mut(key) {
+            Some(entry) if !entry.is_expired(now) => entry.expires_at = expires_at,
+            _ => return Ok(BooleanResponse { value: false }),
+        }
+
+        // Only this item changed, so logging it is enough
+        let written = if self.durability == Durability::WriteAheadLog {
+            let record = WalRecord::Set {
+                key: key.to_string(),
+                entry: Box::new(data[key].clone()),
+            };
+            self.append_to_wal(&cache_file_path, &record, &data)
+        } else {
+            self.write_data(&cache_file_path, &data)
+        };
+        written.map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        if let Some(entry) = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(key)
+        {
+            entry.expires_at = expires_at;
+        }
+
+        self.emit_changes(SET_EVENT, [key], ChangeReason::ExpiryChanged);
+        Ok(BooleanResponse { value: true })
+    }
+
+    /// Moves an item to another key in a single locked operation
+    ///
+    /// Returns `false` without changing anything if `from` doesn't exist or has
+    /// expired, and fails if `to` holds a live item and `overwrite` is not set. Renaming a live
+    /// item to its own key succeeds without changing anything.
+    pub fn rename(&self, from: &str, to: &str, overwrite: bool) -> crate::Result<BooleanResponse> {
+        check_key(from, self.max_key_len)?;
+        check_key(to, self.max_key_len)?;
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        if !data.get(from).is_some_and(|entry| !entry.is_expired(now)) {
+            return Ok(BooleanResponse { value: false });
+        }
+        // Nothing would be overwritten, so there's nothing to do either
+        if from == to {
+            return Ok(BooleanResponse { value: true });
+        }
+        if !overwrite && data.get(to).is_some_and(|entry| !entry.is_expired(now)) {
+            return Err(Error::Cache(format!("Key already exists: {}", to)));
+        }
+
+        // Move the entry as-is, keeping its expiry and compression
+        let entry = data.remove(from).unwrap();
+        data.insert(to.to_string(), entry);
+
+        self.write_data(&cache_file_path, &data)
+            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        // Move the in-memory copy along with it
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            match cache.remove(from) {
+                Some(entry) => {
+                    cache.insert(to.to_string(), entry);
+                }
+                None => {
+                    cache.remove(to);
+                }
+            }
+        }
+
+        self.emit_changes(REMOVED_EVENT, [from], ChangeReason::Renamed);
+        self.emit_changes(SET_EVENT, [to], ChangeReason::Renamed);
+        Ok(BooleanResponse { value: true })
+    }
+
+    /// Counts a call against a fixed-window rate limit whose counter is stored under `key`
+    ///
+    /// The first call starts a window of `window_secs` seconds in which at most `max` calls are
+    /// allowed. The counter expires at the end of the window, so the next call starts a new one.
+    /// Rejected calls aren't counted. The key should be used for nothing but the counter.
+    pub fn rate_limit(
+        &self,
+        key: &str,
+        max: u32,
+        window_secs: u64,
+    ) -> crate::Result<RateLimitResult> {
+        check_key(key, self.max_key_len)?;
+
+        // Acquire lock for file operations, which makes the increment atomic
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        // Continue the current window, or start a new one
+        let (count, created_at, reset_at) = match data.get(key) {
+            Some(entry) if !entry.is_expired(now) && entry.expires_at.is_some() => (
+                self.decode_entry(entry)?.as_u64().unwrap_or(0),
+                entry.created_at,
+                entry.expires_at.unwrap_or(now),
+            ),
+            _ => (0, Some(now), now.saturating_add(window_secs)),
+        };
+
+        if count >= u64::from(max) {
+            return Ok(RateLimitResult {
+                allowed: false,
+                remaining: 0,
+                reset_at,
+            });
+        }
+
+        let count = count + 1;
+        let value = serde_json::Value::from(count);
+        let entry = CacheEntry {
+            value: value.clone(),
+            expires_at: Some(reset_at),
+            is_compressed: Some(false),
+            created_at,
+            version: Some(self.changes.next_token()),
+            ..Default::default()
+        };
+        let memory_entry = entry.to_memory(value, now);
+        data.insert(key.to_string(), entry);
+        let evicted = self.evict_over_capacity(&mut data, Some(key), now);
+
+        self.write_data(&cache_file_path, &data)
+            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            cache.insert(key.to_string(), memory_entry);
+            for key in &evicted {
+                cache.remove(key);
+            }
+        }
+
+        self.emit_changes(SET_EVENT, [key], ChangeReason::Set);
+        self.emit_changes(REMOVED_EVENT, &evicted, ChangeReason::Evicted);
+        Ok(RateLimitResult {
+            allowed: true,
+            remaining: max - count as u32,
+            reset_at,
+        })
+    }
+
+    /// Appends a value to the array stored under `key` and returns the array's new length
+    ///
+    /// A missing or expired item starts a new array, stored with `options` as by `set`. An
+    /// existing array keeps its expiration time and metadata. Fails if the item holds something
+    /// other than an array.
+    pub fn push(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<usize> {
+        self.update_array(key, options.as_ref(), |items| {
+            items.push(value);
+            items.len()
+        })
+    }
+
+    /// Removes and returns the last element of the array stored under `key`
+    ///
+    /// Returns `None` if the item is missing, expired or an empty array. An emptied array is kept.
+    /// Fails if the item holds something other than an array.
+    pub fn pop(&self, key: &str) -> crate::Result<Option<serde_json::Value>> {
+        self.update_array(key, None, |items| items.pop())
+    }
+
+    /// Changes the array stored under `key` with `f` in a single locked operation
+    ///
+    /// A missing or expired item counts as an empty array. The array is only stored if `f` changed
+    /// its length, so a missing item stays missing unless something was added.
+    fn update_array<T>(
+        &self,
+        key: &str,
+        options: Option<&SetItemOptions>,
+        f: impl FnOnce(&mut Vec<serde_json::Value>) -> T,
+    ) -> crate::Result<T> {
+        check_key(key, self.max_key_len)?;
+
+        // Acquire lock for file operations, which makes the read and the write atomic
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        // An undecodable item is handled like `get` handles it, and replaced by a new array
+        let mut changes = ReadChanges::default();
+        let decoded = match data.get(key) {
+            Some(entry) if !entry.is_expired(now) => {
+                self.decode_stored(key, &mut data, &mut changes)?
+            }
+            _ => None,
+        };
+        let existing = data.get(key).filter(|_| decoded.is_some());
+        let mut items = match decoded {
+            Some(serde_json::Value::Array(items)) => items,
+            Some(_) => {
+                return Err(Error::Cache(format!(
+                    "Item \"{}\" doesn't hold an array",
+                    key
+                )))
+            }
+            None => Vec::new(),
+        };
+        let len = items.len();
+        let result = f(&mut items);
+        if items.len() == len {
+            self.save_read_changes(&cache_file_path, &data, &changes)?;
+            return Ok(result);
+        }
+
+        let value = serde_json::Value::Array(items);
+        self.schemas.validate(key, &value)?;
+        self.check_value_size(key, &value)?;
+
+        let mut entry = self.new_entry(value.clone(), options, now)?;
+        if let Some(existing) = existing {
+            entry.expires_at = existing.expires_at;
+            entry.created_at = existing.created_at;
+            entry.soft_ttl = existing.soft_ttl;
+            entry.stale_ttl = existing.stale_ttl;
+            entry.metadata = existing.metadata.clone();
+            entry.tags = existing.tags.clone();
+        }
+        self.check_fits(key, &entry)?;
+        let mut memory_entry = entry.to_memory(value, now);
+
+        let (version, _) = self.place_entry(&mut data, key, entry);
+        memory_entry.version = version;
+        let evicted = self.evict_over_capacity(&mut data, Some(key), now);
+
+        // Without a size limit no other item changed, so logging this one is enough
+        let written = if self.durability == Durability::WriteAheadLog && !self.is_bounded() {
+            let record = WalRecord::Set {
+                key: key.to_string(),
+                entry: Box::new(data[key].clone()),
+            };
+            self.append_to_wal(&cache_file_path, &record, &data)
+        } else {
+            self.write_data(&cache_file_path, &data)
+        };
+        written.map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        let mut cache = self
+            .value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        cache.insert(key.to_string(), memory_entry);
+        for key in &evicted {
+            cache.remove(key);
+        }
+        drop(cache);
+
+        self.emit_changes(SET_EVENT, [key], ChangeReason::Set);
+        self.emit_changes(REMOVED_EVENT, &evicted, ChangeReason::Evicted);
+        Ok(result)
+    }
+
+    /// Runs a function over every live item and stores what it returns, in a single locked operation
+    ///
+    /// Returning `None` removes the item. Rewritten items keep their expiry but are compressed
+    /// according to the current settings. Returns how many items were changed or removed.
+    pub fn transform(
+        &self,
+        f: impl Fn(&str, serde_json::Value) -> Option<serde_json::Value>,
+    ) -> crate::Result<usize> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        // Take a snapshot of the current compression settings
+        let compression = self
+            .compression
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        let live_keys: Vec<String> = data
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        // Work out every change before applying any, so a failure leaves the cache untouched
+        let mut progress = ProgressReporter::new(&self.app, "transform", live_keys.len());
+        let mut updates: Vec<(String, Option<CacheEntry>)> = Vec::new();
+        let mut changed: Vec<(String, Option<CacheValueEntry>)> = Vec::new();
+        for (index, key) in live_keys.into_iter().enumerate() {
+            progress.update(index);
+            let entry = &data[&key];
+            let value = self.decode_entry(entry)?;
+
+            match f(&key, value.clone()) {
+                Some(new_value) if new_value == value => {}
+                Some(new_value) => {
+                    let mut entry = entry.clone();
+                    entry.version = Some(self.changes.next_token());
+                    self.encode_value(&mut entry, new_value.clone(), &compression)?;
+                    let memory_entry = entry.to_memory(new_value, now);
+                    updates.push((key.clone(), Some(entry)));
+                    changed.push((key, Some(memory_entry)));
+                }
+                None => {
+                    updates.push((key.clone(), None));
+                    changed.push((key, None));
+                }
+            }
+        }
+        progress.finish();
+
+        // Only rewrite the file if something changed
+        let count = changed.len();
+        if count > 0 {
+            for (key, entry) in updates {
+                match entry {
+                    Some(entry) => data.insert(key, entry),
+                    None => data.remove(&key),
+                };
+            }
+            self.write_data(&cache_file_path, &data)
+                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            for (key, memory_entry) in changed {
+                match memory_entry {
+                    Some(memory_entry) => {
+                        cache.insert(key.clone(), memory_entry);
+                        self.emit_changes(SET_EVENT, [&key], ChangeReason::Set);
+                    }
+                    None => {
+                        cache.remove(&key);
+                        self.emit_changes(REMOVED_EVENT, [&key], ChangeReason::Removed);
+                    }
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Clears the entire cache
+    pub fn clear(&self) -> crate::Result<EmptyResponse> {
+        // Clear the in-memory cache
+        {
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            cache.clear();
+        }
+
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Only read the cache file if the keys of its items are reported
+        let mut data = if self.emit_events || self.memory_store.is_some() {
+            self.read_data(&cache_file_path)
+                .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?
+        } else {
+            Items::File(HashMap::new())
+        };
+        let cleared: Vec<String> = data.drain().map(|(key, _)| key).collect();
+
+        // Just write an empty cache
+        self.write_through(&cache_file_path, &data)
+            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        self.emit_changes(REMOVED_EVENT, &cleared, ChangeReason::Cleared);
+        Ok(EmptyResponse {})
+    }
+
+    /// Remove every item whose key starts with the prefix, returning how many were removed
+    pub fn clear_prefix(&self, prefix: &str) -> crate::Result<usize> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let matching_keys: Vec<String> = data
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        // Only rewrite the file if something was removed
+        if !matching_keys.is_empty() {
+            for key in &matching_keys {
+                data.remove(key);
+            }
+
+            self.write_through(&cache_file_path, &data)
+                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+        }
+
+        // Values can be held in memory after their item was cleaned up from disk
+        self.value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|key, _| !key.starts_with(prefix));
+
+        self.emit_changes(REMOVED_EVENT, &matching_keys, ChangeReason::Cleared);
+        Ok(matching_keys.len())
+    }
+
+    /// Remove every item whose key matches a glob pattern, returning how many were removed
+    ///
+    /// `*` matches any run of characters and `?` a single one, against the whole key. Every key
+    /// is checked, so it takes time proportional to the size of the cache.
+    pub fn remove_matching(&self, pattern: &str) -> crate::Result<usize> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let matching_keys: Vec<String> = data
+            .keys()
+            .filter(|key| glob::matches(pattern, key))
+            .cloned()
+            .collect();
+
+        // Only rewrite the file if something was removed
+        if !matching_keys.is_empty() {
+            for key in &matching_keys {
+                data.remove(key);
+            }
+
+            self.write_through(&cache_file_path, &data)
+                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+        }
+
+        // Values can be held in memory after their item was cleaned up from disk
+        self.value_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|key, _| !glob::matches(pattern, key));
+
+        self.emit_changes(REMOVED_EVENT, &matching_keys, ChangeReason::Removed);
+        Ok(matching_keys.len())
+    }
+
+    /// Remove every item stored more than `age_secs` seconds ago, returning how many were removed
+    ///
+    /// Items written by older versions of the plugin don't have a creation time and are kept.
+    pub fn clear_older_than(&self, age_secs: u64) -> crate::Result<usize> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+        let cutoff = now.saturating_sub(age_secs);
+
+        let old_keys: Vec<String> = data
+            .iter()
+            .filter(|(_, entry)| {
+                entry
+                    .created_at
+                    .is_some_and(|created_at| created_at < cutoff)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        // Only rewrite the file if something was removed
+        if !old_keys.is_empty() {
+            for key in &old_keys {
+                data.remove(key);
+            }
+
+            self.write_through(&cache_file_path, &data)
+                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            for key in &old_keys {
+                cache.remove(key);
+            }
+        }
+
+        self.emit_changes(REMOVED_EVENT, &old_keys, ChangeReason::Cleared);
+        Ok(old_keys.len())
+    }
+
+    /// Remove every item stored with the tag, returning how many were removed
+    ///
+    /// Scans every item, so it costs about as much as [`Cache::clear_prefix`].
+    pub fn invalidate_tag(&self, tag: &str) -> crate::Result<usize> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let mut data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let tagged_keys: Vec<String> = data
+            .iter()
+            .filter(|(_, entry)| entry.has_tag(tag))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        // Only rewrite the file if something was removed
+        if !tagged_keys.is_empty() {
+            for key in &tagged_keys {
+                data.remove(key);
+            }
+
+            self.write_through(&cache_file_path, &data)
+                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+            let mut cache = self
+                .value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            for key in &tagged_keys {
+                cache.remove(key);
+            }
+        }
+
+        self.emit_changes(REMOVED_EVENT, &tagged_keys, ChangeReason::Cleared);
+        Ok(tagged_keys.len())
+    }
+
+    /// Get the total number of items in the cache
+    pub fn size(&self) -> crate::Result<usize> {
+        self.with_counts(|counts| counts.total)
+    }
+
+    /// Get the number of non-expired items in the cache
+    pub fn active_size(&self) -> crate::Result<usize> {
+        // Get current time
+        let now = self.now()?;
+
+        // Items without expiration are always active
+        self.with_counts(|counts| counts.active(now))
+    }
+
+    /// Get cache statistics, without reading the cache file
+    pub fn stats(&self) -> crate::Result<CacheStats> {
+        // Get current time
+        let now = self.now()?;
+
+        // The write-ahead log holds items too, so it counts towards the size on disk
+        let path = self.get_cache_file_path();
+        let total_bytes_on_disk = match self.is_persisted() {
+            false => 0,
+            true => [path.clone(), sibling_path(&path, WAL_SUFFIX)]
+                .iter()
+                .filter_map(|path| fs::metadata(path).ok())
+                .map(|metadata| metadata.len())
+                .sum(),
+        };
+
+        let (compression_ratio, bytes_saved) = self.compression_metrics();
+        self.with_counts(|counts| CacheStats {
+            total_size: counts.total,
+            active_size: counts.active(now),
+            compression_ratio,
+            bytes_saved,
+            compressed_count: counts.compressed_count,
+            uncompressed_count: counts.total - counts.compressed_count,
+            compressed_bytes: counts.compressed_bytes,
+            uncompressed_bytes: counts.uncompressed_bytes,
+            decompressed_bytes: counts.decompressed_bytes,
+            total_bytes_on_disk,
+            hits: self.access_metrics.hits.load(Ordering::Relaxed),
+            misses: self.access_metrics.misses.load(Ordering::Relaxed),
+            expirations: self.access_metrics.expirations.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Read the item counts, which are kept up to date by every write without taking the file lock
+    fn with_counts<T>(&self, f: impl FnOnce(&EntryCounts) -> T) -> crate::Result<T> {
+        if let Some(counts) = self
+            .changes
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            return Ok(f(counts));
+        }
+
+        // Nothing was written yet, so count the items in the file once
+        let cache_file_path = self
+            .file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        let counts = EntryCounts::new(&data);
+        let result = f(&counts);
+        *self
+            .changes
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(counts);
+
+        Ok(result)
+    }
+
+    /// Get statistics for the items whose keys start with the given prefix
+    pub fn stats_prefix(&self, prefix: &str) -> crate::Result<CacheStats> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        Ok(Self::compute_stats(&data, now, |key| {
+            key.starts_with(prefix)
+        }))
+    }
+
+    /// Get the keys of all live items in lexicographic order, optionally only those under a prefix
+    pub fn keys(&self, prefix: Option<&str>) -> crate::Result<Vec<String>> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        let mut keys: Vec<String> = data
+            .iter()
+            .filter(|(key, entry)| {
+                prefix.map_or(true, |prefix| key.starts_with(prefix)) && !entry.is_expired(now)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort_unstable();
+
+        Ok(keys)
+    }
+
+    /// List the keys of all live items matching a glob pattern, in lexicographic order
+    ///
+    /// `*` matches any run of characters and `?` a single one, against the whole key. Every key
+    /// is checked, so it takes time proportional to the size of the cache.
+    pub fn keys_matching(&self, pattern: &str) -> crate::Result<Vec<String>> {
+        Ok(self
+            .keys(None)?
+            .into_iter()
+            .filter(|key| glob::matches(pattern, key))
+            .collect())
+    }
+
+    /// Find the first key, in lexicographic order, of a live item holding `value`
+    ///
+    /// Scans and decodes every item, so it takes time proportional to the size of the cache.
+    pub fn find_key_by_value(&self, value: &serde_json::Value) -> crate::Result<Option<String>> {
+        // Acquire lock for file operations
+        let cache_file_path = self.lock_for_read();
+
+        // Load data from file
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        let mut entries: Vec<(&String, &CacheEntry)> = data
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+
+        for (key, entry) in entries {
+            // Values may be stored compressed or encrypted, so compare them decoded
+            if self.decode_entry(entry)? == *value {
+                return Ok(Some(key.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get a page of keys in lexicographic order, along with the total number of keys
+    pub fn keys_page(
+        &self,
+        offset: usize,
+        limit: usize,
+        include_expired: bool,
+    ) -> crate::Result<(Vec<String>, usize)> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+
+        // Get current time
+        let now = self.now()?;
+
+        // Sort the keys so pages stay consistent between calls
+        let mut keys: Vec<String> = data
+            .iter()
+            .filter(|(_, entry)| include_expired || !entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort_unstable();
+
+        let total = keys.len();
+        let page = keys.into_iter().skip(offset).take(limit).collect();
+
+        Ok((page, total))
+    }
+
+    /// Get the compressed-to-original size ratio and the bytes saved by compression since the last reset
+    pub fn compression_metrics(&self) -> (f64, u64) {
+        let bytes_in = self.compression_metrics.bytes_in.load(Ordering::Relaxed);
+        let bytes_out = self.compression_metrics.bytes_out.load(Ordering::Relaxed);
+
+        let ratio = if bytes_in == 0 {
+            0.0
+        } else {
+            bytes_out as f64 / bytes_in as f64
+        };
+        (ratio, bytes_in.saturating_sub(bytes_out))
+    }
+
+    /// Reset the compression metrics and the hit and miss counts
+    pub fn reset_stats(&self) -> crate::Result<EmptyResponse> {
+        self.compression_metrics.reset();
+        self.access_metrics.reset();
+        Ok(EmptyResponse {})
+    }
+
+    /// Count total and active items among the entries whose keys match the filter
+    fn compute_stats(
+        data: &HashMap<String, CacheEntry>,
+        now: u64,
+        filter: impl Fn(&str) -> bool,
+    ) -> CacheStats {
+        let mut stats = CacheStats::default();
+
+        for (_, entry) in data.iter().filter(|(key, _)| filter(key)) {
+            stats.total_size += 1;
+
+            if !entry.is_expired(now) {
+                stats.active_size += 1;
+            }
+
+            if entry.is_compressed.unwrap_or(false) {
+                stats.compressed_count += 1;
+                stats.compressed_bytes += entry.stored_len();
+            } else {
+                stats.uncompressed_count += 1;
+                stats.uncompressed_bytes += entry.stored_len();
+            }
+            stats.decompressed_bytes += entry.decoded_len();
+        }
+
+        stats
+    }
+
+    /// Get a token that increases whenever the cache file is written
+    ///
+    /// Compare it with a previously read token to cheaply check whether the cache changed.
+    pub fn change_token(&self) -> crate::Result<u64> {
+        Ok(self.changes.token.load(Ordering::SeqCst))
+    }
+
+    /// Get the time of the last write to the cache file in seconds since the Unix epoch
+    pub fn last_modified(&self) -> crate::Result<Option<u64>> {
+        let last_modified = self.changes.last_modified.load(Ordering::SeqCst);
+        Ok((last_modified > 0).then_some(last_modified))
+    }
+
+    /// Get the path to the cache file
+    ///
+    /// If the cache isn't persisted, this is the configured path, which is never read or written.
+    pub fn get_cache_file_path(&self) -> PathBuf {
+        self.file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Get the cache file and its directory, and whether the file has been created yet
+    pub fn cache_path(&self) -> crate::Result<CachePath> {
+        let path = self.get_cache_file_path();
+        let dir = path
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_default();
+
+        Ok(CachePath {
+            dir: dir.to_string_lossy().into_owned(),
+            file: path.to_string_lossy().into_owned(),
+            exists: self.is_persisted() && fs::metadata(&path).is_ok(),
+        })
+    }
+
+    /// Write every item to `dest` as a pretty-printed JSON array of [`ExportedItem`]s, sorted by key
+    ///
+    /// Values are decompressed and decrypted, so the export can be read by people and other tools.
+    /// Expired items that haven't been cleaned up yet are only included with `include_expired`.
+    pub fn export(&self, dest: PathBuf, include_expired: bool) -> crate::Result<EmptyResponse> {
+        // Acquire lock for file operations
+        let cache_file_path = self
+            .file_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Load data from file
+        let data = self
+            .read_data(&cache_file_path)
+            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+        drop(cache_file_path);
+
+        // Get current time
+        let now = self.now()?;
+
+        let mut items = Vec::with_capacity(data.len());
+        for (key, entry) in data.iter() {
+            if !include_expired && entry.is_expired(now) {
+                continue;
+            }
+
+            items.push(ExportedItem {
+                key: key.clone(),
+                value: self.decode_entry(entry)?,
+                expires_at: entry.expires_at,
+                created_at: entry.created_at,
+                metadata: entry.metadata.clone(),
+                tags: entry.tags.clone(),
+            });
+        }
+        items.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::Cache(format!("Failed to create export directory: {}", e)))?;
+        }
+
+        let file = fs::File::create(&dest)
+            .map_err(|e| Error::Cache(format!("Failed to create export file: {}", e)))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &items)?;
+        writer
+            .flush()
+            .map_err(|e| Error::Cache(format!("Failed to write export file: {}", e)))?;
+
+        Ok(EmptyResponse {})
+    }
+
+    /// Move the cache to a new file, optionally taking the current items along
+    ///
+    /// With `copy_existing` the current file is copied to the new path, otherwise the cache
+    /// continues with whatever the new path already holds. The old file is deleted either way.
+    pub fn relocate(&self, new_path: PathBuf, copy_existing: bool) -> crate::Result<()> {
+        if !self.is_persisted() {
+            return Err(Error::Cache(
+                "The cache isn't persisted, so there's no file to relocate".to_string(),
+            ));
+        }
+
+        let mut cache_file_path = self
+            .file_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if *cache_file_path == new_path {
+            return Ok(());
+        }
+
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::Cache(format!("Failed to create cache directory: {}", e)))?;
+        }
+
+        Self::recover_interrupted_writes(&new_path)
+            .map_err(|e| Error::Cache(format!("Failed to recover cache file: {}", e)))?;
+
+        // Write items held back by write-behind first, so they're copied along
+        Self::flush_memory_store(
+            self.write_behind,
+            self.memory_store.as_ref(),
+            &cache_file_path,
+            self.fsync_on_write,
+            self.storage_format,
+            &self.changes,
+        )
+        .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        // Fold a write-ahead log into the file first, so the file alone holds every item
+        if sibling_path(&cache_file_path, WAL_SUFFIX).exists() {
+            let data = Self::read_from_file(&cache_file_path)
+                .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+            Self::write_to_file(
+                &cache_file_path,
+                &data,
+                self.fsync_on_write,
+                self.storage_format,
+                &self.changes,
+            )
+            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+        }
+
+        if copy_existing {
+            if cache_file_path.exists() {
+                fs::copy(&*cache_file_path, &new_path)
+                    .map_err(|e| Error::Cache(format!("Failed to copy cache file: {}", e)))?;
+
+                // A log left at the new path belongs to the file that was just replaced
+                let new_wal_path = sibling_path(&new_path, WAL_SUFFIX);
+                if new_wal_path.exists() {
+                    fs::remove_file(new_wal_path).map_err(|e| {
+                        Error::Cache(format!("Failed to remove write-ahead log: {}", e))
+                    })?;
+                }
+            }
+        } else {
+            // Values held in memory belong to the old file
+            self.value_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+            self.access_log
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+
+            // With write-behind, so do the items, which now come from the new file
+            if let Some(store) = &self.memory_store {
+                let data = Self::read_from_file(&new_path)
+                    .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+                *store.lock().unwrap_or_else(PoisonError::into_inner) = data;
+            }
+        }
+
+        // The cleanup thread reads the path through the same lock, so it follows along
+        let old_path = std::mem::replace(&mut *cache_file_path, new_path);
+        self.changes.record_write();
+        *self
+            .changes
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+
+        if old_path.exists() {
+            if let Err(e) = fs::remove_file(&old_path) {
+                eprintln!(
+                    "Warning: Failed to remove old cache file {}: {}",
+                    old_path.display(),
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace the clock used to judge expiration, e.g. with a fake clock to test expiration
+    /// without sleeping
+    ///
+    /// Takes effect for every later call and cleanup cycle. Items already stored keep their
+    /// expiration times, which were computed from the previous clock.
+    pub fn set_clock(&self, clock: impl Clock + 'static) {
+        *self.clock.lock().unwrap_or_else(PoisonError::into_inner) = Arc::new(clock);
+    }
+
+    /// Current time in seconds since the Unix epoch, from the configured clock
+    fn now(&self) -> crate::Result<u64> {
+        let clock = self
+            .clock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        clock.now_secs()
+    }
+
+    /// Replace the random source for TTL jitter, e.g. with a fixed sequence to make expiration
+    /// times predictable in tests
+    pub fn set_jitter_rng(&self, rng: impl FnMut() -> u64 + Send + 'static) {
+        *self
+            .jitter_rng
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Box::new(rng);
+    }
+
+    /// Read through to the given store on cache misses, caching loaded values with the given TTL
+    pub fn set_backing_store(&mut self, store: Arc<dyn BackingStore>, ttl: Option<u64>) {
+        self.backing_store = Some(store);
+        self.backing_store_ttl = ttl;
+    }
+
+    /// Configure the cache with compression settings
+    pub fn init_with_config(
+        &mut self,
+        default_compression: bool,
+        compression_level: Option<u32>,
+        threshold: Option<usize>,
+        compression_method: Option<CompressionMethod>,
+    ) {
+        *self.compression.get_mut().unwrap() = CompressionConfig {
+            enabled: default_compression,
+            level: compression_level.unwrap_or(6),
+            threshold: threshold.unwrap_or(COMPRESSION_THRESHOLD),
+            method: compression_method.unwrap_or(CompressionMethod::Zlib),
+        };
+    }
+
+    /// Change the compression settings while the app is running
+    ///
+    /// New writes use the new settings, existing entries keep the compression they were stored with.
+    /// Fails without changing anything if the level is out of range for the method.
+    pub fn reconfigure(
+        &self,
+        default_compression: bool,
+        compression_level: Option<u32>,
+        threshold: Option<usize>,
+        compression_method: Option<CompressionMethod>,
+    ) -> crate::Result<()> {
+        let level = compression_level.unwrap_or(6);
+        let method = compression_method.unwrap_or(CompressionMethod::Zlib);
+
+        // An out-of-range level would make the next compressed write panic in the encoder
+        if let Some(message) = method.level_error(level) {
+            return Err(Error::InvalidConfig(message));
+        }
+
+        let mut compression = self
+            .compression
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *compression = CompressionConfig {
+            enabled: default_compression,
+            level,
+            threshold: threshold.unwrap_or(COMPRESSION_THRESHOLD),
+            method,
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::panic::AssertUnwindSafe;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use serde_json::json;
+    use tauri::Listener;
+
+    use super::{
+        sibling_path, CacheEntry, PayloadHeader, WalRecord, CHECKPOINT_SUFFIX, REWRITE_SUFFIX,
+        WAL_SUFFIX,
+    };
+    use crate::test_support::{test_app, test_app_in, FakeClock};
+    use crate::{
+        CacheConfig, CacheExt, CompressionMethod, DecodeErrorPolicy, Durability, Error,
+        EvictionPolicy, ExportedItem, SetItemOptions, StorageFormat, Ttl, EXPIRED_BATCH_EVENT,
+        STALE_EVENT,
+    };
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn poisoned_locks_are_recovered() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set("before".into(), json!(1), None).unwrap();
+
+        // Panic while holding each lock, as a panicking write would
+        let poisoned = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _file = cache.file_lock.write().unwrap();
+            let _values = cache.value_cache.lock().unwrap();
+            panic!("poison the locks");
+        }));
+        assert!(poisoned.is_err());
+        assert!(cache.file_lock.is_poisoned());
+        assert!(cache.value_cache.is_poisoned());
+
+        cache.set("after".into(), json!(2), None).unwrap();
+        assert_eq!(cache.get("before").unwrap(), Some(json!(1)));
+        assert_eq!(cache.get("after").unwrap(), Some(json!(2)));
+    }
+
+    /// Paths of the backups of a corrupt cache file in `dir`
+    fn corrupt_backups(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("tauri_cache.json.corrupt-")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn corrupt_file_is_backed_up_and_the_next_operation_starts_over() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tauri_cache.json"), b"{\"key\": not json").unwrap();
+        let app = test_app_in(&dir, CacheConfig::default()).unwrap();
+
+        assert!(app.cache().get("key").is_err());
+        let backups = corrupt_backups(dir.path());
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read(&backups[0]).unwrap(), b"{\"key\": not json");
+
+        app.cache().set("key".into(), json!(1), None).unwrap();
+        assert_eq!(app.cache().get("key").unwrap(), Some(json!(1)));
+    }
+
+    #[test]
+    fn corrupt_file_does_not_keep_write_behind_from_starting() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tauri_cache.json"), b"not json").unwrap();
+        let app = test_app_in(
+            &dir,
+            CacheConfig {
+                durability: Some(Durability::WriteBehind),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(corrupt_backups(dir.path()).len(), 1);
+        assert_eq!(app.cache().get("key").unwrap(), None);
+        app.cache().set("key".into(), json!(1), None).unwrap();
+        assert_eq!(app.cache().get("key").unwrap(), Some(json!(1)));
+    }
+
+    #[test]
+    fn reconfigure_rejects_out_of_range_levels() {
+        let (app, _dir) = test_app(CacheConfig::default());
+
+        assert!(app
+            .cache()
+            .reconfigure(true, Some(42), Some(0), Some(CompressionMethod::Lzma2))
+            .is_err());
+
+        // The previous settings still work
+        let value = json!("x".repeat(4096));
+        app.cache().set("key".into(), &value, None).unwrap();
+        assert_eq!(app.cache().get("key").unwrap(), Some(value));
+
+        assert!(app
+            .cache()
+            .reconfigure(true, Some(19), None, Some(CompressionMethod::Zstd))
+            .is_ok());
+    }
+
+    #[test]
+    fn rate_limit_with_huge_window_resets_in_the_future() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        app.cache().set_clock(FakeClock::new(NOW));
+
+        let result = app.cache().rate_limit("calls", 1, u64::MAX).unwrap();
+        assert!(result.allowed);
+        assert_eq!(result.reset_at, u64::MAX);
+        assert!(
+            !app.cache()
+                .rate_limit("calls", 1, u64::MAX)
+                .unwrap()
+                .allowed
+        );
+    }
+
+    #[test]
+    fn rate_limit_rejects_empty_keys() {
+        let (app, _dir) = test_app(CacheConfig::default());
+
+        assert!(app.cache().rate_limit(" ", 1, 60).is_err());
+    }
+
+    #[test]
+    fn huge_ttl_is_reduced_to_max_ttl() {
+        let (app, _dir) = test_app(CacheConfig {
+            max_ttl: Some(3600),
+            ..Default::default()
+        });
+        app.cache().set_clock(FakeClock::new(NOW));
+
+        let options = SetItemOptions {
+            ttl: Some(u64::MAX),
+            ..Default::default()
+        };
+        app.cache()
+            .set("key".into(), json!(1), Some(options))
+            .unwrap();
+
+        assert_eq!(
+            app.cache().ttl("key").unwrap(),
+            Ttl::ExpiresIn { secs: 3600 }
+        );
+    }
+
+    #[test]
+    fn imported_items_are_capped_by_max_ttl() {
+        let (app, dir) = test_app(CacheConfig {
+            max_ttl: Some(3600),
+            ..Default::default()
+        });
+        app.cache().set_clock(FakeClock::new(NOW));
+
+        let items = vec![
+            ExportedItem {
+                key: "forever".into(),
+                value: json!(1),
+                expires_at: None,
+                created_at: None,
+                metadata: None,
+                tags: None,
+            },
+            ExportedItem {
+                key: "soon".into(),
+                value: json!(2),
+                expires_at: Some(NOW + 60),
+                created_at: None,
+                metadata: None,
+                tags: None,
+            },
+        ];
+        let src = dir.path().join("export.json");
+        std::fs::write(&src, serde_json::to_vec(&items).unwrap()).unwrap();
+        assert_eq!(app.cache().import(src, true).unwrap(), 2);
+
+        assert_eq!(
+            app.cache().ttl("forever").unwrap(),
+            Ttl::ExpiresIn { secs: 3600 }
+        );
+        assert_eq!(
+            app.cache().ttl("soon").unwrap(),
+            Ttl::ExpiresIn { secs: 60 }
+        );
+    }
+
+    #[test]
+    fn huge_ttl_without_max_ttl_never_expires() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        app.cache().set_clock(FakeClock::new(NOW));
+
+        let options = SetItemOptions {
+            ttl: Some(u64::MAX),
+            ..Default::default()
+        };
+        app.cache()
+            .set("key".into(), json!(1), Some(options))
+            .unwrap();
+
+        assert!(app.cache().has("key").unwrap().value);
+        assert_eq!(
+            app.cache().ttl("key").unwrap(),
+            Ttl::ExpiresIn {
+                secs: u64::MAX - NOW
+            }
+        );
+    }
+
+    fn with_ttl(ttl: u64) -> Option<SetItemOptions> {
+        Some(SetItemOptions {
+            ttl: Some(ttl),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn default_ttl_applies_to_items_stored_without_one() {
+        let (app, _dir) = test_app(CacheConfig {
+            default_ttl: Some(60),
+            ..Default::default()
+        });
+        app.cache().set_clock(FakeClock::new(NOW));
+
+        app.cache().set("set".into(), json!(1), None).unwrap();
+        app.cache()
+            .set_all(HashMap::from([("all".to_string(), json!(2))]), None)
+            .unwrap();
+        let map = serde_json::Map::from_iter([("loaded".to_string(), json!(3))]);
+        app.cache().load_from_json(map, None, false).unwrap();
+
+        for key in ["set", "all", "loaded"] {
+            assert_eq!(
+                app.cache().ttl(key).unwrap(),
+                Ttl::ExpiresIn { secs: 60 },
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn explicit_ttl_overrides_the_default() {
+        let (app, _dir) = test_app(CacheConfig {
+            default_ttl: Some(60),
+            ..Default::default()
+        });
+        app.cache().set_clock(FakeClock::new(NOW));
+
+        app.cache()
+            .set("short".into(), json!(1), with_ttl(10))
+            .unwrap();
+        app.cache()
+            .set("forever".into(), json!(2), with_ttl(0))
+            .unwrap();
+        app.cache()
+            .set_all(HashMap::from([("all".to_string(), json!(3))]), Some(0))
+            .unwrap();
+
+        assert_eq!(
+            app.cache().ttl("short").unwrap(),
+            Ttl::ExpiresIn { secs: 10 }
+        );
+        assert_eq!(app.cache().ttl("forever").unwrap(), Ttl::NoExpiry);
+        assert_eq!(app.cache().ttl("all").unwrap(), Ttl::NoExpiry);
+    }
+
+    #[test]
+    fn without_default_ttl_items_never_expire() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        app.cache().set_clock(FakeClock::new(NOW));
+
+        app.cache().set("none".into(), json!(1), None).unwrap();
+        app.cache()
+            .set("zero".into(), json!(2), with_ttl(0))
+            .unwrap();
+        app.cache()
+            .set_all(HashMap::from([("all".to_string(), json!(3))]), None)
+            .unwrap();
+
+        for key in ["none", "zero", "all"] {
+            assert_eq!(app.cache().ttl(key).unwrap(), Ttl::NoExpiry, "{}", key);
+        }
+    }
+
+    /// Count the `event`s the app emits from now on
+    fn count_events(app: &tauri::App<tauri::test::MockRuntime>, event: &str) -> Arc<AtomicU64> {
+        let count = Arc::new(AtomicU64::new(0));
+        let counter = count.clone();
+        app.listen_any(event, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        count
+    }
+
+    /// Store an item that has expired and outlived its soft TTL by the time the clock moves on
+    /// to `NOW + 20`, and read and clean it up then
+    fn expire_and_read(app: &tauri::App<tauri::test::MockRuntime>) {
+        app.cache().set_clock(FakeClock::new(NOW));
+        let options = SetItemOptions {
+            ttl: Some(60),
+            soft_ttl: Some(10),
+            ..Default::default()
+        };
+        app.cache()
+            .set("stale".into(), json!(1), Some(options))
+            .unwrap();
+        app.cache()
+            .set("expired".into(), json!(2), with_ttl(10))
+            .unwrap();
+
+        app.cache().set_clock(FakeClock::new(NOW + 20));
+        assert_eq!(app.cache().get("stale").unwrap(), Some(json!(1)));
+        assert_eq!(app.cache().cleanup().unwrap(), 1);
+    }
+
+    #[test]
+    fn expiry_and_stale_events_need_emit_events() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let expired = count_events(&app, EXPIRED_BATCH_EVENT);
+        let stale = count_events(&app, STALE_EVENT);
+
+        expire_and_read(&app);
+
+        assert_eq!(expired.load(Ordering::SeqCst), 0);
+        assert_eq!(stale.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn expiry_and_stale_events_are_emitted_with_emit_events() {
+        let (app, _dir) = test_app(CacheConfig {
+            emit_events: Some(true),
+            ..Default::default()
+        });
+        let expired = count_events(&app, EXPIRED_BATCH_EVENT);
+        let stale = count_events(&app, STALE_EVENT);
+
+        expire_and_read(&app);
+
+        assert_eq!(expired.load(Ordering::SeqCst), 1);
+        assert_eq!(stale.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn memory_store_is_changed_in_place() {
+        let (app, _dir) = test_app(CacheConfig {
+            persist: Some(false),
+            ..Default::default()
+        });
+        let cache = app.cache();
+
+        cache.set("a".into(), json!(1), None).unwrap();
+        cache.set("b".into(), json!(2), None).unwrap();
+        cache.remove("a").unwrap();
+        cache
+            .transform(|_, value| Some(json!(value.as_u64().unwrap() * 10)))
+            .unwrap();
+
+        let store = cache.memory_store.as_ref().unwrap();
+        assert_eq!(store.lock().unwrap().keys().collect::<Vec<_>>(), vec!["b"]);
+        assert_eq!(cache.keys(None).unwrap(), vec!["b"]);
+        assert_eq!(cache.size().unwrap(), 1);
+        assert_eq!(cache.get("b").unwrap(), Some(json!(20)));
+
+        cache.clear().unwrap();
+        assert!(store.lock().unwrap().is_empty());
+        assert_eq!(cache.size().unwrap(), 0);
+    }
+
+    #[test]
+    fn failed_batch_leaves_the_memory_store_untouched() {
+        let (app, _dir) = test_app(CacheConfig {
+            durability: Some(Durability::WriteBehind),
+            default_compression: Some(false),
+            max_bytes: Some(64),
+            ..Default::default()
+        });
+        let cache = app.cache();
+
+        // The small item comes first, so it would be stored before the large one fails
+        let map = serde_json::Map::from_iter([
+            ("a:small".to_string(), json!(1)),
+            ("b:large".to_string(), json!("x".repeat(100))),
+        ]);
+        assert!(cache.load_from_json(map, None, false).is_err());
+
+        assert!(cache
+            .memory_store
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .is_empty());
+        assert_eq!(cache.size().unwrap(), 0);
+    }
+
+    /// A cache held in memory with an item that can't be decoded under each of `keys`
+    fn with_corrupt_item(
+        policy: DecodeErrorPolicy,
+        keys: &[&str],
+    ) -> (tauri::App<tauri::test::MockRuntime>, tempfile::TempDir) {
+        let (app, dir) = test_app(CacheConfig {
+            persist: Some(false),
+            on_decode_error: Some(policy),
+            ..Default::default()
+        });
+        let mut store = app.cache().memory_store.as_ref().unwrap().lock().unwrap();
+        for key in keys {
+            let entry = CacheEntry {
+                value: json!("not base64!"),
+                is_compressed: Some(true),
+                ..Default::default()
+            };
+            store.insert(key.to_string(), entry);
+        }
+        drop(store);
+        (app, dir)
+    }
+
+    fn is_stored(app: &tauri::App<tauri::test::MockRuntime>, key: &str) -> bool {
+        let store = app.cache().memory_store.as_ref().unwrap();
+        store.lock().unwrap().contains_key(key)
+    }
+
+    #[test]
+    fn corrupt_items_are_errors_by_default() {
+        let (app, _dir) = with_corrupt_item(DecodeErrorPolicy::Error, &["key"]);
+
+        assert!(app.cache().has("key").is_err());
+        assert!(app.cache().getset("key".into(), json!(1), None).is_err());
+        assert!(app.cache().push("key", json!(1), None).is_err());
+        assert!(app.cache().value_type("key").is_err());
+        assert!(is_stored(&app, "key"));
+    }
+
+    #[test]
+    fn corrupt_items_can_be_treated_as_missing() {
+        let (app, _dir) = with_corrupt_item(
+            DecodeErrorPolicy::TreatAsMiss,
+            &["has", "getset", "push", "type"],
+        );
+
+        assert!(!app.cache().has("has").unwrap().value);
+        assert!(is_stored(&app, "has"));
+
+        assert_eq!(app.cache().value_type("type").unwrap(), None);
+        assert_eq!(app.cache().get_stale("type").unwrap(), None);
+        assert!(is_stored(&app, "type"));
+
+        assert_eq!(
+            app.cache().getset("getset".into(), json!(1), None).unwrap(),
+            None
+        );
+        assert_eq!(app.cache().get("getset").unwrap(), Some(json!(1)));
+
+        assert_eq!(app.cache().push("push", json!(1), None).unwrap(), 1);
+        assert_eq!(app.cache().get("push").unwrap(), Some(json!([1])));
+    }
+
+    #[test]
+    fn corrupt_items_can_be_evicted() {
+        let (app, _dir) =
+            with_corrupt_item(DecodeErrorPolicy::EvictAndMiss, &["has", "pop", "type"]);
+
+        assert!(!app.cache().has("has").unwrap().value);
+        assert!(!is_stored(&app, "has"));
+
+        assert_eq!(app.cache().value_type("type").unwrap(), None);
+        assert!(!is_stored(&app, "type"));
+
+        assert_eq!(app.cache().pop("pop").unwrap(), None);
+        assert!(!is_stored(&app, "pop"));
+        assert_eq!(app.cache().size().unwrap(), 0);
+    }
+
+    #[test]
+    fn prune_agrees_with_active_size_at_the_expiry_boundary() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("forever".into(), json!(1), None).unwrap();
+        cache
+            .set("boundary".into(), json!(2), with_ttl(10))
+            .unwrap();
+        cache.set("expired".into(), json!(3), with_ttl(5)).unwrap();
+
+        // An item is still active in the second it expires
+        cache.set_clock(FakeClock::new(NOW + 10));
+        let result = cache.prune().unwrap();
+        assert_eq!(result.removed, 1);
+        assert_eq!(result.remaining, 2);
+        assert_eq!(cache.active_size().unwrap(), 2);
+        assert!(cache.has("boundary").unwrap().value);
+    }
+
+    #[test]
+    fn stats_prefix_counts_items_as_active_in_the_second_they_expire() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("user:forever".into(), json!(1), None).unwrap();
+        cache
+            .set("user:boundary".into(), json!(2), with_ttl(10))
+            .unwrap();
+        cache
+            .set("user:expired".into(), json!(3), with_ttl(5))
+            .unwrap();
+        cache.set("other".into(), json!(4), None).unwrap();
+
+        cache.set_clock(FakeClock::new(NOW + 10));
+        let stats = cache.stats_prefix("user:").unwrap();
+        assert_eq!(stats.total_size, 3);
+        assert_eq!(stats.active_size, 2);
+        assert_eq!(cache.stats().unwrap().active_size, 3);
+    }
+
+    #[test]
+    fn get_as_reports_mismatched_types_as_serde_errors() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        app.cache()
+            .set("theme".into(), json!("dark"), None)
+            .unwrap();
+
+        assert_eq!(
+            app.cache().get_as::<String>("theme").unwrap(),
+            Some("dark".to_string())
+        );
+        assert_eq!(app.cache().get_as::<String>("missing").unwrap(), None);
+        match app.cache().get_as::<u32>("theme") {
+            Err(Error::SerdeError(message)) => assert!(message.contains("theme")),
+            other => panic!("expected a SerdeError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn entries_with_the_legacy_marker_still_read() {
+        let value = json!({ "theme": "dark", "items": [1, 2, 3] });
+        let json = serde_json::to_vec(&value).unwrap();
+
+        // Written before the payload header: a compressed flag byte, a method byte, the data
+        let mut encoder =
+            ZlibEncoder::new(vec![1, PayloadHeader::METHOD_ZLIB], Compression::new(6));
+        encoder.write_all(&json).unwrap();
+        let zlib = encoder.finish().unwrap();
+        let plain = [&[0, 0][..], &json].concat();
+
+        let legacy_entry = |payload: &[u8]| CacheEntry {
+            value: json!(STANDARD.encode(payload)),
+            is_compressed: Some(true),
+            ..Default::default()
+        };
+        let data = HashMap::from([
+            ("zlib".to_string(), legacy_entry(&zlib)),
+            ("plain".to_string(), legacy_entry(&plain)),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("tauri_cache.json"),
+            serde_json::to_vec(&data).unwrap(),
+        )
+        .unwrap();
+        let app = test_app_in(&dir, CacheConfig::default()).unwrap();
+
+        assert_eq!(app.cache().get("zlib").unwrap(), Some(value.clone()));
+        assert_eq!(app.cache().get("plain").unwrap(), Some(value));
+    }
+
+    #[test]
+    fn zero_cleanup_interval_starts_no_cleanup_thread() {
+        let (app, _dir) = test_app(CacheConfig {
+            cleanup_interval: Some(0),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("expired".into(), json!(1), with_ttl(1)).unwrap();
+        cache.set_clock(FakeClock::new(NOW + 10));
+        let token = cache.change_token().unwrap();
+
+        assert!(!cache.cleanup_running.load(Ordering::SeqCst));
+
+        // A busy-looping cleanup thread would have removed the item by now
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        assert_eq!(cache.change_token().unwrap(), token);
+        assert_eq!(cache.size().unwrap(), 1);
+
+        assert_eq!(cache.cleanup().unwrap(), 1);
+        assert_eq!(cache.size().unwrap(), 0);
+    }
+
+    /// Store `a`, `b` and `c` a second apart in a cache holding three items, read `reads` in
+    /// the following seconds, then store `d` and return the keys left
+    fn keys_after_eviction(policy: EvictionPolicy, reads: &[&str]) -> Vec<String> {
+        let (app, _dir) = test_app(CacheConfig {
+            max_entries: Some(3),
+            eviction_policy: Some(policy),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        let mut now = NOW;
+        for key in ["a", "b", "c"] {
+            cache.set_clock(FakeClock::new(now));
+            cache.set(key.into(), json!(key), None).unwrap();
+            now += 1;
+        }
+        for key in reads {
+            cache.set_clock(FakeClock::new(now));
+            assert!(cache.get(key).unwrap().is_some());
+            now += 1;
+        }
+        cache.set_clock(FakeClock::new(now));
+        cache.set("d".into(), json!("d"), None).unwrap();
+        cache.keys(None).unwrap()
     }
 
-    /// Compress a JSON value using the default compression configuration
-    #[allow(dead_code)]
-    fn compress_value(&self, value: &serde_json::Value) -> crate::Result<Vec<u8>> {
-        self.compress_value_with_config(value, &self.compression)
+    #[test]
+    fn lru_evicts_the_least_recently_read_item() {
+        assert_eq!(
+            keys_after_eviction(EvictionPolicy::Lru, &["a"]),
+            ["a", "c", "d"]
+        );
+        assert_eq!(
+            keys_after_eviction(EvictionPolicy::Lru, &["b", "a"]),
+            ["a", "b", "d"]
+        );
     }
 
-    /// Decompress a compressed value back to JSON
-    fn decompress_value(&self, data: &[u8]) -> crate::Result<serde_json::Value> {
-        if data.is_empty() {
-            return Err(Error::Cache(
-                "Empty data provided for decompression".to_string(),
-            ));
-        }
+    #[test]
+    fn lfu_evicts_the_least_frequently_read_item() {
+        assert_eq!(
+            keys_after_eviction(EvictionPolicy::Lfu, &["a", "a", "b", "c", "c"]),
+            ["a", "c", "d"]
+        );
+        // Ties go to the item read least recently
+        assert_eq!(
+            keys_after_eviction(EvictionPolicy::Lfu, &["b", "a", "c", "c"]),
+            ["a", "c", "d"]
+        );
+    }
 
-        // First byte indicates if data is compressed
-        let is_compressed = data[0] == 1;
+    #[test]
+    fn fifo_evicts_the_oldest_item_regardless_of_reads() {
+        assert_eq!(
+            keys_after_eviction(EvictionPolicy::Fifo, &["a", "a", "a"]),
+            ["b", "c", "d"]
+        );
+    }
 
-        if !is_compressed {
-            // Data is not compressed - parse the JSON directly
-            let json_data = &data[2..]; // Skip the marker bytes
+    #[test]
+    fn expired_items_are_evicted_first() {
+        let (app, _dir) = test_app(CacheConfig {
+            max_entries: Some(2),
+            eviction_policy: Some(EvictionPolicy::Fifo),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("old".into(), json!(1), None).unwrap();
+        cache.set("expiring".into(), json!(2), with_ttl(1)).unwrap();
 
-            let string_data = std::str::from_utf8(json_data)
-                .map_err(|e| Error::Cache(format!("Failed to decode uncompressed data: {}", e)))?;
+        cache.set_clock(FakeClock::new(NOW + 10));
+        cache.set("new".into(), json!(3), None).unwrap();
+        assert_eq!(cache.keys(None).unwrap(), ["new", "old"]);
+    }
 
-            return serde_json::from_str(string_data)
-                .map_err(|e| Error::Cache(format!("Failed to deserialize value: {}", e)));
-        }
+    #[test]
+    fn size_and_stats_count_write_behind_items_before_a_flush() {
+        let (app, dir) = test_app(CacheConfig {
+            durability: Some(Durability::WriteBehind),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("a".into(), json!(1), None).unwrap();
+        cache.set("b".into(), json!(2), None).unwrap();
+        cache.set("c".into(), json!(3), with_ttl(5)).unwrap();
+        cache.remove("b").unwrap();
+        cache.set("d".into(), json!(4), None).unwrap();
+        cache.set_clock(FakeClock::new(NOW + 10));
 
-        // Second byte indicates compression method
-        let method_marker = data[1];
-        let compressed_data = &data[2..]; // Skip the marker bytes
+        // Only the removal reached the file so far
+        let file = dir.path().join("tauri_cache.json");
+        let on_disk: HashMap<String, CacheEntry> =
+            serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+        assert_eq!(on_disk.len(), 2);
 
-        match method_marker {
-            1 => {
-                // Zlib decompression
-                let mut decoder = ZlibDecoder::new(compressed_data);
-                let mut json_string = String::new();
+        assert_eq!(cache.size().unwrap(), 3);
+        assert_eq!(cache.active_size().unwrap(), 2);
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.total_size, 3);
+        assert_eq!(stats.active_size, 2);
 
-                decoder
-                    .read_to_string(&mut json_string)
-                    .map_err(|e| Error::Cache(format!("Failed to decompress Zlib data: {}", e)))?;
+        cache.flush().unwrap();
+        let on_disk: HashMap<String, CacheEntry> =
+            serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+        assert_eq!(on_disk.len(), 3);
+    }
 
-                serde_json::from_str(&json_string)
-                    .map_err(|e| Error::Cache(format!("Failed to parse decompressed JSON: {}", e)))
-            }
-            2 => {
-                // LZMA2 decompression
-                let mut decoder = XzDecoder::new(compressed_data);
-                let mut json_string = String::new();
+    #[test]
+    fn large_values_round_trip_through_every_compression_method() {
+        let (app, _dir) = test_app(CacheConfig {
+            persist: Some(false),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        let blob = json!((0..2_000)
+            .map(|i| json!({ "id": i, "name": format!("item {}", i), "tags": ["a", "b"] }))
+            .collect::<Vec<_>>());
 
-                decoder
-                    .read_to_string(&mut json_string)
-                    .map_err(|e| Error::Cache(format!("Failed to decompress LZMA2 data: {}", e)))?;
+        // Built without zstd, Zstd falls back to Zlib
+        let zstd_method = if cfg!(feature = "zstd") {
+            PayloadHeader::METHOD_ZSTD
+        } else {
+            PayloadHeader::METHOD_ZLIB
+        };
+        for (method, expected) in [
+            (CompressionMethod::Zlib, PayloadHeader::METHOD_ZLIB),
+            (CompressionMethod::Lzma2, PayloadHeader::METHOD_LZMA2),
+            (CompressionMethod::Gzip, PayloadHeader::METHOD_GZIP),
+            (CompressionMethod::Zstd, zstd_method),
+        ] {
+            let options = SetItemOptions {
+                compress: Some(true),
+                compression_method: Some(method.clone()),
+                ..Default::default()
+            };
+            cache
+                .set("blob".into(), blob.clone(), Some(options))
+                .unwrap();
 
-                serde_json::from_str(&json_string)
-                    .map_err(|e| Error::Cache(format!("Failed to parse decompressed JSON: {}", e)))
-            }
-            _ => Err(Error::Cache(format!(
-                "Unknown compression method marker: {}",
-                method_marker
-            ))),
+            let store = cache.memory_store.as_ref().unwrap().lock().unwrap();
+            let stored = STANDARD
+                .decode(store["blob"].value.as_str().unwrap())
+                .unwrap();
+            drop(store);
+            let (header, payload) = PayloadHeader::parse(&stored).unwrap();
+            assert!(header.is_compressed(), "{:?}", method);
+            assert_eq!(header.method, expected, "{:?}", method);
+            assert!(payload.len() < blob.to_string().len(), "{:?}", method);
+
+            // Read it back from the store rather than the decoded copy held in memory
+            cache.value_cache.lock().unwrap().clear();
+            assert_eq!(
+                cache.get("blob").unwrap(),
+                Some(blob.clone()),
+                "{:?}",
+                method
+            );
         }
     }
 
-    /// Sets a value in the cache with an optional TTL
-    pub fn set<T: Serialize + std::fmt::Debug>(
-        &self,
-        key: String,
-        value: T,
-        options: Option<SetItemOptions>,
-    ) -> crate::Result<EmptyResponse> {
-        // Serialize the value to JSON first (do this outside the lock)
-        let value_json = serde_json::to_value(value)
-            .map_err(|e| Error::Cache(format!("Failed to serialize value: {}", e)))?;
-
-        // Calculate expiration time if TTL is set
-        let expires_at = options.as_ref().and_then(|opt| {
-            opt.ttl.map(|ttl| {
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-                    + ttl
+    /// The JSON of a cache file holding the given plain values
+    fn cache_file(values: &[(&str, serde_json::Value)]) -> Vec<u8> {
+        let data: HashMap<String, CacheEntry> = values
+            .iter()
+            .map(|(key, value)| {
+                let entry = CacheEntry {
+                    value: value.clone(),
+                    is_compressed: Some(false),
+                    ..Default::default()
+                };
+                (key.to_string(), entry)
             })
-        });
-
-        // Update the in-memory cache first
-        {
-            let mut cache = self.value_cache.lock().unwrap();
-            cache.insert(key.clone(), (value_json.clone(), expires_at));
-        }
+            .collect();
+        serde_json::to_vec(&data).unwrap()
+    }
 
-        // Acquire lock for file operations
-        let _guard = self.file_mutex.lock().unwrap();
+    #[test]
+    fn interrupted_rewrite_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tauri_cache.json");
+        std::fs::write(&path, cache_file(&[("key", json!("old"))])).unwrap();
+        // Cut short while replacing the file
+        std::fs::write(sibling_path(&path, REWRITE_SUFFIX), b"{\"key\": {\"val").unwrap();
 
-        // Get current cache data
-        let mut data = Self::read_from_file(&self.cache_file_path)
-            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+        let app = test_app_in(&dir, CacheConfig::default()).unwrap();
+        assert!(!sibling_path(&path, REWRITE_SUFFIX).exists());
+        assert_eq!(app.cache().get("key").unwrap(), Some(json!("old")));
+    }
 
-        // Check if compression is requested
-        let should_compress = options
-            .as_ref()
-            .and_then(|opt| opt.compress)
-            .unwrap_or(self.compression.enabled);
+    #[test]
+    fn completed_checkpoint_replaces_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tauri_cache.json");
+        std::fs::write(&path, cache_file(&[("key", json!("old"))])).unwrap();
+        // The log was removed, but the checkpoint wasn't renamed over the file yet
+        std::fs::write(
+            sibling_path(&path, CHECKPOINT_SUFFIX),
+            cache_file(&[("key", json!("new"))]),
+        )
+        .unwrap();
 
-        // Create a temporary compression config based on options
-        let temp_compression = CompressionConfig {
-            enabled: should_compress,
-            level: self.compression.level,
-            threshold: self.compression.threshold,
-            method: options
-                .as_ref()
-                .and_then(|opt| opt.compression_method.clone())
-                .unwrap_or(self.compression.method.clone()),
-        };
+        let app = test_app_in(&dir, CacheConfig::default()).unwrap();
+        assert!(!sibling_path(&path, CHECKPOINT_SUFFIX).exists());
+        assert_eq!(app.cache().get("key").unwrap(), Some(json!("new")));
+    }
 
-        // Process the value based on compression settings
-        let entry = if should_compress {
-            // Compress the value using the temporary compression config
-            let processed_data = self.compress_value_with_config(&value_json, &temp_compression)?;
-            // Store the processed data as a base64 string
-            let encoded_str = STANDARD.encode(&processed_data);
-            CacheEntry {
-                value: serde_json::Value::String(encoded_str),
-                expires_at,
-                is_compressed: Some(true),
-            }
-        } else {
-            CacheEntry {
-                value: value_json,
-                expires_at,
+    #[test]
+    fn checkpoint_is_discarded_while_the_log_remains() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tauri_cache.json");
+        std::fs::write(&path, cache_file(&[("key", json!("old"))])).unwrap();
+        std::fs::write(sibling_path(&path, CHECKPOINT_SUFFIX), b"{\"key\"").unwrap();
+        let record = WalRecord::Set {
+            key: "key".into(),
+            entry: Box::new(CacheEntry {
+                value: json!("logged"),
                 is_compressed: Some(false),
-            }
+                ..Default::default()
+            }),
         };
+        let mut wal = vec![b'\n'];
+        serde_json::to_writer(&mut wal, &record).unwrap();
+        std::fs::write(sibling_path(&path, WAL_SUFFIX), wal).unwrap();
 
-        // Update the cache
-        data.insert(key, entry);
+        let app = test_app_in(&dir, CacheConfig::default()).unwrap();
+        assert!(!sibling_path(&path, CHECKPOINT_SUFFIX).exists());
+        assert_eq!(app.cache().get("key").unwrap(), Some(json!("logged")));
+    }
 
-        // Save the updated cache to file
-        Self::write_to_file(&self.cache_file_path, &data)
-            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+    #[test]
+    fn whitespace_and_overlong_keys_are_rejected_by_reads_and_writes() {
+        let (app, _dir) = test_app(CacheConfig {
+            max_key_len: Some(8),
+            ..Default::default()
+        });
+        let cache = app.cache();
 
-        Ok(EmptyResponse::default())
+        for key in ["", "   ", "123456789"] {
+            assert!(cache.set(key.into(), json!(1), None).is_err(), "{:?}", key);
+            assert!(cache.get(key).is_err(), "{:?}", key);
+            assert!(cache.has(key).is_err(), "{:?}", key);
+            assert!(cache.remove(key).is_err(), "{:?}", key);
+        }
+        cache.set("12345678".into(), json!(1), None).unwrap();
+        assert_eq!(cache.size().unwrap(), 1);
     }
 
-    /// Gets a value from the cache
-    pub fn get(&self, key: &str) -> crate::Result<Option<serde_json::Value>> {
-        // First check the in-memory cache
-        {
-            let cache = self.value_cache.lock().unwrap();
-            if let Some((value, expires_at)) = cache.get(key) {
-                // Check if expired
-                if let Some(expires) = expires_at {
-                    let now = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-
-                    if *expires < now {
-                        // Item has expired, remove from in-memory cache
-                        drop(cache); // Release the lock before modifying
-                        let mut cache = self.value_cache.lock().unwrap();
-                        cache.remove(key);
-                    } else {
-                        // Not expired, return the cached value
-                        return Ok(Some(value.clone()));
-                    }
-                } else {
-                    // No expiration, return the cached value
-                    return Ok(Some(value.clone()));
-                }
-            }
+    /// A clock that fails like the system clock does when it is set before 1970
+    struct BrokenClock;
+
+    impl crate::Clock for BrokenClock {
+        fn now_secs(&self) -> crate::Result<u64> {
+            Err(Error::Cache(
+                "second time provided was later than self".into(),
+            ))
         }
+    }
 
-        // If not in memory cache, check the file
-        // Acquire lock for file operations
-        let _guard = self.file_mutex.lock().unwrap();
+    #[test]
+    fn a_failing_clock_is_an_error_not_a_panic() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set("kept".into(), json!(1), with_ttl(60)).unwrap();
+        cache.set_clock(BrokenClock);
 
-        // Get current cache data
-        let data = Self::read_from_file(&self.cache_file_path)
-            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+        let calls = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            (
+                cache.set("key".into(), json!(1), with_ttl(60)),
+                cache.get("kept"),
+                cache.has("kept"),
+                cache.cleanup(),
+            )
+        }))
+        .expect("a failing clock must not panic");
+        assert!(matches!(calls.0, Err(Error::Cache(_))));
+        assert!(matches!(calls.1, Err(Error::Cache(_))));
+        assert!(matches!(calls.2, Err(Error::Cache(_))));
+        assert!(matches!(calls.3, Err(Error::Cache(_))));
 
-        if let Some(entry) = data.get(key) {
-            // Check if the item has expired
-            if let Some(expires_at) = entry.expires_at {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
+        // The skipped cycle left the item alone, and it is readable once the clock works again
+        cache.set_clock(FakeClock::new(0));
+        assert_eq!(cache.get("kept").unwrap(), Some(json!(1)));
+    }
 
-                if expires_at < now {
-                    // Item has expired
-                    return Ok(None);
-                }
-            }
+    #[test]
+    fn expire_sets_an_exact_expiration_without_jitter() {
+        let (app, _dir) = test_app(CacheConfig {
+            ttl_jitter_secs: Some(100),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set_jitter_rng(|| 42);
+        cache.set("session".into(), json!(1), with_ttl(60)).unwrap();
+        assert_eq!(cache.ttl("session").unwrap(), Ttl::ExpiresIn { secs: 102 });
 
-            // Check if the value is compressed
-            if entry.is_compressed.unwrap_or(false) {
-                // Value is compressed - need to decompress
-                if let serde_json::Value::String(compressed_str) = &entry.value {
-                    // Decode base64
-                    let compressed_data = STANDARD
-                        .decode(compressed_str)
-                        .map_err(|e| Error::Cache(format!("Failed to decode base64: {}", e)))?;
+        assert!(cache.expire("session", 10).unwrap().value);
+        assert_eq!(cache.ttl("session").unwrap(), Ttl::ExpiresIn { secs: 10 });
+    }
 
-                    // Decompress
-                    let value = self.decompress_value(&compressed_data)?;
+    #[test]
+    fn reads_share_the_file_lock() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        for i in 0..100 {
+            cache.set(format!("key{}", i), json!(i), None).unwrap();
+        }
 
-                    // Cache the decompressed value in memory for future use
-                    {
-                        let mut cache = self.value_cache.lock().unwrap();
-                        cache.insert(key.to_string(), (value.clone(), entry.expires_at));
+        // Hold the file lock for reading while many threads read through the file: if reads took
+        // it exclusively they would block until the timeout
+        let held = cache.file_lock.read().unwrap();
+        let (done, finished) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            for thread in 0..16 {
+                let done = done.clone();
+                scope.spawn(move || {
+                    for round in 0..20 {
+                        let i = (thread * 7 + round) % 100;
+                        cache
+                            .value_cache
+                            .lock()
+                            .unwrap()
+                            .remove(&format!("key{}", i));
+                        assert_eq!(cache.get(&format!("key{}", i)).unwrap(), Some(json!(i)));
+                        assert!(cache.has(&format!("key{}", i)).unwrap().value);
                     }
-
-                    return Ok(Some(value));
-                } else {
-                    return Err(Error::Cache(
-                        "Compressed value is not in expected format".to_string(),
-                    ));
-                }
+                    done.send(()).unwrap();
+                });
+            }
+            for _ in 0..16 {
+                finished
+                    .recv_timeout(std::time::Duration::from_secs(10))
+                    .expect("a reader was blocked by another read");
             }
+            drop(held);
+        });
+    }
 
-            // Store uncompressed value in memory cache for future use
-            {
-                let mut cache = self.value_cache.lock().unwrap();
-                cache.insert(key.to_string(), (entry.value.clone(), entry.expires_at));
+    #[test]
+    fn every_storage_format_round_trips_ten_thousand_items() {
+        type TestCache = super::Cache<tauri::test::MockRuntime>;
+
+        let dir = tempfile::tempdir().unwrap();
+        let data: HashMap<String, CacheEntry> = (0..10_000)
+            .map(|i| {
+                let entry = CacheEntry {
+                    value: json!({ "id": i, "name": format!("user {}", i), "tags": ["a", "b"] }),
+                    expires_at: Some(NOW + i),
+                    is_compressed: Some(false),
+                    ..Default::default()
+                };
+                (format!("user:{}", i), entry)
+            })
+            .collect();
+
+        let mut sizes = Vec::new();
+        for format in [
+            StorageFormat::Json,
+            StorageFormat::MessagePack,
+            StorageFormat::Cbor,
+        ] {
+            let path = dir.path().join(format!("{:?}.cache", format));
+            TestCache::write_contents(&path, &data, false, format).unwrap();
+
+            let read = TestCache::read_from_file(&path).unwrap();
+            sizes.push(std::fs::metadata(&path).unwrap().len());
+
+            assert_eq!(read.len(), data.len());
+            for (key, entry) in &data {
+                assert_eq!(read[key].value, entry.value);
+                assert_eq!(read[key].expires_at, entry.expires_at);
             }
+        }
 
-            // Return the value as is (not compressed)
-            Ok(Some(entry.value.clone()))
-        } else {
-            Ok(None)
+        // Binary formats are smaller than JSON
+        assert!(sizes[1] < sizes[0]);
+        assert!(sizes[2] < sizes[0]);
+    }
+
+    #[test]
+    fn the_same_items_always_write_the_same_bytes() {
+        type TestCache = super::Cache<tauri::test::MockRuntime>;
+
+        let dir = tempfile::tempdir().unwrap();
+        let items = |keys: Vec<u64>| -> HashMap<String, CacheEntry> {
+            keys.into_iter()
+                .map(|i| {
+                    let entry = CacheEntry {
+                        value: json!({ "id": i, "name": format!("item {}", i) }),
+                        is_compressed: Some(false),
+                        ..Default::default()
+                    };
+                    (format!("key{}", i), entry)
+                })
+                .collect()
+        };
+        // Inserted in opposite orders, into maps with different hash seeds
+        let forward = items((0..200).collect());
+        let backward = items((0..200).rev().collect());
+
+        for format in [
+            StorageFormat::Json,
+            StorageFormat::MessagePack,
+            StorageFormat::Cbor,
+        ] {
+            let first = dir.path().join("first");
+            let second = dir.path().join("second");
+            TestCache::write_contents(&first, &forward, false, format).unwrap();
+            TestCache::write_contents(&second, &backward, false, format).unwrap();
+            assert_eq!(
+                std::fs::read(&first).unwrap(),
+                std::fs::read(&second).unwrap(),
+                "{:?}",
+                format
+            );
         }
+
+        // The keys are written in sorted order
+        TestCache::write_contents(
+            &dir.path().join("json"),
+            &forward,
+            false,
+            StorageFormat::Json,
+        )
+        .unwrap();
+        let json = std::fs::read_to_string(dir.path().join("json")).unwrap();
+        assert!(json.find("\"key10\"").unwrap() < json.find("\"key2\"").unwrap());
+        assert!(json.find("\"key0\"").unwrap() < json.find("\"key1\"").unwrap());
     }
 
-    /// Checks if a key exists in the cache and hasn't expired
-    pub fn has(&self, key: &str) -> crate::Result<BooleanResponse> {
-        // First check the in-memory cache
-        {
-            let cache = self.value_cache.lock().unwrap();
-            if let Some((_, expires_at)) = cache.get(key) {
-                // Check if expired
-                if let Some(expires) = expires_at {
-                    let now = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-
-                    if *expires < now {
-                        // Item has expired
-                        drop(cache); // Release the lock before modifying
-                        let mut cache = self.value_cache.lock().unwrap();
-                        cache.remove(key);
-                    } else {
-                        // Not expired
-                        return Ok(BooleanResponse { value: true });
-                    }
-                } else {
-                    // No expiration
-                    return Ok(BooleanResponse { value: true });
-                }
-            }
+    #[test]
+    fn export_clear_import_round_trips_every_item() {
+        let (app, dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache
+            .set("plain".into(), json!({ "a": [1, 2] }), None)
+            .unwrap();
+        let session = SetItemOptions::builder()
+            .ttl(60)
+            .metadata(json!({ "etag": "abc" }))
+            .build();
+        cache
+            .set("session".into(), json!("token"), Some(session))
+            .unwrap();
+        let big = SetItemOptions::builder().compress(true).build();
+        cache
+            .set("big".into(), json!("x".repeat(4096)), Some(big))
+            .unwrap();
+
+        let keys = ["big", "plain", "session"];
+        let before: Vec<_> = keys
+            .iter()
+            .map(|key| cache.get_item(key).unwrap().unwrap())
+            .collect();
+
+        let dest = dir.path().join("export.json");
+        cache.export(dest.clone(), false).unwrap();
+        cache.clear().unwrap();
+        assert!(cache.keys(None).unwrap().is_empty());
+        assert_eq!(cache.import(dest, false).unwrap(), 3);
+
+        assert_eq!(cache.keys(None).unwrap(), keys);
+        for (key, before) in keys.iter().zip(before) {
+            cache.value_cache.lock().unwrap().clear();
+            let after = cache.get_item(key).unwrap().unwrap();
+            assert_eq!(after.value, before.value, "{}", key);
+            assert_eq!(after.expires_at, before.expires_at, "{}", key);
+            assert_eq!(after.is_compressed, before.is_compressed, "{}", key);
+            assert_eq!(after.metadata, before.metadata, "{}", key);
         }
+    }
 
-        // Acquire lock for file operations
-        let _guard = self.file_mutex.lock().unwrap();
+    #[test]
+    fn values_up_to_max_value_bytes_are_stored_and_larger_ones_rejected() {
+        let (app, _dir) = test_app(CacheConfig {
+            max_value_bytes: Some(100),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        // A JSON string is its characters plus two quotes
+        let sized = |len: usize| json!("x".repeat(len - 2));
 
-        // Get current time
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| Error::Cache(e.to_string()))?
-            .as_secs();
+        cache.set("under".into(), sized(99), None).unwrap();
+        cache.set("limit".into(), sized(100), None).unwrap();
+        let over = cache.set("over".into(), sized(101), None);
+        assert!(
+            matches!(&over, Err(Error::Cache(message)) if message.contains("max_value_bytes")),
+            "{:?}",
+            over
+        );
+        assert!(!cache.has("over").unwrap().value);
 
-        // Load data from file
-        let data = Self::read_from_file(&self.cache_file_path)
-            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+        // One oversized item rejects the whole batch
+        let batch = cache.set_many(vec![
+            ("small".to_string(), sized(50), None),
+            ("large".to_string(), sized(101), None),
+        ]);
+        assert!(matches!(batch, Err(Error::Cache(_))));
+        assert_eq!(cache.keys(None).unwrap(), ["limit", "under"]);
+    }
 
-        if let Some(entry) = data.get(key) {
-            // Check if the entry has expired
-            if let Some(expires_at) = entry.expires_at {
-                if expires_at < now {
-                    return Ok(BooleanResponse { value: false });
-                }
-            }
+    #[test]
+    fn rename_of_a_missing_item_returns_false() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set("to".into(), json!(1), None).unwrap();
 
-            // Add to memory cache
-            {
-                let mut cache = self.value_cache.lock().unwrap();
-                cache.insert(key.to_string(), (entry.value.clone(), entry.expires_at));
-            }
+        assert!(!cache.rename("from", "to", true).unwrap().value);
+        assert_eq!(cache.get("to").unwrap(), Some(json!(1)));
+    }
 
-            Ok(BooleanResponse { value: true })
-        } else {
-            Ok(BooleanResponse { value: false })
-        }
+    #[test]
+    fn rename_onto_a_live_item_needs_overwrite() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set("from".into(), json!(1), None).unwrap();
+        cache.set("to".into(), json!(2), None).unwrap();
+
+        assert!(matches!(
+            cache.rename("from", "to", false),
+            Err(Error::Cache(_))
+        ));
+        assert_eq!(cache.get("from").unwrap(), Some(json!(1)));
+        assert_eq!(cache.get("to").unwrap(), Some(json!(2)));
+
+        assert!(cache.rename("from", "to", true).unwrap().value);
+        assert_eq!(cache.get("from").unwrap(), None);
+        assert_eq!(cache.get("to").unwrap(), Some(json!(1)));
     }
 
-    /// Removes a value from the cache
-    pub fn remove(&self, key: &str) -> crate::Result<EmptyResponse> {
-        // Remove from in-memory cache first
-        {
-            let mut cache = self.value_cache.lock().unwrap();
-            cache.remove(key);
-        }
+    #[test]
+    fn rename_to_the_same_key_succeeds_without_overwrite() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set("key".into(), json!(1), None).unwrap();
 
-        // Acquire lock for file operations
-        let _guard = self.file_mutex.lock().unwrap();
+        assert!(cache.rename("key", "key", false).unwrap().value);
+        assert_eq!(cache.get("key").unwrap(), Some(json!(1)));
+    }
 
-        // Load data from file
-        let mut data = Self::read_from_file(&self.cache_file_path)
-            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+    #[test]
+    fn rename_validates_both_keys() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set("key".into(), json!(1), None).unwrap();
 
-        // Remove item if exists
-        if data.remove(key).is_some() {
-            // Save changes to file
-            Self::write_to_file(&self.cache_file_path, &data)
-                .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
-        }
+        assert!(matches!(
+            cache.rename(" ", "key", true),
+            Err(Error::Cache(message)) if message.contains("non-empty")
+        ));
+        assert!(matches!(
+            cache.rename("key", "", true),
+            Err(Error::Cache(message)) if message.contains("non-empty")
+        ));
+    }
 
-        Ok(EmptyResponse {})
+    #[test]
+    fn items_cleared_by_age_are_removed_from_disk_under_write_behind() {
+        let (app, dir) = test_app(CacheConfig {
+            durability: Some(Durability::WriteBehind),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("old".into(), json!(1), None).unwrap();
+        cache.flush().unwrap();
+
+        cache.set_clock(FakeClock::new(NOW + 100));
+        assert_eq!(cache.clear_older_than(50).unwrap(), 1);
+
+        // Without a flush, as after a crash, the file no longer has the item
+        let path = dir.path().join("tauri_cache.json");
+        let on_disk = super::Cache::<tauri::test::MockRuntime>::read_from_file(&path).unwrap();
+        assert!(!on_disk.contains_key("old"));
     }
 
-    /// Clears the entire cache
-    pub fn clear(&self) -> crate::Result<EmptyResponse> {
-        // Clear the in-memory cache
-        {
-            let mut cache = self.value_cache.lock().unwrap();
-            cache.clear();
-        }
+    #[test]
+    fn replace_leaves_a_missing_item_alone_without_writing() {
+        let (app, dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("other".into(), json!(1), None).unwrap();
+        let path = dir.path().join("tauri_cache.json");
+        let file = std::fs::read(&path).unwrap();
+        let token = cache.change_token().unwrap();
 
-        // Acquire lock for file operations
-        let _guard = self.file_mutex.lock().unwrap();
+        assert!(!cache.replace("key".into(), json!(2), None).unwrap().value);
+        assert_eq!(cache.get("key").unwrap(), None);
+        assert_eq!(cache.change_token().unwrap(), token);
+        assert_eq!(std::fs::read(&path).unwrap(), file);
+    }
 
-        // Just write an empty cache
-        Self::write_to_file(&self.cache_file_path, &HashMap::new())
-            .map_err(|e| Error::Cache(format!("Failed to write cache file: {}", e)))?;
+    #[test]
+    fn replace_leaves_an_expired_item_alone() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("key".into(), json!(1), with_ttl(10)).unwrap();
+        cache.set_clock(FakeClock::new(NOW + 20));
+        let token = cache.change_token().unwrap();
 
-        Ok(EmptyResponse {})
+        assert!(!cache.replace("key".into(), json!(2), None).unwrap().value);
+        assert_eq!(cache.get("key").unwrap(), None);
+        assert_eq!(cache.change_token().unwrap(), token);
     }
 
-    /// Get the total number of items in the cache
-    pub fn size(&self) -> crate::Result<usize> {
-        // Acquire lock for file operations
-        let _guard = self.file_mutex.lock().unwrap();
+    #[test]
+    fn replace_stores_over_a_live_item() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let cache = app.cache();
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("key".into(), json!(1), with_ttl(10)).unwrap();
 
-        // Load data from file
-        let data = Self::read_from_file(&self.cache_file_path)
-            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+        assert!(cache.replace("key".into(), json!(2), None).unwrap().value);
+        assert_eq!(cache.get("key").unwrap(), Some(json!(2)));
+        cache.value_cache.lock().unwrap().clear();
+        assert_eq!(cache.get("key").unwrap(), Some(json!(2)));
+    }
+
+    #[test]
+    fn values_below_the_compression_threshold_are_stored_as_plain_json() {
+        let (app, dir) = test_app(CacheConfig {
+            default_compression: Some(true),
+            ..Default::default()
+        });
+        // Ten bytes of JSON, well below the default threshold
+        let value = json!("abcdefgh");
+        let compress = SetItemOptions::builder().compress(true).build();
+        app.cache()
+            .set("small".into(), &value, Some(compress))
+            .unwrap();
 
-        Ok(data.len())
+        let file: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("tauri_cache.json")).unwrap())
+                .unwrap();
+        assert_eq!(file["small"]["value"], value);
+        assert_eq!(file["small"]["is_compressed"], json!(false));
     }
 
-    /// Get the number of non-expired items in the cache
-    pub fn active_size(&self) -> crate::Result<usize> {
-        // Acquire lock for file operations
-        let _guard = self.file_mutex.lock().unwrap();
+    #[test]
+    fn max_bytes_counts_compressed_values_at_their_stored_size() {
+        let (app, dir) = test_app(CacheConfig {
+            default_compression: Some(true),
+            max_bytes: Some(300),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        // Small values stay below the compression threshold and are stored as they are, at 103
+        // bytes each with their key
+        let small = json!("y".repeat(100));
+        cache.set_clock(FakeClock::new(NOW));
+        cache.set("a".into(), &small, None).unwrap();
+        cache.set_clock(FakeClock::new(NOW + 1));
+        cache.set("b".into(), &small, None).unwrap();
 
-        // Load data from file
-        let data = Self::read_from_file(&self.cache_file_path)
-            .map_err(|e| Error::Cache(format!("Failed to read cache file: {}", e)))?;
+        // Twenty kilobytes of JSON that compress to a few dozen bytes fit next to them
+        cache.set_clock(FakeClock::new(NOW + 2));
+        cache
+            .set("big".into(), json!("x".repeat(20_000)), None)
+            .unwrap();
+        assert_eq!(cache.keys(None).unwrap(), ["a", "b", "big"]);
+        let file: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("tauri_cache.json")).unwrap())
+                .unwrap();
+        assert_eq!(file["big"]["is_compressed"], json!(true));
+        assert!(file["big"]["value"].as_str().unwrap().len() < 90);
 
-        // Get current time
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| Error::Cache(e.to_string()))?
-            .as_secs();
+        // One more small value only needs the oldest item gone to fit
+        cache.set_clock(FakeClock::new(NOW + 3));
+        cache.set("c".into(), &small, None).unwrap();
+        assert_eq!(cache.keys(None).unwrap(), ["b", "big", "c"]);
 
-        // Count only non-expired items
-        let active_count = data
-            .iter()
-            .filter(|(_, entry)| {
-                if let Some(expires_at) = entry.expires_at {
-                    expires_at > now
-                } else {
-                    true // Items without expiration are always active
-                }
+        // A value that can't fit even in an empty cache is rejected without evicting anything
+        let plain = SetItemOptions::builder().compress(false).build();
+        let too_big = cache.set("huge".into(), json!("z".repeat(400)), Some(plain));
+        assert!(
+            matches!(&too_big, Err(Error::Cache(message)) if message.contains("max_bytes")),
+            "{:?}",
+            too_big
+        );
+        assert_eq!(cache.keys(None).unwrap(), ["b", "big", "c"]);
+    }
+
+    #[test]
+    fn stats_report_compressed_and_decompressed_bytes() {
+        let (app, dir) = test_app(CacheConfig {
+            default_compression: Some(true),
+            ..Default::default()
+        });
+        let cache = app.cache();
+        let path = dir.path().join("tauri_cache.json");
+
+        cache
+            .set("compressible".into(), json!("ab".repeat(5_000)), None)
+            .unwrap();
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.compressed_count, 1);
+        assert_eq!(stats.decompressed_bytes, 10_002);
+        assert!(stats.decompressed_bytes >= 10 * stats.compressed_bytes);
+        assert_eq!(
+            stats.total_bytes_on_disk,
+            std::fs::metadata(&path).unwrap().len()
+        );
+
+        // Random characters from a 64-letter alphabet deflate to about three quarters, which
+        // base64 takes back to about their original size
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let random: String = (0..10_000)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                alphabet[(seed % 64) as usize] as char
             })
-            .count();
+            .collect();
+        cache.clear().unwrap();
+        cache.set("random".into(), json!(random), None).unwrap();
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.compressed_count, 1);
+        assert_eq!(stats.decompressed_bytes, 10_002);
+        let ratio = stats.compressed_bytes as f64 / stats.decompressed_bytes as f64;
+        assert!((0.9..1.1).contains(&ratio), "{}", ratio);
+        assert_eq!(
+            stats.total_bytes_on_disk,
+            std::fs::metadata(&path).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn payload_headers_round_trip_for_every_method_and_flag() {
+        let methods = [
+            PayloadHeader::METHOD_NONE,
+            PayloadHeader::METHOD_ZLIB,
+            PayloadHeader::METHOD_LZMA2,
+            PayloadHeader::METHOD_GZIP,
+            PayloadHeader::METHOD_ZSTD,
+        ];
+        let flags = [
+            0,
+            PayloadHeader::FLAG_COMPRESSED,
+            PayloadHeader::FLAG_ENCRYPTED,
+            PayloadHeader::FLAG_COMPRESSED | PayloadHeader::FLAG_ENCRYPTED,
+        ];
+        for method in methods {
+            for flags in flags {
+                let header = PayloadHeader {
+                    version: PayloadHeader::VERSION,
+                    method,
+                    flags,
+                };
+                let data = header.wrap(b"payload");
+                assert_eq!(data.len(), PayloadHeader::LEN + 7);
+                assert!(data.starts_with(b"TC"));
 
-        Ok(active_count)
+                let (parsed, payload) = PayloadHeader::parse(&data).unwrap();
+                assert_eq!(parsed, header);
+                assert_eq!(payload, b"payload");
+                assert_eq!(
+                    parsed.is_compressed(),
+                    flags & PayloadHeader::FLAG_COMPRESSED != 0
+                );
+                assert_eq!(
+                    parsed.is_encrypted(),
+                    flags & PayloadHeader::FLAG_ENCRYPTED != 0
+                );
+            }
+        }
     }
 
-    /// Get the path to the cache file
-    pub fn get_cache_file_path(&self) -> PathBuf {
-        self.cache_file_path.clone()
+    #[test]
+    fn legacy_markers_parse_as_version_zero() {
+        for method in [PayloadHeader::METHOD_ZLIB, PayloadHeader::METHOD_LZMA2] {
+            let data = [&[1, method][..], b"payload"].concat();
+            let (header, payload) = PayloadHeader::parse(&data).unwrap();
+            assert_eq!(
+                header,
+                PayloadHeader {
+                    version: 0,
+                    method,
+                    flags: PayloadHeader::FLAG_COMPRESSED,
+                }
+            );
+            assert_eq!(payload, b"payload");
+        }
+
+        // Uncompressed data ignores the method byte
+        let (header, payload) = PayloadHeader::parse(b"\x00\x07{}").unwrap();
+        assert_eq!(
+            header,
+            PayloadHeader {
+                version: 0,
+                method: PayloadHeader::METHOD_NONE,
+                flags: 0,
+            }
+        );
+        assert!(!header.is_compressed());
+        assert_eq!(payload, b"{}");
     }
 
-    /// Configure the cache with compression settings
-    pub fn init_with_config(
-        &mut self,
-        default_compression: bool,
-        compression_level: Option<u32>,
-        threshold: Option<usize>,
-        compression_method: Option<CompressionMethod>,
-    ) {
-        self.compression = CompressionConfig {
-            enabled: default_compression,
-            level: compression_level.unwrap_or(6),
-            threshold: threshold.unwrap_or(COMPRESSION_THRESHOLD),
-            method: compression_method.unwrap_or(CompressionMethod::Zlib),
-        };
+    #[test]
+    fn payload_headers_with_bad_magic_or_unknown_version_are_rejected() {
+        // Neither the magic, the gzip magic nor a legacy marker
+        assert!(PayloadHeader::parse(b"XY\x01\x01\x01payload").is_err());
+        assert!(PayloadHeader::parse(b"").is_err());
+        // A version written by a newer release
+        let newer = [&b"TC"[..], &[PayloadHeader::VERSION + 1, 1, 1], b"payload"].concat();
+        assert!(PayloadHeader::parse(&newer).is_err());
+        // Cut short inside the header
+        assert!(PayloadHeader::parse(b"TC\x01").is_err());
     }
 }
@@ -10,6 +10,18 @@ pub enum Error {
     Json(#[from] serde_json::Error),
     #[error("Cache error: {0}")]
     Cache(String),
+    #[error("Key is read-only: {0}")]
+    ReadOnly(String),
+    #[error("Schema violation: {0}")]
+    SchemaViolation(String),
+    #[error("Decryption failed: {0}")]
+    Decryption(String),
+    #[error("Invalid cache configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Failed to initialize cache: {0}")]
+    InitError(String),
+    #[error("Failed to deserialize cached value: {0}")]
+    SerdeError(String),
     #[cfg(mobile)]
     #[error(transparent)]
     PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
@@ -0,0 +1,83 @@
+/// Whether `key` matches a glob pattern as a whole
+///
+/// `*` matches any run of characters, including none, and `?` matches exactly one character.
+/// Every other character matches only itself.
+pub(crate) fn matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+
+    let (mut p, mut k) = (0, 0);
+    // Where the last `*` was seen and how much of the key it had taken, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, k));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == key[k] => {
+                p += 1;
+                k += 1;
+            }
+            _ => match star {
+                // Let the last `*` take one more character and try again
+                Some((star_p, star_k)) => {
+                    star = Some((star_p, star_k + 1));
+                    p = star_p + 1;
+                    k = star_k + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    // Trailing stars match the empty rest of the key
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+
+    #[test]
+    fn star_as_prefix_suffix_and_middle() {
+        assert!(matches("*:small", "thumb:small"));
+        assert!(!matches("*:small", "thumb:large"));
+        assert!(matches("thumb:*", "thumb:1:small"));
+        assert!(!matches("thumb:*", "photo:1"));
+        assert!(matches("thumb:*:small", "thumb:1:small"));
+        assert!(matches("thumb:*:small", "thumb::small"));
+        assert!(!matches("thumb:*:small", "thumb:1:large"));
+    }
+
+    #[test]
+    fn question_mark_matches_exactly_one_character() {
+        assert!(matches("user:?", "user:1"));
+        assert!(!matches("user:?", "user:"));
+        assert!(!matches("user:?", "user:12"));
+        assert!(matches("?é", "aé"));
+    }
+
+    #[test]
+    fn empty_pattern_only_matches_the_empty_key() {
+        assert!(matches("", ""));
+        assert!(!matches("", "key"));
+        assert!(matches("*", ""));
+    }
+
+    #[test]
+    fn pattern_must_match_the_whole_key() {
+        assert!(matches("key", "key"));
+        assert!(!matches("key", "keys"));
+        assert!(!matches("ey", "key"));
+    }
+
+    #[test]
+    fn star_backtracks_to_later_occurrences() {
+        assert!(matches("a*b*c", "aXbYbc"));
+        assert!(matches("*a*b", "xaybzb"));
+        assert!(!matches("a*b*c", "aXbYbd"));
+        assert!(matches("a**b", "ab"));
+    }
+}
@@ -1,6 +1,10 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{
+    path::BaseDirectory,
     plugin::{Builder, TauriPlugin},
-    Manager, Runtime,
+    AppHandle, Manager, Runtime,
 };
 
 pub use models::*;
@@ -12,9 +16,13 @@ mod mobile;
 
 mod commands;
 mod error;
+mod glob;
 mod models;
+mod namespace;
+mod schema;
 
 pub use error::{Error, Result};
+pub use namespace::NamespacedCache;
 
 #[cfg(desktop)]
 use desktop::Cache;
@@ -32,6 +40,37 @@ impl<R: Runtime, T: Manager<R>> crate::CacheExt<R> for T {
     }
 }
 
+/// A slower source of values the cache falls back to on a miss, e.g. an embedded database.
+///
+/// Values loaded from the store are cached with the configured `backing_store_ttl`.
+/// Only consulted on desktop.
+pub trait BackingStore: Send + Sync {
+    /// Load the value for a key, returning `None` if the store doesn't have it either
+    fn load(&self, key: &str) -> Result<Option<serde_json::Value>>;
+}
+
+/// A source of the current time, used to judge expiration.
+///
+/// Replace the system clock with `app.cache().set_clock(...)`, e.g. to test
+/// expiration with a fake clock instead of sleeping. Only used on desktop.
+pub trait Clock: Send + Sync {
+    /// Current time in seconds since the Unix epoch
+    fn now_secs(&self) -> Result<u64>;
+}
+
+/// The system clock, used unless another clock is set
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_secs(&self) -> Result<u64> {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .map_err(|e| Error::Cache(e.to_string()))
+    }
+}
+
 /// Initializes the plugin.
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     // Default config
@@ -41,6 +80,23 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
 
 /// Initializes the plugin with custom configuration.
 pub fn init_with_config<R: Runtime>(config: CacheConfig) -> TauriPlugin<R> {
+    build_plugin(config, None)
+}
+
+/// Initializes the plugin with custom configuration and a store to read through to on cache misses.
+#[cfg(desktop)]
+pub fn init_with_backing_store<R: Runtime>(
+    config: CacheConfig,
+    store: impl BackingStore + 'static,
+) -> TauriPlugin<R> {
+    build_plugin(config, Some(Arc::new(store)))
+}
+
+#[cfg_attr(mobile, allow(unused_variables))]
+fn build_plugin<R: Runtime>(
+    config: CacheConfig,
+    backing_store: Option<Arc<dyn BackingStore>>,
+) -> TauriPlugin<R> {
     // Clone config for use in the closure
     let config_clone = config.clone();
 
@@ -51,18 +107,63 @@ pub fn init_with_config<R: Runtime>(config: CacheConfig) -> TauriPlugin<R> {
             commands::has,
             commands::remove,
             commands::clear,
-            commands::stats
+            commands::stats,
+            commands::stats_prefix,
+            commands::reconfigure,
+            commands::keys_page,
+            commands::clear_older_than,
+            commands::rename,
+            commands::relocate,
+            commands::rate_limit,
+            commands::get_if_changed,
+            commands::value_type,
+            commands::set_all,
+            commands::get_with_source,
+            commands::load_from_json,
+            commands::reset_stats,
+            commands::change_token,
+            commands::cache_path,
+            commands::get_item,
+            commands::get_many,
+            commands::set_many,
+            commands::keys,
+            commands::clear_prefix,
+            commands::remove_many,
+            commands::ttl,
+            commands::expire,
+            commands::persist,
+            commands::get_or_set,
+            commands::flush,
+            commands::push,
+            commands::pop,
+            commands::export,
+            commands::import,
+            commands::invalidate_tag,
+            commands::get_stale,
+            commands::size,
+            commands::active_size,
+            commands::find_key_by_value,
+            commands::set_cleanup_interval,
+            commands::cleanup,
+            commands::set_if_absent,
+            commands::replace,
+            commands::getset,
+            commands::remove_matching,
+            commands::keys_matching,
+            commands::prune
         ])
         .setup(move |app, api| {
+            // Reject values that can't work before anything touches the disk
+            config_clone.validate()?;
+
             // Provide the config manually to the desktop implementation
             #[cfg(desktop)]
             let cache = {
-                // Always start from app's cache directory
-                let base_cache_dir = app.path().app_cache_dir().map_err(|e| {
-                    crate::Error::Cache(format!("Failed to get app cache directory: {}", e))
-                })?;
+                // Start from the configured base directory, the app's cache directory by default
+                let base_dir = config_clone.base_dir.unwrap_or(BaseDirectory::AppCache);
+                let base_cache_dir = resolve_base_dir(app, base_dir);
 
-                // If custom subdirectory is specified, append it to the app cache directory path
+                // If custom subdirectory is specified, append it to the base directory path
                 let cache_dir = if let Some(custom_dir) = config_clone.cache_dir.as_deref() {
                     let custom_path = std::path::PathBuf::from(custom_dir);
                     if custom_path.is_absolute() {
@@ -85,31 +186,32 @@ pub fn init_with_config<R: Runtime>(config: CacheConfig) -> TauriPlugin<R> {
                     base_cache_dir
                 };
 
-                // Create the cache directory if it doesn't exist
-                std::fs::create_dir_all(&cache_dir).map_err(|e| {
-                    crate::Error::Cache(format!("Failed to create cache directory: {}", e))
-                })?;
+                // Create the cache directory if it doesn't exist and will be written to
+                if config_clone.persist.unwrap_or(true) {
+                    std::fs::create_dir_all(&cache_dir).map_err(|e| {
+                        crate::Error::Cache(format!("Failed to create cache directory: {}", e))
+                    })?;
+                }
 
-                // Determine the cache file name
-                let cache_file_name = config_clone
-                    .cache_file_name
-                    .as_deref()
-                    .unwrap_or("tauri_cache.json");
+                // Determine the cache file name, expanding any tokens in it
+                let cache_file_name = expand_file_name(
+                    app,
+                    config_clone
+                        .cache_file_name
+                        .as_deref()
+                        .unwrap_or("tauri_cache.json"),
+                )?;
                 let cache_file_path = cache_dir.join(cache_file_name);
 
                 // Get the default compression settings
                 let default_compression = config_clone.default_compression.unwrap_or(true);
                 let compression_level = config_clone.compression_level;
                 let compression_threshold = config_clone.compression_threshold;
-                let compression_method = config_clone.compression_method;
+                let compression_method = config_clone.compression_method.clone();
 
-                // Initialize the cache with cleanup interval
-                let mut cache = desktop::init_with_config(
-                    app,
-                    api,
-                    cache_file_path,
-                    config_clone.cleanup_interval.unwrap_or(60),
-                )?;
+                // Initialize the cache with cleanup and durability settings
+                let mut cache =
+                    desktop::init_with_config(app, api, cache_file_path, &config_clone)?;
 
                 // Initialize with compression settings
                 cache.init_with_config(
@@ -118,17 +220,21 @@ pub fn init_with_config<R: Runtime>(config: CacheConfig) -> TauriPlugin<R> {
                     compression_threshold,
                     compression_method,
                 );
+
+                // Read through to the backing store on misses, if one was provided
+                if let Some(store) = backing_store.clone() {
+                    cache.set_backing_store(store, config_clone.backing_store_ttl);
+                }
                 cache
             };
 
             #[cfg(mobile)]
             let cache = {
-                // Always start from app's cache directory
-                let base_cache_dir = app.path().app_cache_dir().map_err(|e| {
-                    crate::Error::Cache(format!("Failed to get app cache directory: {}", e))
-                })?;
+                // Start from the configured base directory, the app's cache directory by default
+                let base_dir = config_clone.base_dir.unwrap_or(BaseDirectory::AppCache);
+                let base_cache_dir = resolve_base_dir(app, base_dir);
 
-                // If custom subdirectory is specified, append it to the app cache directory path
+                // If custom subdirectory is specified, append it to the base directory path
                 let cache_dir = if let Some(custom_dir) = config_clone.cache_dir.as_deref() {
                     let custom_path = std::path::PathBuf::from(custom_dir);
                     if custom_path.is_absolute() {
@@ -156,26 +262,24 @@ pub fn init_with_config<R: Runtime>(config: CacheConfig) -> TauriPlugin<R> {
                     crate::Error::Cache(format!("Failed to create cache directory: {}", e))
                 })?;
 
-                // Determine the cache file name
-                let cache_file_name = config_clone
-                    .cache_file_name
-                    .as_deref()
-                    .unwrap_or("tauri_cache.json");
+                // Determine the cache file name, expanding any tokens in it
+                let cache_file_name = expand_file_name(
+                    app,
+                    config_clone
+                        .cache_file_name
+                        .as_deref()
+                        .unwrap_or("tauri_cache.json"),
+                )?;
                 let cache_file_path = cache_dir.join(cache_file_name);
 
                 // Get the default compression settings
                 let default_compression = config_clone.default_compression.unwrap_or(true);
                 let compression_level = config_clone.compression_level;
                 let compression_threshold = config_clone.compression_threshold;
-                let compression_method = config_clone.compression_method;
+                let compression_method = config_clone.compression_method.clone();
 
-                // Initialize the cache with cleanup interval
-                let mut cache = mobile::init_with_config(
-                    app,
-                    api,
-                    cache_file_path,
-                    config_clone.cleanup_interval.unwrap_or(60),
-                )?;
+                // Initialize the cache with the plugin configuration
+                let mut cache = mobile::init_with_config(app, api, cache_file_path, &config_clone)?;
 
                 // Initialize with compression settings
                 cache.init_with_config(
@@ -188,7 +292,169 @@ pub fn init_with_config<R: Runtime>(config: CacheConfig) -> TauriPlugin<R> {
             };
 
             app.manage(cache);
+            app.manage(commands::ReadOnlyPrefixes(
+                config_clone.readonly_prefixes.clone().unwrap_or_default(),
+            ));
             Ok(())
         })
         .build()
 }
+
+/// Resolve the base directory of the cache, falling back to the temp directory if it's unavailable
+///
+/// Minimal environments (e.g. containers without `XDG_CACHE_HOME` or `HOME`) can't resolve the
+/// standard directories, which would otherwise keep the plugin and the app from starting.
+fn resolve_base_dir<R: Runtime>(app: &AppHandle<R>, base_dir: BaseDirectory) -> PathBuf {
+    app.path().resolve("", base_dir).unwrap_or_else(|e| {
+        let fallback = std::env::temp_dir().join(&app.config().identifier);
+        eprintln!(
+            "Warning: Failed to get base directory ({}), storing the cache in {}",
+            e,
+            fallback.display()
+        );
+        fallback
+    })
+}
+
+/// Name of the JSON type of a value, as reported by `value_type`
+pub(crate) fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Fail if a key is empty, only whitespace or longer than `max_len` bytes
+///
+/// Such keys would be stored but practically unreachable, so they're rejected up front.
+pub(crate) fn check_key(key: &str, max_len: Option<usize>) -> Result<()> {
+    if key.trim().is_empty() {
+        return Err(Error::Cache("cache key must be non-empty".to_string()));
+    }
+    if let Some(max_len) = max_len.filter(|&max_len| key.len() > max_len) {
+        return Err(Error::Cache(format!(
+            "cache key is {} bytes long, more than max_key_len ({})",
+            key.len(),
+            max_len
+        )));
+    }
+    Ok(())
+}
+
+/// Expand the `{version}`, `{os}` and `{user}` tokens in a cache file name
+fn expand_file_name<R: Runtime>(app: &AppHandle<R>, name: &str) -> Result<String> {
+    let mut expanded = String::with_capacity(name.len());
+    let mut rest = name;
+
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| Error::Cache(format!("Unclosed token in cache file name: {}", name)))?
+            + start;
+
+        match &rest[start + 1..end] {
+            "version" => expanded.push_str(&app.package_info().version.to_string()),
+            "os" => expanded.push_str(std::env::consts::OS),
+            "user" => expanded.push_str(
+                &std::env::var("USER")
+                    .or_else(|_| std::env::var("USERNAME"))
+                    .unwrap_or_else(|_| "default".to_string()),
+            ),
+            token => {
+                return Err(Error::Cache(format!(
+                    "Unknown token {{{}}} in cache file name: {}",
+                    token, name
+                )))
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+/// Helpers for tests that need a running cache
+#[cfg(all(test, desktop))]
+pub(crate) mod test_support {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+    use tauri::App;
+
+    use crate::{CacheConfig, Clock, Result};
+
+    /// A mock app with the plugin set up from `config`, keeping its cache file in a fresh
+    /// temporary directory and running no background cleanup
+    pub(crate) fn test_app(config: CacheConfig) -> (App<MockRuntime>, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let app = test_app_in(&dir, config).unwrap();
+        (app, dir)
+    }
+
+    /// A mock app with the plugin set up from `config`, keeping its cache file in `dir`, or the
+    /// error that kept it from starting
+    pub(crate) fn test_app_in(
+        dir: &tempfile::TempDir,
+        config: CacheConfig,
+    ) -> std::result::Result<App<MockRuntime>, tauri::Error> {
+        let config = CacheConfig {
+            base_dir: Some(tauri::path::BaseDirectory::Temp),
+            cache_dir: Some(dir.path().to_string_lossy().into_owned()),
+            cleanup_interval: Some(0),
+            ..config
+        };
+        mock_builder()
+            .plugin(crate::init_with_config(config))
+            .build(mock_context(noop_assets()))
+    }
+
+    /// A clock that only moves when told to
+    #[derive(Clone)]
+    pub(crate) struct FakeClock(Arc<AtomicU64>);
+
+    impl FakeClock {
+        pub(crate) fn new(now: u64) -> Self {
+            Self(Arc::new(AtomicU64::new(now)))
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now_secs(&self) -> Result<u64> {
+            Ok(self.0.load(Ordering::SeqCst))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_key;
+
+    #[test]
+    fn empty_and_whitespace_keys_are_rejected() {
+        assert!(check_key("", None).is_err());
+        assert!(check_key(" ", None).is_err());
+        assert!(check_key("\t\n ", None).is_err());
+        assert!(check_key(" key ", None).is_ok());
+    }
+
+    #[test]
+    fn keys_may_be_exactly_max_key_len_bytes_long() {
+        assert!(check_key("abcd", Some(4)).is_ok());
+        assert!(check_key("abcde", Some(4)).is_err());
+        assert!(check_key(&"k".repeat(10_000), None).is_ok());
+    }
+
+    #[test]
+    fn key_length_is_counted_in_bytes() {
+        // Two characters, four bytes
+        assert!(check_key("éé", Some(4)).is_ok());
+        assert!(check_key("éé", Some(3)).is_err());
+    }
+}
@@ -1,12 +1,15 @@
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tauri::{
     plugin::{PluginApi, PluginHandle},
     AppHandle, Runtime,
 };
 
+use crate::glob;
 use crate::models::*;
-use crate::Error;
+use crate::schema::SchemaRegistry;
+use crate::{check_key, Error};
 
 #[cfg(target_os = "ios")]
 tauri::ios_plugin_binding!(init_plugin_cache);
@@ -20,7 +23,7 @@ pub fn init<R: Runtime, C: DeserializeOwned>(
     let handle = api.register_android_plugin("app.tauri.plugin.cache", "CachePlugin")?;
     #[cfg(target_os = "ios")]
     let handle = api.register_ios_plugin(init_plugin_cache)?;
-    Ok(Cache(handle))
+    Ok(Cache(handle, SchemaRegistry::default(), None))
 }
 
 // Initialize the plugin with a custom cache file path
@@ -28,8 +31,11 @@ pub fn init_with_config<R: Runtime, C: DeserializeOwned>(
     _app: &AppHandle<R>,
     api: PluginApi<R, C>,
     cache_file_path: PathBuf,
-    cleanup_interval: u64,
+    config: &CacheConfig,
 ) -> crate::Result<Cache<R>> {
+    let schemas = SchemaRegistry::new(config.schemas.as_ref())?;
+    let max_key_len = config.max_key_len;
+
     // Create config for mobile platforms
     let config = CacheConfig {
         cache_dir: cache_file_path
@@ -38,11 +44,12 @@ pub fn init_with_config<R: Runtime, C: DeserializeOwned>(
         cache_file_name: cache_file_path
             .file_name()
             .map(|f| f.to_string_lossy().to_string()),
-        cleanup_interval: Some(cleanup_interval),
+        cleanup_interval: config.cleanup_interval,
         default_compression: Some(true),
         compression_level: Some(6),
         compression_threshold: Some(crate::models::COMPRESSION_THRESHOLD),
         compression_method: Some(CompressionMethod::Zlib),
+        ..config.clone()
     };
 
     // Register the plugin with API
@@ -73,11 +80,11 @@ pub fn init_with_config<R: Runtime, C: DeserializeOwned>(
         api.register_ios_plugin_with_config(init_plugin_cache, config_json)?
     };
 
-    Ok(Cache(handle))
+    Ok(Cache(handle, schemas, max_key_len))
 }
 
 /// Access to the cache APIs.
-pub struct Cache<R: Runtime>(PluginHandle<R>);
+pub struct Cache<R: Runtime>(PluginHandle<R>, SchemaRegistry, Option<usize>);
 
 impl<R: Runtime> Cache<R> {
     /// Configure the cache with compression settings
@@ -105,6 +112,30 @@ impl<R: Runtime> Cache<R> {
         // Error handling should be added in a real application
     }
 
+    /// Change the compression settings while the app is running
+    pub fn reconfigure(
+        &self,
+        default_compression: bool,
+        compression_level: Option<u32>,
+        compression_threshold: Option<usize>,
+        compression_method: Option<CompressionMethod>,
+    ) -> crate::Result<()> {
+        let config = CompressionConfig {
+            enabled: default_compression,
+            level: compression_level.unwrap_or(6),
+            threshold: compression_threshold.unwrap_or(crate::models::COMPRESSION_THRESHOLD),
+            method: compression_method.unwrap_or(CompressionMethod::Zlib),
+        };
+        if let Some(message) = config.method.level_error(config.level) {
+            return Err(Error::InvalidConfig(message));
+        }
+
+        self.0
+            .run_mobile_plugin::<EmptyResponse>("updateCompressionConfig", config)
+            .map(|_| ())
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
     /// Sets a value in the cache with optional TTL
     pub fn set<T: Serialize + DeserializeOwned + std::fmt::Debug>(
         &self,
@@ -112,6 +143,13 @@ impl<R: Runtime> Cache<R> {
         value: T,
         options: Option<SetItemOptions>,
     ) -> crate::Result<EmptyResponse> {
+        check_key(&key, self.2)?;
+
+        // Reject values that don't match the schema registered for their key
+        if self.1.applies_to(&key) {
+            self.1.validate(&key, &serde_json::to_value(&value)?)?;
+        }
+
         let request = SetRequest::<T> {
             key,
             value,
@@ -122,8 +160,182 @@ impl<R: Runtime> Cache<R> {
             .map_err(|e| crate::Error::PluginInvoke(e))
     }
 
+    /// Gets a value, first storing `value` if the key is missing or expired, and returns the
+    /// value now held by the cache
+    ///
+    /// The native side has no compare-and-set, so unlike on desktop two concurrent callers can
+    /// both store their value.
+    pub fn get_or_set<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<serde_json::Value> {
+        if let Some(existing) = self.get(&key)? {
+            return Ok(existing);
+        }
+        let value = serde_json::to_value(value)?;
+        self.set(key, value.clone(), options)?;
+        Ok(value)
+    }
+
+    /// Stores `value` only if the key is missing or expired, returning whether it was stored
+    ///
+    /// The native side has no compare-and-set, so unlike on desktop two concurrent callers can
+    /// both get `true`.
+    pub fn set_if_absent<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<BooleanResponse> {
+        if self.has(&key)?.value {
+            return Ok(BooleanResponse { value: false });
+        }
+        self.set(key, serde_json::to_value(value)?, options)?;
+        Ok(BooleanResponse { value: true })
+    }
+
+    /// Stores `value` and returns the value it replaced, or `None` if the key was missing or
+    /// expired
+    ///
+    /// Composed of a get and a set, so unlike on desktop another write can slip in between.
+    pub fn getset<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<Option<serde_json::Value>> {
+        let previous = self.get(&key)?;
+        self.set(key, serde_json::to_value(value)?, options)?;
+        Ok(previous)
+    }
+
+    /// Stores `value` only if the key holds a live item, returning whether it was stored
+    ///
+    /// The native side has no compare-and-set, so unlike on desktop an item removed between the
+    /// check and the write is stored again.
+    pub fn replace<T: Serialize>(
+        &self,
+        key: String,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<BooleanResponse> {
+        if !self.has(&key)?.value {
+            return Ok(BooleanResponse { value: false });
+        }
+        self.set(key, serde_json::to_value(value)?, options)?;
+        Ok(BooleanResponse { value: true })
+    }
+
+    /// Appends a value to the array stored under `key` and returns the array's new length
+    ///
+    /// Composed of a get and a set, so unlike on desktop concurrent pushes can lose elements, and
+    /// the array is stored again with `options`, replacing its expiration time.
+    pub fn push(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<usize> {
+        let mut items = match self.get(key)? {
+            Some(serde_json::Value::Array(items)) => items,
+            Some(_) => {
+                return Err(Error::Cache(format!(
+                    "Item \"{}\" doesn't hold an array",
+                    key
+                )))
+            }
+            None => Vec::new(),
+        };
+        items.push(value);
+        let len = items.len();
+        self.set(key.to_string(), serde_json::Value::Array(items), options)?;
+        Ok(len)
+    }
+
+    /// Removes and returns the last element of the array stored under `key`
+    ///
+    /// Composed of a get and a set like [`Cache::push`], so the shortened array loses its
+    /// expiration time.
+    pub fn pop(&self, key: &str) -> crate::Result<Option<serde_json::Value>> {
+        let mut items = match self.get(key)? {
+            Some(serde_json::Value::Array(items)) => items,
+            Some(_) => {
+                return Err(Error::Cache(format!(
+                    "Item \"{}\" doesn't hold an array",
+                    key
+                )))
+            }
+            None => return Ok(None),
+        };
+        let last = items.pop();
+        if last.is_some() {
+            self.set(key.to_string(), serde_json::Value::Array(items), None)?;
+        }
+        Ok(last)
+    }
+
+    /// Inserts every key/value pair of a JSON object, returning how many were stored
+    pub fn load_from_json(
+        &self,
+        map: serde_json::Map<String, serde_json::Value>,
+        default_ttl: Option<u64>,
+        skip_existing: bool,
+    ) -> crate::Result<usize> {
+        // Reject the whole batch if any value doesn't match its schema
+        for (key, value) in &map {
+            check_key(key, self.2)?;
+            self.1.validate(key, value)?;
+        }
+
+        let request = LoadFromJsonRequest {
+            map,
+            default_ttl,
+            skip_existing,
+        };
+        self.0
+            .run_mobile_plugin::<usize>("loadFromJson", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Sets several values, each with its own options
+    ///
+    /// Every value is serialized and validated before anything is stored, but the native side
+    /// stores them one at a time, so a failing write can leave the earlier ones in place.
+    pub fn set_many<T: Serialize>(
+        &self,
+        items: Vec<(String, T, Option<SetItemOptions>)>,
+    ) -> crate::Result<EmptyResponse> {
+        let mut prepared = Vec::with_capacity(items.len());
+        for (key, value, options) in items {
+            check_key(&key, self.2)?;
+            let value = serde_json::to_value(value)?;
+            if self.1.applies_to(&key) {
+                self.1.validate(&key, &value)?;
+            }
+            prepared.push((key, value, options));
+        }
+
+        for (key, value, options) in prepared {
+            self.set(key, value, options)?;
+        }
+        Ok(EmptyResponse::default())
+    }
+
+    /// Stores every value with the same TTL
+    pub fn set_all(
+        &self,
+        values: HashMap<String, serde_json::Value>,
+        ttl: Option<u64>,
+    ) -> crate::Result<()> {
+        self.load_from_json(values.into_iter().collect(), ttl, false)
+            .map(|_| ())
+    }
+
     /// Gets a value from the cache
     pub fn get(&self, key: &str) -> crate::Result<Option<serde_json::Value>> {
+        check_key(key, self.2)?;
         let request = GetRequest {
             key: key.to_string(),
         };
@@ -132,8 +344,148 @@ impl<R: Runtime> Cache<R> {
             .map_err(|e| crate::Error::PluginInvoke(e))
     }
 
+    /// Gets several values at once, with `None` for items that are missing or expired
+    pub fn get_many(
+        &self,
+        keys: &[String],
+    ) -> crate::Result<HashMap<String, Option<serde_json::Value>>> {
+        let request = GetManyRequest {
+            keys: keys.to_vec(),
+        };
+        self.0
+            .run_mobile_plugin::<HashMap<String, Option<serde_json::Value>>>("getMany", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Gets a value, or an expired one still within its stale window, flagged as stale
+    ///
+    /// The native side drops expired values, so a value is never reported as stale on mobile.
+    pub fn get_stale(&self, key: &str) -> crate::Result<Option<(serde_json::Value, bool)>> {
+        Ok(self.get(key)?.map(|value| (value, false)))
+    }
+
+    /// Gets a value from the cache along with where it was found
+    ///
+    /// Mobile platforms don't keep values in memory, so every hit is read from disk.
+    pub fn get_with_source(
+        &self,
+        key: &str,
+    ) -> crate::Result<Option<(serde_json::Value, CacheSource)>> {
+        Ok(self.get(key)?.map(|value| (value, CacheSource::Disk)))
+    }
+
+    /// Gets a value along with its expiration time, compression and metadata
+    ///
+    /// The native side only returns values, so the other details are always empty.
+    pub fn get_item(&self, key: &str) -> crate::Result<Option<CacheItem<serde_json::Value>>> {
+        Ok(self.get(key)?.map(|value| CacheItem {
+            value,
+            expires_at: None,
+            is_compressed: None,
+            metadata: None,
+        }))
+    }
+
+    /// Gets a value unless the caller already holds its current version
+    ///
+    /// Mobile platforms don't track versions, so the value is always returned.
+    pub fn get_if_changed(
+        &self,
+        key: &str,
+        _since: Option<u64>,
+    ) -> crate::Result<Option<GetConditional>> {
+        Ok(self.get(key)?.map(|value| GetConditional::Modified {
+            value,
+            version: None,
+        }))
+    }
+
+    /// Gets the JSON type of a stored value, e.g. `"object"` or `"string"`, without returning it
+    ///
+    /// The native side has no introspection, so the value is read in full and only its type kept.
+    pub fn value_type(&self, key: &str) -> crate::Result<Option<String>> {
+        Ok(self
+            .get(key)?
+            .map(|value| crate::json_type_name(&value).to_string()))
+    }
+
+    /// Gets a value deserialized into `T`, like [`Cache::get`] followed by `serde_json::from_value`
+    ///
+    /// Fails with [`Error::SerdeError`] if the stored value doesn't deserialize into `T`.
+    pub fn get_as<T: DeserializeOwned>(&self, key: &str) -> crate::Result<Option<T>> {
+        match self.get(key)? {
+            Some(value) => serde_json::from_value(value)
+                .map(Some)
+                .map_err(|e| Error::SerdeError(format!("item \"{}\": {}", key, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the outcome of a fallible operation, so known failures aren't retried until the TTL
+    ///
+    /// Read it back with [`Cache::get_result`], which keeps success and failure apart.
+    pub fn set_result<T: Serialize>(
+        &self,
+        key: String,
+        result: std::result::Result<T, String>,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<EmptyResponse> {
+        let value = serde_json::to_value(CachedResult::from(result))?;
+        self.set(key, value, options)
+    }
+
+    /// Gets an outcome stored by [`Cache::set_result`]
+    ///
+    /// Returns `Ok(Some(Err(message)))` for a cached failure. Fails if the stored value isn't an
+    /// outcome or doesn't deserialize into `T`.
+    pub fn get_result<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> crate::Result<Option<std::result::Result<T, String>>> {
+        match self.get(key)? {
+            Some(value) => Ok(Some(
+                serde_json::from_value::<CachedResult<T>>(value)?.into(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets every live value whose key starts with the prefix, deserialized into `T`
+    ///
+    /// Values that don't deserialize into `T` are skipped, and how many were skipped is logged.
+    pub fn get_all_as<T: DeserializeOwned>(
+        &self,
+        prefix: &str,
+    ) -> crate::Result<HashMap<String, T>> {
+        let mut values = HashMap::new();
+        let mut skipped = 0;
+        for key in self.keys(Some(prefix))? {
+            // Items can expire between listing and reading them
+            let Some(value) = self.get(&key)? else {
+                continue;
+            };
+
+            match serde_json::from_value(value) {
+                Ok(value) => {
+                    values.insert(key, value);
+                }
+                Err(_) => skipped += 1,
+            }
+        }
+
+        if skipped > 0 {
+            eprintln!(
+                "Warning: Skipped {} cache items under \"{}\" that don't match the requested type",
+                skipped, prefix
+            );
+        }
+
+        Ok(values)
+    }
+
     /// Checks if a key exists in the cache
     pub fn has(&self, key: &str) -> crate::Result<BooleanResponse> {
+        check_key(key, self.2)?;
         let request = HasRequest {
             key: key.to_string(),
         };
@@ -142,8 +494,19 @@ impl<R: Runtime> Cache<R> {
             .map_err(|e| crate::Error::PluginInvoke(e))
     }
 
+    /// Gets the time left until an item expires
+    pub fn ttl(&self, key: &str) -> crate::Result<Ttl> {
+        let request = TtlRequest {
+            key: key.to_string(),
+        };
+        self.0
+            .run_mobile_plugin::<Ttl>("ttl", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
     /// Removes a value from the cache
     pub fn remove(&self, key: &str) -> crate::Result<EmptyResponse> {
+        check_key(key, self.2)?;
         let request = RemoveRequest {
             key: key.to_string(),
         };
@@ -152,6 +515,114 @@ impl<R: Runtime> Cache<R> {
             .map_err(|e| crate::Error::PluginInvoke(e))
     }
 
+    /// Removes several values from the cache
+    pub fn remove_many(&self, keys: &[String]) -> crate::Result<EmptyResponse> {
+        for key in keys {
+            self.remove(key)?;
+        }
+        Ok(EmptyResponse {})
+    }
+
+    /// Remove every item whose key matches a glob pattern, returning how many were removed
+    ///
+    /// Composed of a key listing and a removal per match, so it's not atomic on mobile.
+    pub fn remove_matching(&self, pattern: &str) -> crate::Result<usize> {
+        let matching_keys = self.keys_matching(pattern)?;
+        self.remove_many(&matching_keys)?;
+        Ok(matching_keys.len())
+    }
+
+    /// Sets an item to expire `ttl` seconds from now, without rewriting its value
+    pub fn expire(&self, key: &str, ttl: u64) -> crate::Result<BooleanResponse> {
+        self.set_expiry(key, Some(ttl))
+    }
+
+    /// Keeps an item until it's removed, dropping its expiration time
+    pub fn persist(&self, key: &str) -> crate::Result<BooleanResponse> {
+        self.set_expiry(key, None)
+    }
+
+    fn set_expiry(&self, key: &str, ttl: Option<u64>) -> crate::Result<BooleanResponse> {
+        let request = SetExpiryRequest {
+            key: key.to_string(),
+            ttl,
+        };
+        self.0
+            .run_mobile_plugin::<BooleanResponse>("setExpiry", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Moves an item to another key, optionally replacing an existing item
+    ///
+    /// Fails if `to` holds a live item and `overwrite` is not set.
+    pub fn rename(&self, from: &str, to: &str, overwrite: bool) -> crate::Result<BooleanResponse> {
+        check_key(to, self.2)?;
+
+        let request = RenameRequest {
+            from: from.to_string(),
+            to: to.to_string(),
+            overwrite,
+        };
+        self.0
+            .run_mobile_plugin::<BooleanResponse>("rename", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Moves the cache to a new location
+    ///
+    /// Mobile platforms keep the cache in the app's own storage, so it can't be relocated.
+    pub fn relocate(&self, _new_path: PathBuf, _copy_existing: bool) -> crate::Result<()> {
+        Err(Error::Cache(
+            "Relocating the cache is not supported on mobile".to_string(),
+        ))
+    }
+
+    /// Writes every item to a JSON file
+    ///
+    /// Mobile platforms store every item in its own file, which aren't exported.
+    pub fn export(&self, _dest: PathBuf, _include_expired: bool) -> crate::Result<EmptyResponse> {
+        Err(Error::Cache(
+            "Exporting the cache is not supported on mobile".to_string(),
+        ))
+    }
+
+    /// Stores the items of a file written by `export`
+    ///
+    /// Exporting isn't supported on mobile, so neither is importing.
+    pub fn import(&self, _src: PathBuf, _overwrite: bool) -> crate::Result<usize> {
+        Err(Error::Cache(
+            "Importing the cache is not supported on mobile".to_string(),
+        ))
+    }
+
+    /// Removes every item stored with the tag
+    ///
+    /// The native side doesn't store tags, so this isn't supported on mobile.
+    pub fn invalidate_tag(&self, _tag: &str) -> crate::Result<usize> {
+        Err(Error::Cache(
+            "Invalidating tags is not supported on mobile".to_string(),
+        ))
+    }
+
+    /// Counts a call against a fixed-window rate limit whose counter is stored under `key`
+    pub fn rate_limit(
+        &self,
+        key: &str,
+        max: u32,
+        window_secs: u64,
+    ) -> crate::Result<RateLimitResult> {
+        check_key(key, self.2)?;
+
+        let request = RateLimitRequest {
+            key: key.to_string(),
+            max,
+            window_secs,
+        };
+        self.0
+            .run_mobile_plugin::<RateLimitResult>("rateLimit", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
     /// Clears all values from the cache
     pub fn clear(&self) -> crate::Result<EmptyResponse> {
         self.0
@@ -159,10 +630,164 @@ impl<R: Runtime> Cache<R> {
             .map_err(|e| crate::Error::PluginInvoke(e))
     }
 
+    /// Clears all values stored more than `age_secs` seconds ago
+    pub fn clear_older_than(&self, age_secs: u64) -> crate::Result<usize> {
+        let request = ClearOlderThanRequest { age_secs };
+        self.0
+            .run_mobile_plugin::<usize>("clearOlderThan", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Remove every item whose key starts with the prefix, returning how many were removed
+    pub fn clear_prefix(&self, prefix: &str) -> crate::Result<usize> {
+        let request = ClearPrefixRequest {
+            prefix: prefix.to_string(),
+        };
+        self.0
+            .run_mobile_plugin::<usize>("clearPrefix", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> crate::Result<CacheStats> {
         self.0
             .run_mobile_plugin::<CacheStats>("stats", ())
             .map_err(|e| crate::Error::PluginInvoke(e))
     }
+
+    /// Change how often the background cleanup runs, in seconds
+    ///
+    /// The native side removes expired items when they're read instead, so this does nothing on
+    /// mobile.
+    pub fn set_cleanup_interval(&self, _secs: u64) {}
+
+    /// Run a cleanup cycle right away, returning how many expired items were removed
+    ///
+    /// The native side removes expired items when they're read, so there's no cycle to run and
+    /// this always returns 0 on mobile.
+    pub fn cleanup(&self) -> crate::Result<usize> {
+        Ok(0)
+    }
+
+    /// Run a cleanup cycle right away, returning how many expired items were removed and how
+    /// many active items are left
+    pub fn prune(&self) -> crate::Result<PruneResult> {
+        let removed = self.cleanup()?;
+        Ok(PruneResult {
+            removed,
+            remaining: self.active_size()?,
+        })
+    }
+
+    /// Get the total number of items in the cache
+    pub fn size(&self) -> crate::Result<usize> {
+        self.0
+            .run_mobile_plugin::<usize>("size", ())
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Get the number of non-expired items in the cache
+    pub fn active_size(&self) -> crate::Result<usize> {
+        self.0
+            .run_mobile_plugin::<usize>("activeSize", ())
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Get a token that increases whenever the cache is written
+    pub fn change_token(&self) -> crate::Result<u64> {
+        self.0
+            .run_mobile_plugin::<ChangeToken>("changeToken", ())
+            .map(|change| change.token)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Get the time of the last write to the cache in seconds since the Unix epoch
+    pub fn last_modified(&self) -> crate::Result<Option<u64>> {
+        self.0
+            .run_mobile_plugin::<ChangeToken>("changeToken", ())
+            .map(|change| change.last_modified)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Get the directory holding the cache files, and whether it has been created yet
+    pub fn cache_path(&self) -> crate::Result<CachePath> {
+        self.0
+            .run_mobile_plugin::<CachePath>("cachePath", ())
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Reset the compression metrics
+    pub fn reset_stats(&self) -> crate::Result<EmptyResponse> {
+        self.0
+            .run_mobile_plugin::<EmptyResponse>("resetStats", ())
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Get cache statistics for keys starting with a prefix
+    pub fn stats_prefix(&self, prefix: &str) -> crate::Result<CacheStats> {
+        let request = StatsPrefixRequest {
+            prefix: prefix.to_string(),
+        };
+        self.0
+            .run_mobile_plugin::<CacheStats>("statsPrefix", request)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Get the keys of all live items in lexicographic order, optionally only those under a prefix
+    pub fn keys(&self, prefix: Option<&str>) -> crate::Result<Vec<String>> {
+        let request = KeysRequest {
+            prefix: prefix.map(str::to_string),
+        };
+        self.0
+            .run_mobile_plugin::<KeysPage>("keys", request)
+            .map(|page| page.keys)
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// List the keys of all live items matching a glob pattern, in lexicographic order
+    pub fn keys_matching(&self, pattern: &str) -> crate::Result<Vec<String>> {
+        Ok(self
+            .keys(None)?
+            .into_iter()
+            .filter(|key| glob::matches(pattern, key))
+            .collect())
+    }
+
+    /// Find the first key, in lexicographic order, of a live item holding `value`
+    ///
+    /// The native side can't search values, so every item is read back one at a time.
+    pub fn find_key_by_value(&self, value: &serde_json::Value) -> crate::Result<Option<String>> {
+        for key in self.keys(None)? {
+            if self.get(&key)?.as_ref() == Some(value) {
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get a page of keys in lexicographic order, along with the total number of keys
+    pub fn keys_page(
+        &self,
+        offset: usize,
+        limit: usize,
+        include_expired: bool,
+    ) -> crate::Result<(Vec<String>, usize)> {
+        let request = KeysPageRequest {
+            offset,
+            limit,
+            include_expired,
+        };
+        self.0
+            .run_mobile_plugin::<KeysPage>("keysPage", request)
+            .map(|page| (page.keys, page.total))
+            .map_err(|e| crate::Error::PluginInvoke(e))
+    }
+
+    /// Makes sure every write so far has reached the disk
+    ///
+    /// Mobile platforms write each item to its own file as soon as it's stored, so there is
+    /// nothing left to flush.
+    pub fn flush(&self) -> crate::Result<EmptyResponse> {
+        Ok(EmptyResponse {})
+    }
 }
@@ -1,26 +1,174 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use tauri::path::BaseDirectory;
+
+use crate::Error;
 
 // The size threshold in bytes after which compression will be applied
 pub const COMPRESSION_THRESHOLD: usize = 1024; // 1KB
 
+/// Event emitted when a value older than its soft TTL is read, if `emit_events` is enabled
+/// (desktop only)
+pub const STALE_EVENT: &str = "cache://stale";
+
+/// Event emitted periodically while a bulk operation runs (desktop only)
+pub const PROGRESS_EVENT: &str = "cache://progress";
+
+/// Event emitted for each item removed by the background cleanup, if `emit_events` is enabled
+/// (desktop only)
+pub const EXPIRED_EVENT: &str = "cache://expired";
+
+/// Event emitted once per cleanup cycle with all items it removed, if `emit_events` is enabled
+/// (desktop only)
+pub const EXPIRED_BATCH_EVENT: &str = "cache://expired_batch";
+
+/// Event emitted when an item is stored, if `emit_events` is enabled (desktop only)
+pub const SET_EVENT: &str = "cache://set";
+
+/// Event emitted when an item is removed, if `emit_events` is enabled (desktop only)
+pub const REMOVED_EVENT: &str = "cache://removed";
+
 /// Supported compression methods
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum CompressionMethod {
     /// Zlib compression (default, balanced speed/ratio)
+    #[default]
     Zlib,
     /// LZMA2 compression (better compression ratio, slower)
     Lzma2,
+    /// Gzip compression, stored as plain gzip data that `gunzip` can read (desktop only, mobile
+    /// platforms use Zlib instead)
+    Gzip,
+    /// Zstandard compression, faster than Zlib at a similar or better ratio (desktop only with the
+    /// `zstd` feature, Zlib is used instead otherwise)
+    Zstd,
 }
 
-impl Default for CompressionMethod {
-    fn default() -> Self {
-        Self::Zlib
+impl CompressionMethod {
+    /// Highest compression level the method accepts
+    pub fn max_level(&self) -> u32 {
+        match self {
+            CompressionMethod::Zstd => 22,
+            _ => 9,
+        }
+    }
+
+    /// Describe why `level` can't be used with the method, if it can't
+    pub(crate) fn level_error(&self, level: u32) -> Option<String> {
+        (level > self.max_level()).then(|| {
+            format!(
+                "compression level for {:?} must be between 0 and {}, got {}",
+                self,
+                self.max_level(),
+                level
+            )
+        })
+    }
+}
+
+/// Strategies for choosing which items to evict once the cache is full
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EvictionPolicy {
+    /// Evict the least recently read item (default)
+    #[default]
+    Lru,
+    /// Evict the least frequently read item
+    Lfu,
+    /// Evict the oldest item
+    Fifo,
+}
+
+/// How the background cleanup reports the expired items it removes
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExpiryEvents {
+    /// Emit an [`EXPIRED_EVENT`] for every removed item
+    PerKey,
+    /// Emit one [`EXPIRED_BATCH_EVENT`] per cleanup cycle (default)
+    #[default]
+    Batched,
+}
+
+/// What `get` does with a stored value that can't be decompressed or decoded
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DecodeErrorPolicy {
+    /// Return the error to the caller (default)
+    #[default]
+    Error,
+    /// Report the item as missing, leaving it in place
+    TreatAsMiss,
+    /// Remove the item and report it as missing
+    EvictAndMiss,
+}
+
+/// A 256-bit key for encrypting cached values at rest
+///
+/// Its `Debug` output is redacted so the key doesn't end up in logs.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct EncryptionKey(pub [u8; 32]);
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+impl From<[u8; 32]> for EncryptionKey {
+    fn from(key: [u8; 32]) -> Self {
+        Self(key)
     }
 }
 
+/// How writes are saved to the cache file
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Durability {
+    /// Rewrite the whole cache file on every write (default)
+    #[default]
+    Rewrite,
+    /// Append `set` and `remove` to a log next to the cache file, which is folded into the file
+    /// once it has grown larger than it
+    WriteAheadLog,
+    /// Keep the items in memory and write them to the cache file in the background, at most once
+    /// per cleanup interval and on `flush`; removals are written right away
+    WriteBehind,
+}
+
+/// How the items are encoded in the cache file
+///
+/// Binary formats start with a short header naming the format, so a file written in one format is
+/// still read after switching to another, and rewritten in the new one on the next write.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageFormat {
+    /// Plain JSON (default)
+    #[default]
+    Json,
+    /// MessagePack, smaller and faster to parse than JSON
+    MessagePack,
+    /// CBOR, smaller and faster to parse than JSON
+    Cbor,
+}
+
+/// Where a value returned by the cache was found
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheSource {
+    /// The in-memory value cache
+    Memory,
+    /// The cache file on disk
+    Disk,
+    /// The configured backing store, after a cache miss
+    BackingStore,
+}
+
 /// Options for setting an item in the cache
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SetItemOptions {
     /// Time-to-live in seconds
@@ -29,6 +177,112 @@ pub struct SetItemOptions {
     pub compress: Option<bool>,
     /// Compression method to use (overrides default)
     pub compression_method: Option<CompressionMethod>,
+    /// Age in seconds after which the value is still returned but a stale event is emitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_ttl: Option<u64>,
+    /// Seconds past expiration during which `get_stale` still returns the value, flagged as
+    /// stale, and the cleanup keeps it (desktop only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_ttl: Option<u64>,
+    /// Arbitrary data stored along with the value and returned by `get_item`, e.g. an ETag; it's
+    /// never compressed and doesn't affect expiration (desktop only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// Tags to group the item by, so `invalidate_tag` can remove every item carrying one, e.g.
+    /// everything tagged `user-42` on logout (desktop only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+impl SetItemOptions {
+    /// Start building options, with every setting left at its default
+    pub fn builder() -> SetItemOptionsBuilder {
+        SetItemOptionsBuilder::default()
+    }
+}
+
+/// Builder for [`SetItemOptions`]
+#[derive(Debug, Clone, Default)]
+pub struct SetItemOptionsBuilder {
+    options: SetItemOptions,
+}
+
+impl SetItemOptionsBuilder {
+    /// Expire the item after this many seconds
+    pub fn ttl(mut self, ttl: u64) -> Self {
+        self.options.ttl = Some(ttl);
+        self
+    }
+
+    /// Compress the item or store it as-is, overriding the default
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.options.compress = Some(compress);
+        self
+    }
+
+    /// Compress the item with this method, overriding the default
+    pub fn compression_method(mut self, method: CompressionMethod) -> Self {
+        self.options.compression_method = Some(method);
+        self
+    }
+
+    /// Emit a stale event for reads after this many seconds
+    pub fn soft_ttl(mut self, soft_ttl: u64) -> Self {
+        self.options.soft_ttl = Some(soft_ttl);
+        self
+    }
+
+    /// Keep serving the item from `get_stale` for this many seconds after it expires
+    pub fn stale_ttl(mut self, stale_ttl: u64) -> Self {
+        self.options.stale_ttl = Some(stale_ttl);
+        self
+    }
+
+    /// Store this metadata along with the item
+    pub fn metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.options.metadata = Some(metadata);
+        self
+    }
+
+    /// Tag the item, so it's removed by `invalidate_tag` for any of the tags
+    pub fn tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.options.tags = Some(tags.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Finish building the options
+    pub fn build(self) -> SetItemOptions {
+        self.options
+    }
+}
+
+/// Outcome of a fallible operation as stored by `set_result`, serialized as `{ "ok": value }` or
+/// `{ "err": message }`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CachedResult<T> {
+    /// The operation succeeded with this value
+    Ok(T),
+    /// The operation failed with this message
+    Err(String),
+}
+
+impl<T> From<std::result::Result<T, String>> for CachedResult<T> {
+    fn from(result: std::result::Result<T, String>) -> Self {
+        match result {
+            Ok(value) => CachedResult::Ok(value),
+            Err(message) => CachedResult::Err(message),
+        }
+    }
+}
+
+impl<T> From<CachedResult<T>> for std::result::Result<T, String> {
+    fn from(result: CachedResult<T>) -> Self {
+        match result {
+            CachedResult::Ok(value) => Ok(value),
+            CachedResult::Err(message) => Err(message),
+        }
+    }
 }
 
 /// A cache item with its value and expiration time
@@ -41,6 +295,9 @@ pub struct CacheItem<T> {
     pub expires_at: Option<u64>,
     /// Whether the data is compressed
     pub is_compressed: Option<bool>,
+    /// Metadata stored along with the value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
 }
 
 /// Request to set an item in the cache
@@ -93,6 +350,26 @@ pub struct GetRequest {
     pub key: String,
 }
 
+/// Request to get several items from the cache at once
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetManyRequest {
+    /// The keys to retrieve
+    pub keys: Vec<String>,
+}
+
+/// An item to store with `set_many`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetManyItem {
+    /// The key to store the value under
+    pub key: String,
+    /// The value to store
+    pub value: serde_json::Value,
+    /// Options for storing this item
+    pub options: Option<SetItemOptions>,
+}
+
 /// Request to remove an item from the cache
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -109,6 +386,207 @@ pub struct HasRequest {
     pub key: String,
 }
 
+/// Request to get the time left until an item expires
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtlRequest {
+    /// The key to inspect
+    pub key: String,
+}
+
+/// Request to seed the cache from a JSON object
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadFromJsonRequest {
+    /// The items to store, keyed by cache key
+    pub map: serde_json::Map<String, serde_json::Value>,
+    /// TTL in seconds applied to every item
+    pub default_ttl: Option<u64>,
+    /// Whether existing items are kept instead of overwritten
+    pub skip_existing: bool,
+}
+
+/// Request to move an item to another key
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameRequest {
+    /// The key to move the item from
+    pub from: String,
+    /// The key to move the item to
+    pub to: String,
+    /// Whether an existing item under `to` is replaced
+    pub overwrite: bool,
+}
+
+/// Request to change when an item expires without rewriting its value
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExpiryRequest {
+    /// The key of the item
+    pub key: String,
+    /// New time-to-live in seconds from now, or `None` to keep the item until removed
+    pub ttl: Option<u64>,
+}
+
+/// Request to count a call against a rate limit
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitRequest {
+    /// The key holding the counter
+    pub key: String,
+    /// Maximum number of calls allowed per window
+    pub max: u32,
+    /// Length of the window in seconds
+    pub window_secs: u64,
+}
+
+/// Result of a conditional read
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum GetConditional {
+    /// The caller already holds the current version of the value
+    NotModified,
+    /// The value changed since the version held by the caller
+    Modified {
+        /// The current value
+        value: serde_json::Value,
+        /// The current version, to pass to the next conditional read
+        version: Option<u64>,
+    },
+}
+
+/// Time left until an item expires
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Ttl {
+    /// The item doesn't exist or has already expired
+    Missing,
+    /// The item exists and never expires
+    NoExpiry,
+    /// The item expires after this many seconds
+    ExpiresIn {
+        /// Seconds left until the item expires
+        secs: u64,
+    },
+}
+
+/// An item as written by `export`, with its value decoded
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedItem {
+    /// The key of the item
+    pub key: String,
+    /// The stored value, decompressed and decrypted
+    pub value: serde_json::Value,
+    /// Unix timestamp in seconds when this item expires (if applicable)
+    pub expires_at: Option<u64>,
+    /// Unix timestamp in seconds when this item was stored, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Metadata stored along with the value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// Tags the item was stored with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+/// Outcome of a rate-limited call
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitResult {
+    /// Whether the call is allowed
+    pub allowed: bool,
+    /// Number of calls still allowed in the current window
+    pub remaining: u32,
+    /// Time the current window ends in seconds since the Unix epoch
+    pub reset_at: u64,
+}
+
+/// Request to clear the items under a key prefix
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearPrefixRequest {
+    /// The key prefix of the items to remove
+    pub prefix: String,
+}
+
+/// Request to clear items older than a given age
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearOlderThanRequest {
+    /// Minimum age in seconds of the items to remove
+    pub age_secs: u64,
+}
+
+/// Request to get statistics for keys with a given prefix
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsPrefixRequest {
+    /// The key prefix to restrict the statistics to
+    pub prefix: String,
+}
+
+/// Request to list the keys in the cache
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeysRequest {
+    /// Only list keys starting with this prefix
+    pub prefix: Option<String>,
+}
+
+/// Request to get a page of keys
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeysPageRequest {
+    /// Number of keys to skip
+    pub offset: usize,
+    /// Maximum number of keys to return
+    pub limit: usize,
+    /// Whether expired items that haven't been cleaned up yet are included
+    pub include_expired: bool,
+}
+
+/// A value along with where it was found
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcedValue {
+    /// The stored value
+    pub value: serde_json::Value,
+    /// Where the value was found
+    pub source: CacheSource,
+}
+
+/// A value along with whether it has expired and is only served within its stale window
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleValue {
+    /// The stored value
+    pub value: serde_json::Value,
+    /// Whether the value has expired and should be refreshed
+    pub stale: bool,
+}
+
+/// Outcome of removing the expired items
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneResult {
+    /// Number of expired items removed
+    pub removed: usize,
+    /// Number of active items left
+    pub remaining: usize,
+}
+
+/// A page of keys in lexicographic order
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeysPage {
+    /// The keys in this page
+    pub keys: Vec<String>,
+    /// Total number of keys across all pages
+    pub total: usize,
+}
+
 /// Enhanced statistics about the cache
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -117,6 +595,122 @@ pub struct CacheStats {
     pub total_size: usize,
     /// Number of active (non-expired) items in the cache
     pub active_size: usize,
+    /// Compressed size divided by original size across all compressed writes since the last
+    /// reset (0 if nothing was compressed)
+    #[serde(default)]
+    pub compression_ratio: f64,
+    /// Bytes saved by compression since the last reset
+    #[serde(default)]
+    pub bytes_saved: u64,
+    /// Number of items stored compressed
+    #[serde(default)]
+    pub compressed_count: usize,
+    /// Number of items stored as plain JSON
+    #[serde(default)]
+    pub uncompressed_count: usize,
+    /// Bytes taken up by the stored values of compressed items
+    #[serde(default)]
+    pub compressed_bytes: u64,
+    /// Bytes taken up by the stored values of uncompressed items
+    #[serde(default)]
+    pub uncompressed_bytes: u64,
+    /// Bytes the stored values of all items take up once decompressed, so
+    /// `compressed_bytes + uncompressed_bytes` against this shows how much compression saves
+    #[serde(default)]
+    pub decompressed_bytes: u64,
+    /// Size of the cache on disk, including the write-ahead log
+    ///
+    /// On mobile this is the size of the counted item files. Desktop reports 0 for `stats_prefix`,
+    /// as the items share one file.
+    #[serde(default)]
+    pub total_bytes_on_disk: u64,
+    /// Reads that found a live item since the last reset (desktop only)
+    #[serde(default)]
+    pub hits: u64,
+    /// Reads that found no live item since the last reset, including expired ones (desktop only)
+    #[serde(default)]
+    pub misses: u64,
+    /// Misses where the item was still stored but had expired (desktop only)
+    #[serde(default)]
+    pub expirations: u64,
+}
+
+/// A token identifying the current state of the cache
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeToken {
+    /// Increases whenever the cache is written
+    pub token: u64,
+    /// Time of the last write in seconds since the Unix epoch, if known
+    pub last_modified: Option<u64>,
+}
+
+/// Where the cache is stored on disk
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePath {
+    /// Directory containing the cache
+    pub dir: String,
+    /// The cache file, or on mobile, where every item is a separate file, the cache directory
+    pub file: String,
+    /// Whether the cache exists on disk yet
+    pub exists: bool,
+}
+
+/// Payload of the events emitted by the cache
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEventPayload {
+    /// The key the event refers to
+    pub key: String,
+}
+
+/// Why an item was stored or removed, sent with [`SET_EVENT`] and [`REMOVED_EVENT`]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeReason {
+    /// The item's value was stored or replaced
+    Set,
+    /// Only the item's expiration time changed, with `expire` or `persist`
+    ExpiryChanged,
+    /// The item was moved to another key with `rename`
+    Renamed,
+    /// The item was removed with `remove`, `removeMany` or `transform`
+    Removed,
+    /// The item was removed with `clear`, `clearPrefix`, `clearOlderThan` or `invalidateTag`
+    Cleared,
+    /// The item was evicted to stay within `max_entries` or `max_bytes`
+    Evicted,
+}
+
+/// Payload of the events emitted when an item is stored or removed
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEventPayload {
+    /// The key the event refers to
+    pub key: String,
+    /// Why the item changed
+    pub reason: ChangeReason,
+}
+
+/// Payload of the batched expiry events
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiredBatchPayload {
+    /// Keys of all items removed in the cleanup cycle
+    pub keys: Vec<String>,
+}
+
+/// Payload of the progress events emitted during bulk operations
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressPayload {
+    /// Number of items processed so far
+    pub processed: usize,
+    /// Total number of items the operation processes
+    pub total: usize,
+    /// Name of the operation, e.g. `transform` or `loadFromJson`
+    pub operation: String,
 }
 
 /// Response containing a boolean value
@@ -160,11 +754,15 @@ impl Default for CompressionConfig {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CacheConfig {
+    /// Base directory the cache directory is resolved against (the app's cache directory if not
+    /// set); e.g. `BaseDirectory::AppData` to include the cache in OS backups
+    pub base_dir: Option<BaseDirectory>,
     /// Custom directory path for storing cache files
     pub cache_dir: Option<String>,
-    /// Custom file name for the cache file
+    /// Custom file name for the cache file; `{version}`, `{os}` and `{user}` are expanded to the
+    /// app version, operating system and current user name
     pub cache_file_name: Option<String>,
-    /// Cleanup interval in seconds
+    /// Cleanup interval in seconds; 0 disables the background cleanup thread on desktop
     pub cleanup_interval: Option<u64>,
     /// Default compression setting for new items
     pub default_compression: Option<bool>,
@@ -174,11 +772,81 @@ pub struct CacheConfig {
     pub compression_threshold: Option<usize>,
     /// Compression method to use (zlib or lzma2)
     pub compression_method: Option<CompressionMethod>,
+    /// Whether to fsync the cache file after every write.
+    ///
+    /// Guarantees that a completed write survives a crash or power loss, at the
+    /// cost of waiting for the disk on every write (often several milliseconds).
+    pub fsync_on_write: Option<bool>,
+    /// Whether reading a compressed item re-stores it when the compression settings changed
+    ///
+    /// Lets entries migrate lazily to a new compression method or level. Items already
+    /// stored under the current settings are never rewritten, so reads stay reads.
+    pub recompress_on_read: Option<bool>,
+    /// Maximum number of items in the cache; storing more evicts items chosen by the eviction
+    /// policy (unbounded if not set)
+    pub max_entries: Option<usize>,
+    /// Maximum total size in bytes of the stored keys and values, counting compressed values at
+    /// their compressed size; storing more evicts items chosen by the eviction policy (desktop
+    /// only, unbounded if not set)
+    pub max_bytes: Option<usize>,
+    /// Maximum size in bytes of a single value's JSON, before compression; larger values are
+    /// rejected by `set` and the other writing calls (desktop only, no limit if not set)
+    pub max_value_bytes: Option<usize>,
+    /// Maximum length of a key in bytes; longer keys are rejected like empty ones (no limit if
+    /// not set)
+    pub max_key_len: Option<usize>,
+    /// How items are chosen for eviction once `max_entries` or `max_bytes` is reached
+    pub eviction_policy: Option<EvictionPolicy>,
+    /// How the background cleanup reports expired items it removes
+    pub expiry_events: Option<ExpiryEvents>,
+    /// Emit [`SET_EVENT`] and [`REMOVED_EVENT`] whenever an item is stored or removed, so the
+    /// frontend can react without polling (desktop only)
+    pub emit_events: Option<bool>,
+    /// How writes are saved to the cache file (desktop only)
+    pub durability: Option<Durability>,
+    /// Whether items are saved to the cache file; if false, the cache lives only in memory, never
+    /// touches the disk and starts empty on every launch (desktop only)
+    pub persist: Option<bool>,
+    /// How the items are encoded in the cache file; the write-ahead log is always JSON (desktop
+    /// only)
+    pub storage_format: Option<StorageFormat>,
+    /// TTL in seconds for items stored without one, including by `set_all` and `load_from_json`;
+    /// an explicit `ttl: Some(0)` stores an item without expiration (desktop only, no expiration
+    /// if not set)
+    pub default_ttl: Option<u64>,
+    /// Longest TTL in seconds an item can get; longer TTLs are shortened to it, and items stored
+    /// without one get it too (desktop only, no limit if not set)
+    pub max_ttl: Option<u64>,
+    /// Maximum number of seconds randomly added to each TTL, so items stored together don't all
+    /// expire at once (desktop only, no jitter if not set)
+    pub ttl_jitter_secs: Option<u64>,
+    /// What `get` does with an item that can't be decoded, e.g. after corruption (desktop only)
+    pub on_decode_error: Option<DecodeErrorPolicy>,
+    /// Skip rewriting the cache file when `set` stores an entry identical to the existing one
+    pub skip_unchanged_writes: Option<bool>,
+    /// Seconds after which values that haven't been read are dropped from memory, keeping only
+    /// the compact copy on disk (kept until expiration if not set)
+    pub memory_idle_secs: Option<u64>,
+    /// Seconds past expiration during which `get` keeps serving a value still held in memory,
+    /// emitting a stale event (disabled if not set)
+    pub stale_grace_secs: Option<u64>,
+    /// Key prefixes the frontend can read but not modify; `set`, `remove` and other writing
+    /// commands fail with `Error::ReadOnly` on matching keys (the Rust API is unaffected)
+    pub readonly_prefixes: Option<Vec<String>>,
+    /// JSON schemas keyed by key prefix; `set` rejects values under a matching prefix that don't
+    /// conform with `Error::SchemaViolation`
+    pub schemas: Option<HashMap<String, serde_json::Value>>,
+    /// TTL in seconds for values loaded from the backing store (`default_ttl` if not set)
+    pub backing_store_ttl: Option<u64>,
+    /// Key to encrypt values at rest with AES-256-GCM; items stored without it stay readable
+    /// and are encrypted when next written (desktop only, values are stored as is if not set)
+    pub encryption_key: Option<EncryptionKey>,
 }
 
 impl Default for CacheConfig {
     fn default() -> Self {
         Self {
+            base_dir: None, // Default the app's cache directory
             cache_dir: None,
             cache_file_name: None,
             cleanup_interval: Some(60),        // Default 60 seconds
@@ -186,6 +854,120 @@ impl Default for CacheConfig {
             compression_level: Some(6),        // Default medium compression level
             compression_threshold: Some(1024), // Default 1KB threshold
             compression_method: Some(CompressionMethod::Zlib), // Default to Zlib
+            fsync_on_write: Some(false),       // Default no fsync for performance
+            recompress_on_read: Some(false),   // Default keep entries as stored
+            max_entries: None,                 // Default no size limit
+            max_bytes: None,                   // Default no byte limit
+            max_value_bytes: None,             // Default no limit per value
+            max_key_len: None,                 // Default no limit on key length
+            eviction_policy: Some(EvictionPolicy::Lru), // Default evict least recently read items
+            expiry_events: Some(ExpiryEvents::Batched), // Default one event per cleanup cycle
+            emit_events: Some(false),          // Default don't emit events for every change
+            durability: Some(Durability::Rewrite), // Default rewrite the file on every write
+            persist: Some(true),               // Default save items to the cache file
+            storage_format: Some(StorageFormat::Json), // Default plain JSON cache file
+            default_ttl: None,                 // Default keep items without a TTL forever
+            max_ttl: None,                     // Default allow any TTL
+            ttl_jitter_secs: None,             // Default expire exactly at the TTL
+            on_decode_error: Some(DecodeErrorPolicy::Error), // Default surface corrupt items
+            skip_unchanged_writes: Some(true), // Default avoid redundant writes
+            memory_idle_secs: None,            // Default keep values in memory until they expire
+            stale_grace_secs: None,            // Default evict as soon as items expire
+            readonly_prefixes: None,           // Default let the frontend write every key
+            schemas: None,                     // Default accept any value
+            backing_store_ttl: None,           // Default keep loaded values until removed
+            encryption_key: None,              // Default store values unencrypted
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Check the configuration for values that can't work, so they fail at startup rather than
+    /// misbehave later
+    ///
+    /// A `cleanup_interval` of 0 is accepted: it no longer busy-loops the cleanup thread but
+    /// disables it, which is how the cleanup is turned off.
+    pub fn validate(&self) -> crate::Result<()> {
+        let method = self.compression_method.clone().unwrap_or_default();
+        if let Some(message) = self
+            .compression_level
+            .and_then(|level| method.level_error(level))
+        {
+            return Err(Error::InitError(message));
         }
+        if self.max_key_len == Some(0) {
+            return Err(Error::InitError(
+                "max_key_len must be at least 1".to_string(),
+            ));
+        }
+        if self
+            .cache_file_name
+            .as_deref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err(Error::InitError(
+                "cache_file_name must not be empty".to_string(),
+            ));
+        }
+        if self.max_ttl == Some(0) {
+            return Err(Error::InitError(
+                "max_ttl must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_checks_the_level_against_the_method() {
+        let config = |method, level| CacheConfig {
+            compression_method: Some(method),
+            compression_level: Some(level),
+            ..Default::default()
+        };
+
+        assert!(config(CompressionMethod::Zlib, 9).validate().is_ok());
+        assert!(matches!(
+            config(CompressionMethod::Zlib, 10).validate(),
+            Err(Error::InitError(_))
+        ));
+        assert!(config(CompressionMethod::Zstd, 22).validate().is_ok());
+        assert!(matches!(
+            config(CompressionMethod::Zstd, 23).validate(),
+            Err(Error::InitError(_))
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_unusable_values() {
+        for config in [
+            CacheConfig {
+                max_key_len: Some(0),
+                ..Default::default()
+            },
+            CacheConfig {
+                cache_file_name: Some(" ".into()),
+                ..Default::default()
+            },
+            CacheConfig {
+                max_ttl: Some(0),
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(config.validate(), Err(Error::InitError(_))));
+        }
+    }
+
+    #[test]
+    fn validate_accepts_a_zero_cleanup_interval() {
+        let config = CacheConfig {
+            cleanup_interval: Some(0),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
     }
 }
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use tauri::Runtime;
+
+use crate::models::*;
+use crate::Cache;
+
+/// Separates a namespace's name from the keys within it
+const SEPARATOR: char = ':';
+
+/// A view of the cache whose keys all live under a namespace, e.g. one per subsystem
+///
+/// Keys are stored as `<name>:<key>`, so two namespaces can use the same key without colliding.
+/// The cache file and its locks are shared with the rest of the cache.
+pub struct NamespacedCache<'a, R: Runtime> {
+    cache: &'a Cache<R>,
+    prefix: String,
+}
+
+impl<R: Runtime> Cache<R> {
+    /// Get a view of the cache that keeps its keys under `name`
+    pub fn namespace(&self, name: &str) -> NamespacedCache<'_, R> {
+        NamespacedCache {
+            cache: self,
+            prefix: format!("{}{}", name, SEPARATOR),
+        }
+    }
+}
+
+impl<R: Runtime> NamespacedCache<'_, R> {
+    /// The key an item of this namespace is stored under
+    fn full_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// Sets a value in the namespace with optional TTL
+    pub fn set<T: Serialize + serde::de::DeserializeOwned + std::fmt::Debug>(
+        &self,
+        key: &str,
+        value: T,
+        options: Option<SetItemOptions>,
+    ) -> crate::Result<EmptyResponse> {
+        self.cache.set(self.full_key(key), value, options)
+    }
+
+    /// Gets a value from the namespace
+    pub fn get(&self, key: &str) -> crate::Result<Option<serde_json::Value>> {
+        self.cache.get(&self.full_key(key))
+    }
+
+    /// Checks if a key exists in the namespace and hasn't expired
+    pub fn has(&self, key: &str) -> crate::Result<BooleanResponse> {
+        self.cache.has(&self.full_key(key))
+    }
+
+    /// Removes a value from the namespace
+    pub fn remove(&self, key: &str) -> crate::Result<EmptyResponse> {
+        self.cache.remove(&self.full_key(key))
+    }
+
+    /// Removes every item in the namespace, leaving the rest of the cache alone, and returns how
+    /// many were removed
+    pub fn clear(&self) -> crate::Result<usize> {
+        self.cache.clear_prefix(&self.prefix)
+    }
+
+    /// Lists the keys in the namespace, without the namespace's name
+    pub fn keys(&self) -> crate::Result<Vec<String>> {
+        Ok(self
+            .cache
+            .keys(Some(&self.prefix))?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+}
+
+#[cfg(all(test, desktop))]
+mod tests {
+    use serde_json::json;
+
+    use crate::test_support::test_app;
+    use crate::{CacheConfig, CacheExt};
+
+    #[test]
+    fn namespaces_with_the_same_key_do_not_collide() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let user = app.cache().namespace("user");
+        let users = app.cache().namespace("users");
+
+        user.set("id", json!(1), None).unwrap();
+        users.set("id", json!(2), None).unwrap();
+        app.cache().set("id".into(), json!(3), None).unwrap();
+
+        assert_eq!(user.get("id").unwrap(), Some(json!(1)));
+        assert_eq!(users.get("id").unwrap(), Some(json!(2)));
+        assert_eq!(app.cache().get("user:id").unwrap(), Some(json!(1)));
+        assert_eq!(user.keys().unwrap(), ["id"]);
+
+        user.remove("id").unwrap();
+        assert!(!user.has("id").unwrap().value);
+        assert!(users.has("id").unwrap().value);
+        assert!(app.cache().has("id").unwrap().value);
+    }
+
+    #[test]
+    fn clearing_a_namespace_leaves_the_others_alone() {
+        let (app, _dir) = test_app(CacheConfig::default());
+        let user = app.cache().namespace("user");
+        let users = app.cache().namespace("users");
+
+        user.set("a", json!(1), None).unwrap();
+        user.set("b", json!(2), None).unwrap();
+        users.set("a", json!(3), None).unwrap();
+        app.cache().set("user".into(), json!(4), None).unwrap();
+
+        assert_eq!(user.clear().unwrap(), 2);
+        assert!(user.keys().unwrap().is_empty());
+        assert_eq!(users.keys().unwrap(), ["a"]);
+        assert_eq!(app.cache().keys(None).unwrap(), ["user", "users:a"]);
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use jsonschema::Validator;
+
+use crate::Error;
+
+/// JSON schemas that values stored under a key prefix must conform to
+#[derive(Default)]
+pub(crate) struct SchemaRegistry {
+    validators: Vec<(String, Validator)>,
+}
+
+impl SchemaRegistry {
+    /// Compile the schemas configured for each key prefix
+    pub(crate) fn new(schemas: Option<&HashMap<String, serde_json::Value>>) -> crate::Result<Self> {
+        let mut validators = Vec::new();
+
+        for (prefix, schema) in schemas.into_iter().flatten() {
+            let validator = jsonschema::validator_for(schema).map_err(|e| {
+                Error::Cache(format!("Invalid schema for prefix {}: {}", prefix, e))
+            })?;
+            validators.push((prefix.clone(), validator));
+        }
+
+        Ok(Self { validators })
+    }
+
+    /// Whether any schema applies to the key
+    #[cfg(mobile)]
+    pub(crate) fn applies_to(&self, key: &str) -> bool {
+        self.validators
+            .iter()
+            .any(|(prefix, _)| key.starts_with(prefix.as_str()))
+    }
+
+    /// Check the value against every schema whose prefix matches the key
+    pub(crate) fn validate(&self, key: &str, value: &serde_json::Value) -> crate::Result<()> {
+        for (prefix, validator) in &self.validators {
+            if !key.starts_with(prefix.as_str()) {
+                continue;
+            }
+
+            if let Err(e) = validator.validate(value) {
+                return Err(Error::SchemaViolation(format!(
+                    "{} (at \"{}\" in {})",
+                    e, e.instance_path, key
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}